@@ -0,0 +1,31 @@
+#!/usr/bin/env bash
+# Rebuild cities.tsv from the GeoNames dumps.
+#
+# Usage: ./build-cities.sh [cities15000|cities5000|cities1000]
+#
+# The bundled file keeps only what reverse geocoding needs: the city name,
+# its country code and name, and its coordinates. Larger dumps give closer
+# matches at the cost of a bigger app.
+set -euo pipefail
+
+DUMP="${1:-cities15000}"
+BASE_URL="https://download.geonames.org/export/dump"
+WORK_DIR="$(mktemp -d)"
+trap 'rm -rf "$WORK_DIR"' EXIT
+
+curl -fsSL "$BASE_URL/$DUMP.zip" -o "$WORK_DIR/$DUMP.zip"
+curl -fsSL "$BASE_URL/countryInfo.txt" -o "$WORK_DIR/countryInfo.txt"
+unzip -q "$WORK_DIR/$DUMP.zip" -d "$WORK_DIR"
+
+{
+    echo "# GeoNames $DUMP (https://www.geonames.org), licensed under CC BY 4.0"
+    echo "# name	country_code	country	latitude	longitude"
+    # countryInfo.txt: ISO code in column 1, country name in column 5
+    # cities: name in column 2, latitude 5, longitude 6, country code 9
+    awk -F '\t' '
+        FNR == NR { if ($0 !~ /^#/) country[$1] = $5; next }
+        { printf "%s\t%s\t%s\t%.4f\t%.4f\n", $2, $9, country[$9], $5, $6 }
+    ' "$WORK_DIR/countryInfo.txt" "$WORK_DIR/$DUMP.txt" | sort
+} > "$(dirname "$0")/cities.tsv"
+
+echo "Wrote $(grep -vc '^#' "$(dirname "$0")/cities.tsv") cities"
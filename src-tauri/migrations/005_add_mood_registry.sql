@@ -0,0 +1,73 @@
+-- Migration 005: Structured mood registry
+-- Moods get valence/energy coordinates so they can be charted, and entries
+-- get a numeric intensity for how strongly the mood was felt
+
+-- Create moods table (id is the canonical slug stored in journal_entries.mood)
+CREATE TABLE IF NOT EXISTS moods (
+    id TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    emoji TEXT,
+    valence REAL NOT NULL CHECK (valence BETWEEN -1.0 AND 1.0), -- unpleasant .. pleasant
+    energy REAL NOT NULL CHECK (energy BETWEEN -1.0 AND 1.0), -- calm .. activated
+    is_custom INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now'))
+);
+
+-- Create mood_aliases table mapping normalized spellings and emoji to a mood
+CREATE TABLE IF NOT EXISTS mood_aliases (
+    alias TEXT PRIMARY KEY,
+    mood_id TEXT NOT NULL,
+    FOREIGN KEY (mood_id) REFERENCES moods(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_mood_aliases_mood_id ON mood_aliases(mood_id);
+
+-- Built-in moods
+INSERT OR IGNORE INTO moods (id, label, emoji, valence, energy) VALUES
+    ('happy', 'Happy', '😊', 0.8, 0.4),
+    ('excited', 'Excited', '🤩', 0.7, 0.9),
+    ('grateful', 'Grateful', '🙏', 0.8, 0.1),
+    ('content', 'Content', '🙂', 0.6, -0.3),
+    ('calm', 'Calm', '😌', 0.5, -0.6),
+    ('neutral', 'Neutral', '😐', 0.0, 0.0),
+    ('tired', 'Tired', '😴', -0.2, -0.8),
+    ('sad', 'Sad', '😢', -0.7, -0.4),
+    ('anxious', 'Anxious', '😰', -0.6, 0.7),
+    ('stressed', 'Stressed', '😫', -0.6, 0.6),
+    ('frustrated', 'Frustrated', '😤', -0.6, 0.5),
+    ('angry', 'Angry', '😠', -0.8, 0.8);
+
+INSERT OR IGNORE INTO mood_aliases (alias, mood_id) VALUES
+    ('😊', 'happy'),
+    ('😀', 'happy'),
+    ('😄', 'happy'),
+    ('joyful', 'happy'),
+    ('positive', 'happy'),
+    ('🤩', 'excited'),
+    ('🙏', 'grateful'),
+    ('thankful', 'grateful'),
+    ('🙂', 'content'),
+    ('😌', 'calm'),
+    ('relaxed', 'calm'),
+    ('peaceful', 'calm'),
+    ('😐', 'neutral'),
+    ('okay', 'neutral'),
+    ('ok', 'neutral'),
+    ('😴', 'tired'),
+    ('exhausted', 'tired'),
+    ('😢', 'sad'),
+    ('😞', 'sad'),
+    ('down', 'sad'),
+    ('negative', 'sad'),
+    ('😰', 'anxious'),
+    ('worried', 'anxious'),
+    ('nervous', 'anxious'),
+    ('😫', 'stressed'),
+    ('overwhelmed', 'stressed'),
+    ('😤', 'frustrated'),
+    ('annoyed', 'frustrated'),
+    ('😠', 'angry'),
+    ('mad', 'angry');
+
+-- Add intensity to journal entries
+ALTER TABLE journal_entries ADD COLUMN mood_intensity REAL;
@@ -0,0 +1,30 @@
+-- Migration 006: Keep the full-text search index in sync
+-- journal_entries_fts is an external content table, so rows must be removed
+-- with the special 'delete' command and their old values. A plain DELETE
+-- reads the values back from journal_entries, which has already changed by
+-- the time an AFTER trigger runs, and leaves stale tokens in the index.
+-- The update trigger only fires for the indexed columns: the updated_at
+-- trigger updates the row again from inside the same statement, and a
+-- search trigger nested in it would delete tokens that were never indexed.
+
+DROP TRIGGER IF EXISTS journal_entries_fts_delete;
+DROP TRIGGER IF EXISTS journal_entries_fts_update;
+
+CREATE TRIGGER IF NOT EXISTS journal_entries_fts_delete
+    AFTER DELETE ON journal_entries
+    BEGIN
+        INSERT INTO journal_entries_fts(journal_entries_fts, rowid, title, content, tags)
+        VALUES ('delete', OLD.rowid, OLD.title, OLD.content, OLD.tags);
+    END;
+
+CREATE TRIGGER IF NOT EXISTS journal_entries_fts_update
+    AFTER UPDATE OF title, content, tags ON journal_entries
+    BEGIN
+        INSERT INTO journal_entries_fts(journal_entries_fts, rowid, title, content, tags)
+        VALUES ('delete', OLD.rowid, OLD.title, OLD.content, OLD.tags);
+        INSERT INTO journal_entries_fts(rowid, title, content, tags)
+        VALUES (NEW.rowid, NEW.title, NEW.content, NEW.tags);
+    END;
+
+-- Rebuild the index from scratch to drop anything the old triggers left behind
+INSERT INTO journal_entries_fts(journal_entries_fts) VALUES ('rebuild');
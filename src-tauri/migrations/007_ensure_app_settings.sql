@@ -0,0 +1,24 @@
+-- Migration 007: Make sure app_settings exists
+-- Databases created by the old embedded schema skip 001, which is where
+-- app_settings is created, but settings, goals and analytics all rely on it
+
+CREATE TABLE IF NOT EXISTS app_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO app_settings (key, value) VALUES
+    ('database_version', '1'),
+    ('app_version', '0.1.0'),
+    ('last_backup', ''),
+    ('theme', 'light'),
+    ('auto_save', 'true'),
+    ('privacy_mode', 'private');
+
+CREATE TRIGGER IF NOT EXISTS update_app_settings_updated_at
+    AFTER UPDATE ON app_settings
+    FOR EACH ROW
+    BEGIN
+        UPDATE app_settings SET updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now') WHERE key = NEW.key;
+    END;
@@ -0,0 +1,70 @@
+-- Migration 008: Entry dates and normalized timestamps
+-- entry_date is the moment an entry is about, which differs from created_at
+-- for backdated and imported entries. It is stored in UTC so entries sort as
+-- text, next to the UTC offset (in minutes) it was written with so it still
+-- lands on the right day. A NULL offset means the local timezone.
+--
+-- Every timestamp is rewritten as UTC RFC 3339 with milliseconds
+-- (YYYY-MM-DDTHH:MM:SS.SSSZ), the format the app writes from now on.
+
+ALTER TABLE journal_entries ADD COLUMN entry_date TEXT;
+ALTER TABLE journal_entries ADD COLUMN entry_utc_offset INTEGER;
+
+-- Drop the updated_at triggers before rewriting rows so they don't bump them
+DROP TRIGGER IF EXISTS update_journal_entries_updated_at;
+DROP TRIGGER IF EXISTS update_app_settings_updated_at;
+DROP TRIGGER IF EXISTS update_tags_updated_at;
+
+UPDATE journal_entries SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at),
+    updated_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at),
+    entry_date = COALESCE(entry_date, strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE embeddings SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE echo_patterns SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE app_settings SET
+    updated_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at);
+
+UPDATE analytics_events SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE tags SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at),
+    updated_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at);
+
+UPDATE moods SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE schema_migrations SET
+    applied_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', applied_at), applied_at);
+
+CREATE INDEX IF NOT EXISTS idx_journal_entries_entry_date ON journal_entries(entry_date);
+
+-- Only fill in updated_at when the writer didn't set it
+CREATE TRIGGER IF NOT EXISTS update_journal_entries_updated_at
+    AFTER UPDATE ON journal_entries
+    FOR EACH ROW
+    WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE journal_entries SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = NEW.id;
+    END;
+
+CREATE TRIGGER IF NOT EXISTS update_app_settings_updated_at
+    AFTER UPDATE ON app_settings
+    FOR EACH ROW
+    WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE app_settings SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE key = NEW.key;
+    END;
+
+CREATE TRIGGER IF NOT EXISTS update_tags_updated_at
+    AFTER UPDATE ON tags
+    FOR EACH ROW
+    WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE tags SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = NEW.id;
+    END;
@@ -0,0 +1,13 @@
+-- Migration 009: Files behind Markdown journals
+-- Journals stored as a folder of Markdown files use journal_entries as an
+-- index. This table maps each indexed entry to its file so files that
+-- haven't changed since the last scan can be skipped.
+
+CREATE TABLE IF NOT EXISTS markdown_files (
+    entry_id TEXT PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE, -- relative to the journal folder, '/' separated
+    modified_ms INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    content_hash TEXT NOT NULL, -- SHA-256 of the file
+    FOREIGN KEY (entry_id) REFERENCES journal_entries(id) ON DELETE CASCADE
+);
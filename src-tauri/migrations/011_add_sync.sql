@@ -0,0 +1,36 @@
+-- Migration 011: Folder sync between devices
+-- Every device appends its entry changes to its own log file in a shared
+-- folder and replays the logs of the other devices. Each entry carries a
+-- version vector so concurrent edits can be told apart from newer ones.
+
+-- Latest known version of each entry; rows outlive deleted entries as tombstones
+CREATE TABLE IF NOT EXISTS sync_versions (
+    entry_id TEXT PRIMARY KEY,
+    clock TEXT NOT NULL, -- JSON version vector, device id -> change count
+    hlc TEXT NOT NULL, -- hybrid logical clock of the change
+    deleted INTEGER NOT NULL DEFAULT 0,
+    pending INTEGER NOT NULL DEFAULT 1, -- not yet written to this device's log
+    resolved TEXT -- JSON list of conflict hlcs resolved here, sent with the next write
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_versions_pending ON sync_versions(pending);
+
+-- How far each other device's log has been read
+CREATE TABLE IF NOT EXISTS sync_peers (
+    device_id TEXT PRIMARY KEY,
+    log_offset INTEGER NOT NULL DEFAULT 0,
+    last_hlc TEXT,
+    updated_at TEXT NOT NULL
+);
+
+-- Versions that lost to a concurrent edit, kept until the user resolves them
+CREATE TABLE IF NOT EXISTS sync_conflicts (
+    id TEXT PRIMARY KEY,
+    entry_id TEXT NOT NULL,
+    clock TEXT NOT NULL,
+    hlc TEXT NOT NULL,
+    entry TEXT NOT NULL, -- JSON of the losing version
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_conflicts_entry_id ON sync_conflicts(entry_id);
@@ -0,0 +1,56 @@
+-- Migration 012: Entry templates
+-- Reusable starting points for entries written again and again. Title and
+-- content may contain {{variables}} that are filled in when an entry is
+-- created from the template
+
+CREATE TABLE IF NOT EXISTS entry_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    title TEXT NOT NULL DEFAULT '',
+    content TEXT NOT NULL DEFAULT '',
+    tags TEXT NOT NULL DEFAULT '[]', -- JSON array
+    mood TEXT, -- mood id from the registry
+    mood_intensity REAL CHECK (mood_intensity BETWEEN 0.0 AND 1.0),
+    privacy TEXT NOT NULL DEFAULT 'private' CHECK (privacy IN ('private', 'public', 'friends')),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_entry_templates_name ON entry_templates(name);
+
+-- Built-in templates
+INSERT OR IGNORE INTO entry_templates (id, name, title, content, tags, mood, privacy) VALUES
+    (
+        'standup',
+        'Daily standup',
+        'Standup {{date}}',
+        '## Yesterday' || char(10) || char(10) || '- ' || char(10) || char(10) ||
+        '## Today' || char(10) || char(10) || '- ' || char(10) || char(10) ||
+        '## Blockers' || char(10) || char(10) || '- ' || char(10),
+        '["standup","work"]',
+        NULL,
+        'private'
+    ),
+    (
+        'gratitude',
+        'Gratitude',
+        'Grateful on {{weekday}}',
+        'Three things I am grateful for today:' || char(10) || char(10) ||
+        '1. ' || char(10) || '2. ' || char(10) || '3. ' || char(10) || char(10) ||
+        '> {{prompt_of_the_day}}' || char(10),
+        '["gratitude"]',
+        'grateful',
+        'private'
+    ),
+    (
+        'weekly-review',
+        'Weekly review',
+        'Week {{week}} review',
+        'Last entry: {{last_entry_title}}' || char(10) || char(10) ||
+        '## What went well' || char(10) || char(10) ||
+        '## What was hard' || char(10) || char(10) ||
+        '## Next week' || char(10),
+        '["weekly-review"]',
+        NULL,
+        'private'
+    );
@@ -0,0 +1,31 @@
+-- Migration 013: Journaling reminders
+-- Reminders are scheduled in local time. last_occurrence_at is the latest
+-- scheduled time that was either fired or skipped, so a restart never fires
+-- the same occurrence twice
+
+CREATE TABLE IF NOT EXISTS reminders (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    message TEXT NOT NULL DEFAULT '',
+    schedule TEXT NOT NULL, -- JSON, see reminders::ReminderSchedule
+    enabled INTEGER NOT NULL DEFAULT 1,
+    skip_if_written INTEGER NOT NULL DEFAULT 1,
+    snoozed_until TEXT,
+    last_occurrence_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Every reminder shown, and what the user did about it
+CREATE TABLE IF NOT EXISTS reminder_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    reminder_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    scheduled_for TEXT NOT NULL,
+    fired_at TEXT NOT NULL,
+    action TEXT CHECK (action IN ('opened', 'wrote', 'snoozed', 'dismissed')),
+    acted_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminder_history_reminder_id ON reminder_history(reminder_id);
+CREATE INDEX IF NOT EXISTS idx_reminder_history_fired_at ON reminder_history(fired_at);
@@ -0,0 +1,17 @@
+-- Migration 014: Entry locations
+-- Where an entry was written: coordinates with their accuracy in metres, the
+-- place name and country (from the entry itself or the bundled GeoNames
+-- cities), and whether it came from a photo, a Day One import or by hand.
+-- An entry has a location when both coordinates are set.
+
+ALTER TABLE journal_entries ADD COLUMN location_latitude REAL;
+ALTER TABLE journal_entries ADD COLUMN location_longitude REAL;
+ALTER TABLE journal_entries ADD COLUMN location_accuracy REAL;
+ALTER TABLE journal_entries ADD COLUMN location_name TEXT;
+ALTER TABLE journal_entries ADD COLUMN location_country TEXT;
+ALTER TABLE journal_entries ADD COLUMN location_source TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_journal_entries_location
+    ON journal_entries(location_latitude, location_longitude);
+CREATE INDEX IF NOT EXISTS idx_journal_entries_location_name
+    ON journal_entries(location_name, location_country);
@@ -0,0 +1,27 @@
+-- Migration 015: Entry states
+-- Pinned, favorite and archived entries, and locked entries that the editor
+-- refuses to change. Posts imported from other services start out locked,
+-- including the ones imported before entries could be locked.
+
+ALTER TABLE journal_entries ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE journal_entries ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE journal_entries ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE journal_entries ADD COLUMN locked INTEGER NOT NULL DEFAULT 0;
+
+-- Keep updated_at as it is while locking imported posts
+DROP TRIGGER IF EXISTS update_journal_entries_updated_at;
+
+UPDATE journal_entries SET locked = 1
+WHERE source IS NOT NULL AND source NOT IN ('', 'local');
+
+CREATE TRIGGER IF NOT EXISTS update_journal_entries_updated_at
+    AFTER UPDATE ON journal_entries
+    FOR EACH ROW
+    WHEN NEW.updated_at = OLD.updated_at
+    BEGIN
+        UPDATE journal_entries SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = NEW.id;
+    END;
+
+CREATE INDEX IF NOT EXISTS idx_journal_entries_pinned ON journal_entries(pinned) WHERE pinned = 1;
+CREATE INDEX IF NOT EXISTS idx_journal_entries_favorite ON journal_entries(favorite) WHERE favorite = 1;
+CREATE INDEX IF NOT EXISTS idx_journal_entries_archived ON journal_entries(archived) WHERE archived = 1;
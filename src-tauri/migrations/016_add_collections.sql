@@ -0,0 +1,34 @@
+-- Migration 016: Collections
+-- Ordered groups of entries such as a trip or the chapters of a draft. An
+-- entry can be in any number of collections. Memberships don't reference
+-- journal_entries so a Markdown journal's index can be rebuilt without
+-- losing them; they are removed when the entry is deleted.
+
+CREATE TABLE IF NOT EXISTS collections (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS collection_entries (
+    collection_id TEXT NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    entry_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    added_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (collection_id, entry_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_collection_entries_position ON collection_entries(collection_id, position);
+CREATE INDEX IF NOT EXISTS idx_collection_entries_entry ON collection_entries(entry_id);
+
+-- The Documents page groups the entries it writes with metadata.type = 'document'
+INSERT OR IGNORE INTO collections (id, name, description, position) VALUES
+    ('documents', 'Documents', 'Documents written on the Documents page', 0);
+
+INSERT OR IGNORE INTO collection_entries (collection_id, entry_id, position)
+SELECT 'documents', id, ROW_NUMBER() OVER (ORDER BY created_at, id) - 1
+FROM journal_entries
+WHERE CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.type') END = 'document';
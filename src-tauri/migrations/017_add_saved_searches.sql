@@ -0,0 +1,16 @@
+-- Migration 017: Saved searches
+-- A name plus a query and filter spec, evaluated live when run. The entry
+-- count is cached for the sidebar and refreshed when entries change
+
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    spec TEXT NOT NULL DEFAULT '{}', -- JSON SearchSpec
+    position INTEGER NOT NULL DEFAULT 0,
+    entry_count INTEGER, -- NULL until first counted
+    counted_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_saved_searches_position ON saved_searches(position);
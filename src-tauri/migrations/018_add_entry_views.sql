@@ -0,0 +1,13 @@
+-- Migration 018: Entry views
+-- When each entry was last opened and last resurfaced, so resurfacing can
+-- favour entries that haven't been read in a while and avoid repeating
+-- itself. Like collection memberships, rows outlive a Markdown index rebuild
+
+CREATE TABLE IF NOT EXISTS entry_views (
+    entry_id TEXT PRIMARY KEY,
+    view_count INTEGER NOT NULL DEFAULT 0,
+    last_viewed_at TEXT,
+    last_resurfaced_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_entry_views_resurfaced ON entry_views(last_resurfaced_at);
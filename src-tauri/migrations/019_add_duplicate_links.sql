@@ -0,0 +1,22 @@
+-- Migration 019: Duplicate links
+-- Entries the user has confirmed are the same post, e.g. a thought
+-- cross-posted to several networks, and pairs they said are not duplicates
+-- so detection stops suggesting them
+
+CREATE TABLE IF NOT EXISTS duplicate_links (
+    entry_id TEXT PRIMARY KEY,
+    group_id TEXT NOT NULL,
+    linked_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_duplicate_links_group ON duplicate_links(group_id);
+
+-- Each pair is stored once with first_id < second_id
+CREATE TABLE IF NOT EXISTS duplicate_dismissals (
+    first_id TEXT NOT NULL,
+    second_id TEXT NOT NULL,
+    dismissed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (first_id, second_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_duplicate_dismissals_second ON duplicate_dismissals(second_id);
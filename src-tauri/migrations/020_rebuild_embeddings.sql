@@ -0,0 +1,34 @@
+-- Migration 020: Embedding index
+-- Nothing wrote to the embeddings table created by 001, and databases from
+-- the old embedded schema only hold placeholder vectors, so the table is
+-- rebuilt rather than migrated. Each entry has at most one embedding per
+-- model, kept with a hash of the text it was made from so unchanged entries
+-- aren't embedded again. Vectors are little-endian f32 arrays.
+
+DROP TABLE IF EXISTS embeddings;
+
+CREATE TABLE embeddings (
+    entry_id TEXT NOT NULL,
+    model_name TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    dimension INTEGER NOT NULL,
+    embedding_data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (entry_id, model_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_embeddings_model_name ON embeddings(model_name);
+
+-- Entries waiting to be embedded by the background indexer
+CREATE TABLE IF NOT EXISTS embedding_queue (
+    entry_id TEXT PRIMARY KEY,
+    force INTEGER NOT NULL DEFAULT 0, -- embed even if the text is unchanged
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TEXT,
+    queued_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_embedding_queue_next_attempt ON embedding_queue(next_attempt_at);
+
+INSERT OR IGNORE INTO embedding_queue (entry_id) SELECT id FROM journal_entries;
@@ -10,9 +10,16 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 use tokio::process::Command;
 use reqwest;
 
+/// Give up on a model server that can't be reached within this long
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Give up on a request the model hasn't answered within this long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingRequest {
     pub text: String,
@@ -23,6 +30,22 @@ pub struct EmbeddingRequest {
 pub struct EmbeddingResponse {
     pub embedding: Vec<f32>,
     pub model: String,
+    pub dimension: usize,
+}
+
+/// Several texts embedded in one request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingBatchRequest {
+    pub texts: Vec<String>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingBatchResponse {
+    /// One vector per text, in the order they were sent
+    pub embeddings: Vec<Vec<f32>>,
+    pub model: String,
+    pub dimension: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,16 +80,21 @@ pub struct EchoPattern {
     pub pattern_type: String,
 }
 
+#[derive(Clone)]
 pub enum AIModel {
     Ollama(()),
     LlamaCpp(String),
 }
 
+#[derive(Clone)]
 pub struct AIService {
     model: AIModel,
     embedding_model: String,
     chat_model: String,
     ollama_url: String,
+    llama_server_url: String,
+    /// Shared by every request to Ollama and llama-server; clones share its connections
+    client: reqwest::Client,
 }
 
 impl AIService {
@@ -82,9 +110,77 @@ impl AIService {
             embedding_model: "nomic-embed-text".to_string(), // Default embedding model
             chat_model: "llama3.2".to_string(),              // Default chat model
             ollama_url: std::env::var("OLLAMA_URL").unwrap_or_else(|_| "http://localhost:11434".to_string()),
+            llama_server_url: "http://localhost:8080".to_string(),
+            client: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .context("Failed to create HTTP client")?,
         })
     }
 
+    /// Use the given models and Ollama endpoint instead of the defaults
+    pub fn with_models(mut self, chat_model: &str, embedding_model: &str, ollama_url: &str) -> Self {
+        self.chat_model = chat_model.to_string();
+        self.embedding_model = embedding_model.to_string();
+        self.ollama_url = ollama_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use the llama-server at `url` for llama.cpp embeddings
+    pub fn with_llama_server(mut self, url: &str) -> Self {
+        self.llama_server_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Name of the model that makes embeddings when none is requested
+    pub async fn embedding_model(&self) -> Result<String> {
+        match &self.model {
+            AIModel::Ollama(_) => Ok(self.embedding_model.clone()),
+            // llama-server embeds with whichever model it was started with
+            AIModel::LlamaCpp(_) => self.llama_server_model().await,
+        }
+    }
+
+    /// The model llama-server was started with, from its /v1/models list
+    async fn llama_server_model(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct ModelsResponseBody {
+            data: Vec<ModelInfo>,
+        }
+
+        #[derive(Deserialize)]
+        struct ModelInfo {
+            id: String,
+        }
+
+        let url = format!("{}/v1/models", self.llama_server_url);
+        let response = self.client.get(&url).send().await.with_context(|| {
+            format!("Failed to reach llama-server at {}", self.llama_server_url)
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = error_message(&response.text().await.unwrap_or_default());
+            return Err(anyhow::anyhow!(
+                "llama-server did not list its model ({}): {}",
+                status,
+                message
+            ));
+        }
+
+        let body: ModelsResponseBody = response
+            .json()
+            .await
+            .context("Failed to parse llama-server model list")?;
+
+        body.data
+            .into_iter()
+            .next()
+            .map(|model| model.id)
+            .context("llama-server did not report which model it runs")
+    }
+
     /// Check if the AI service is available
     pub async fn check_availability(&self) -> Result<bool> {
         match &self.model {
@@ -103,49 +199,157 @@ impl AIService {
 
     /// Generate embeddings for text
     pub async fn generate_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
-        match &self.model {
-            AIModel::Ollama(_) => self.generate_ollama_embedding(request).await,
+        let batch = self
+            .generate_embeddings(EmbeddingBatchRequest {
+                texts: vec![request.text],
+                model: request.model,
+            })
+            .await?;
+
+        Ok(EmbeddingResponse {
+            embedding: batch.embeddings.into_iter().next().unwrap_or_default(),
+            model: batch.model,
+            dimension: batch.dimension,
+        })
+    }
+
+    /// Generate embeddings for several texts in one request
+    pub async fn generate_embeddings(
+        &self,
+        request: EmbeddingBatchRequest,
+    ) -> Result<EmbeddingBatchResponse> {
+        if request.texts.is_empty() {
+            return Err(anyhow::anyhow!("Nothing to embed"));
+        }
+
+        let (embeddings, model) = match &self.model {
+            AIModel::Ollama(_) => {
+                let model = request
+                    .model
+                    .unwrap_or_else(|| self.embedding_model.clone());
+                let embeddings = self
+                    .generate_ollama_embeddings(&request.texts, &model)
+                    .await?;
+                (embeddings, model)
+            }
             AIModel::LlamaCpp(_) => {
-                // For now, return a mock embedding since llama.cpp doesn't have direct embedding support
-                // In a real implementation, you'd use a separate embedding model
-                Ok(EmbeddingResponse {
-                    embedding: self.mock_embedding(&request.text),
-                    model: "mock-embedding".to_string(),
-                })
+                let model = self.llama_server_model().await?;
+                let embeddings = self
+                    .generate_llama_server_embeddings(&request.texts)
+                    .await?;
+                (embeddings, model)
             }
+        };
+
+        if embeddings.len() != request.texts.len() {
+            return Err(anyhow::anyhow!(
+                "Expected {} embeddings but got {}",
+                request.texts.len(),
+                embeddings.len()
+            ));
         }
+        let dimension = embeddings.first().map(Vec::len).unwrap_or_default();
+        if dimension == 0
+            || embeddings
+                .iter()
+                .any(|embedding| embedding.len() != dimension)
+        {
+            return Err(anyhow::anyhow!("Embeddings have inconsistent dimensions"));
+        }
+
+        Ok(EmbeddingBatchResponse {
+            embeddings,
+            model,
+            dimension,
+        })
     }
 
-    /// Generate embedding using Ollama
-    async fn generate_ollama_embedding(
+    /// Generate embeddings using Ollama's /api/embed
+    async fn generate_ollama_embeddings(
         &self,
-        request: EmbeddingRequest,
-    ) -> Result<EmbeddingResponse> {
-        let model = request
-            .model
-            .unwrap_or_else(|| self.embedding_model.clone());
-
-        let output = Command::new("ollama")
-            .arg("run")
-            .arg(&model)
-            .arg(&format!("Generate embedding for: {}", request.text))
-            .output()
+        texts: &[String],
+        model: &str,
+    ) -> Result<Vec<Vec<f32>>> {
+        #[derive(Serialize)]
+        struct EmbedRequestBody<'a> {
+            model: &'a str,
+            input: &'a [String],
+        }
+
+        #[derive(Deserialize)]
+        struct EmbedResponseBody {
+            embeddings: Vec<Vec<f32>>,
+        }
+
+        let url = format!("{}/api/embed", self.ollama_url);
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&EmbedRequestBody {
+                model,
+                input: texts,
+            })
+            .send()
             .await
-            .context("Failed to run Ollama embedding command")?;
+            .with_context(|| format!("Failed to reach Ollama at {}", self.ollama_url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = error_message(&response.text().await.unwrap_or_default());
+            // Ollama answers {"error": "model \"name\" not found, try pulling it first"}
+            if message.contains(&format!("model \"{}\" not found", model)) {
+                return Err(anyhow::anyhow!(
+                    "Embedding model \"{}\" is not installed in Ollama; run `ollama pull {}`",
+                    model,
+                    model
+                ));
+            }
+            return Err(anyhow::anyhow!(
+                "Ollama embedding failed ({}): {}",
+                status,
+                message
+            ));
+        }
 
-        if !output.status.success() {
+        let body: EmbedResponseBody = response
+            .json()
+            .await
+            .context("Failed to parse Ollama embedding response")?;
+
+        Ok(body.embeddings)
+    }
+
+    /// Generate embeddings using llama-server's /embedding endpoint
+    async fn generate_llama_server_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embedding", self.llama_server_url);
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "content": texts }))
+            .send()
+            .await
+            .with_context(|| {
+                format!("Failed to reach llama-server at {}", self.llama_server_url)
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = error_message(&response.text().await.unwrap_or_default());
             return Err(anyhow::anyhow!(
-                "Ollama embedding failed: {}",
-                String::from_utf8_lossy(&output.stderr)
+                "llama-server embedding failed ({}): {}",
+                status,
+                message
             ));
         }
 
-        // Parse the output (this is a simplified version)
-        // In a real implementation, you'd parse the actual embedding vector
-        let _response = String::from_utf8_lossy(&output.stdout);
-        let embedding = self.mock_embedding(&request.text);
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse llama-server embedding response")?;
 
-        Ok(EmbeddingResponse { embedding, model })
+        parse_llama_server_embeddings(body)
     }
 
     /// Generate a chat response
@@ -206,10 +410,10 @@ impl AIService {
             stream: false,
         };
 
-        let client = reqwest::Client::new();
         let url = format!("{}/api/chat", self.ollama_url);
-        
-        let response = client
+
+        let response = self
+            .client
             .post(&url)
             .json(&body)
             .send()
@@ -391,27 +595,6 @@ impl AIService {
         })
     }
 
-    /// Generate a mock embedding (for development/testing)
-    fn mock_embedding(&self, text: &str) -> Vec<f32> {
-        // Generate a deterministic mock embedding based on text hash
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        text.hash(&mut hasher);
-        let hash = hasher.finish();
-
-        // Generate a 384-dimensional vector (common embedding size)
-        let mut embedding = Vec::with_capacity(384);
-        for i in 0..384 {
-            let seed = hash.wrapping_add(i);
-            let value = (seed as f32 / u64::MAX as f32) * 2.0 - 1.0; // Normalize to [-1, 1]
-            embedding.push(value);
-        }
-
-        embedding
-    }
-
     /// Get available models
     pub async fn list_models(&self) -> Result<Vec<String>> {
         match &self.model {
@@ -442,3 +625,244 @@ impl AIService {
         }
     }
 }
+
+/// The message of an error body from Ollama or llama-server, or the body itself
+fn error_message(body: &str) -> String {
+    let value: serde_json::Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(_) => return body.trim().to_string(),
+    };
+
+    // Ollama sends {"error": "..."}, llama-server {"error": {"message": "..."}}
+    match &value["error"] {
+        serde_json::Value::String(message) => message.clone(),
+        error => error["message"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| body.trim().to_string()),
+    }
+}
+
+/// Read the vectors from a llama-server /embedding response. Older servers
+/// answer a single text with {"embedding": [...]}; newer ones send a list of
+/// {"index", "embedding"} where a pooled embedding is wrapped in one more array.
+fn parse_llama_server_embeddings(body: serde_json::Value) -> Result<Vec<Vec<f32>>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Vector {
+        Pooled(Vec<f32>),
+        Wrapped(Vec<Vec<f32>>),
+    }
+
+    impl Vector {
+        fn into_pooled(self) -> Result<Vec<f32>> {
+            match self {
+                Vector::Pooled(vector) => Ok(vector),
+                Vector::Wrapped(mut rows) if rows.len() == 1 => Ok(rows.remove(0)),
+                Vector::Wrapped(_) => Err(anyhow::anyhow!(
+                    "llama-server returned one vector per token; start it with --pooling mean"
+                )),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Item {
+        #[serde(default)]
+        index: usize,
+        embedding: Vector,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Body {
+        List(Vec<Item>),
+        Single(Item),
+    }
+
+    let mut items =
+        match serde_json::from_value(body).context("Unexpected llama-server embedding response")? {
+            Body::List(items) => items,
+            Body::Single(item) => vec![item],
+        };
+    items.sort_by_key(|item| item.index);
+
+    items
+        .into_iter()
+        .map(|item| item.embedding.into_pooled())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockServer, Response};
+    use serde_json::{json, Value};
+
+    /// Answers every request with the JSON `reply` gives for its path and body
+    fn json_server(reply: fn(&str, &Value) -> (u16, Value)) -> MockServer {
+        MockServer::start(move |request| {
+            let (status, body) = reply(&request.path, &request.json());
+            Response::json(status, &body)
+        })
+    }
+
+    fn ollama(server: &MockServer) -> AIService {
+        AIService::new("ollama", "").unwrap().with_models(
+            "llama3.2",
+            "nomic-embed-text",
+            &server.url,
+        )
+    }
+
+    fn llama_server(server: &MockServer) -> AIService {
+        AIService::new("llama.cpp", "llama-cli")
+            .unwrap()
+            .with_llama_server(&server.url)
+    }
+
+    fn batch(texts: &[&str]) -> EmbeddingBatchRequest {
+        EmbeddingBatchRequest {
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            model: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn ollama_embeds_a_batch_in_one_call() {
+        let server = json_server(|_, body| {
+            let vectors: Vec<Vec<f32>> = (0..body["input"].as_array().unwrap().len())
+                .map(|i| vec![i as f32, 0.5, -0.5])
+                .collect();
+            (
+                200,
+                json!({ "model": body["model"], "embeddings": vectors }),
+            )
+        });
+
+        let response = ollama(&server)
+            .generate_embeddings(batch(&["first", "second", "third"]))
+            .await
+            .unwrap();
+        assert_eq!(response.model, "nomic-embed-text");
+        assert_eq!(response.dimension, 3);
+        assert_eq!(response.embeddings[2][0], 2.0);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/api/embed");
+        assert_eq!(
+            requests[0].json(),
+            json!({ "model": "nomic-embed-text", "input": ["first", "second", "third"] })
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_ollama_model_suggests_pulling_it() {
+        let server = json_server(|_, body| match body["model"].as_str() {
+            Some("nomic-embed-text") => (
+                404,
+                json!({ "error": "model \"nomic-embed-text\" not found, try pulling it first" }),
+            ),
+            _ => (404, json!({ "error": "404 page not found" })),
+        });
+        let service = ollama(&server);
+
+        let error = service
+            .generate_embeddings(batch(&["text"]))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("ollama pull nomic-embed-text"), "{}", error);
+
+        // A 404 from an Ollama without /api/embed is not a missing model
+        let error = service
+            .generate_embeddings(EmbeddingBatchRequest {
+                model: Some("other".to_string()),
+                ..batch(&["text"])
+            })
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(!error.contains("ollama pull"), "{}", error);
+        assert!(error.contains("404 page not found"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn llama_server_embeddings_are_keyed_by_its_model() {
+        let server = json_server(|path, body| match path {
+            "/v1/models" => (
+                200,
+                json!({ "object": "list", "data": [{ "id": "nomic-embed-text-v1.5.Q8_0.gguf" }] }),
+            ),
+            // Newer servers: one item per text, pooled vectors wrapped in an array, any order
+            _ => {
+                let count = body["content"].as_array().unwrap().len();
+                let items: Vec<Value> = (0..count)
+                    .rev()
+                    .map(|index| json!({ "index": index, "embedding": [[index as f32, 1.0]] }))
+                    .collect();
+                (200, Value::Array(items))
+            }
+        });
+        let service = llama_server(&server);
+
+        assert_eq!(
+            service.embedding_model().await.unwrap(),
+            "nomic-embed-text-v1.5.Q8_0.gguf"
+        );
+        let response = service
+            .generate_embeddings(batch(&["first", "second"]))
+            .await
+            .unwrap();
+        assert_eq!(response.model, "nomic-embed-text-v1.5.Q8_0.gguf");
+        assert_eq!(response.embeddings, [vec![0.0, 1.0], vec![1.0, 1.0]]);
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.method == "POST"
+                && request.path == "/embedding"
+                && request.json()["content"] == json!(["first", "second"])));
+    }
+
+    #[test]
+    fn llama_server_response_shapes() {
+        // Older servers answer a single text with a bare vector
+        assert_eq!(
+            parse_llama_server_embeddings(json!({ "embedding": [0.25, 0.5] })).unwrap(),
+            [vec![0.25, 0.5]]
+        );
+        assert_eq!(
+            parse_llama_server_embeddings(json!([
+                { "index": 1, "embedding": [[3.0, 4.0]] },
+                { "index": 0, "embedding": [[1.0, 2.0]] },
+            ]))
+            .unwrap(),
+            [vec![1.0, 2.0], vec![3.0, 4.0]]
+        );
+
+        // Without pooling there is a vector for every token
+        let error = parse_llama_server_embeddings(json!([
+            { "index": 0, "embedding": [[1.0, 2.0], [3.0, 4.0]] },
+        ]))
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("--pooling mean"), "{}", error);
+
+        assert!(parse_llama_server_embeddings(json!({ "vectors": [] })).is_err());
+    }
+
+    #[test]
+    fn error_messages_come_from_either_server() {
+        assert_eq!(
+            error_message(r#"{"error": "model missing"}"#),
+            "model missing"
+        );
+        assert_eq!(
+            error_message(r#"{"error": {"code": 500, "message": "context too long"}}"#),
+            "context too long"
+        );
+        assert_eq!(error_message(" Bad Gateway \n"), "Bad Gateway");
+    }
+}
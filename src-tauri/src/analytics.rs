@@ -0,0 +1,421 @@
+/**
+ * Local analytics for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Opt-in recording of usage events into analytics_events
+ * - Hashing search queries so their text is never stored
+ * - Retention pruning and a one-click wipe
+ * - Aggregations for the "how I use the app" panel
+ *
+ * Events are only ever written to the local database. Nothing in this
+ * module talks to the network.
+ */
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::database::{format_timestamp, Database};
+
+const ENABLED_SETTING: &str = "analytics_enabled";
+const RETENTION_SETTING: &str = "analytics_retention_days";
+const SALT_SETTING: &str = "analytics_salt";
+const DEFAULT_RETENTION_DAYS: u32 = 90;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AnalyticsEvent {
+    Command {
+        name: String,
+    },
+    Search {
+        query_hash: String,
+        result_count: usize,
+    },
+    AiCall {
+        operation: String,
+        model: Option<String>,
+        latency_ms: u64,
+        success: bool,
+    },
+    Import {
+        source: String,
+        count: u64,
+    },
+}
+
+impl AnalyticsEvent {
+    pub fn command(name: &str) -> Self {
+        AnalyticsEvent::Command {
+            name: name.to_string(),
+        }
+    }
+
+    fn event_type(&self) -> &'static str {
+        match self {
+            AnalyticsEvent::Command { .. } => "command",
+            AnalyticsEvent::Search { .. } => "search",
+            AnalyticsEvent::AiCall { .. } => "ai_call",
+            AnalyticsEvent::Import { .. } => "import",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsSettings {
+    pub enabled: bool,
+    pub retention_days: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiUsage {
+    pub operation: String,
+    pub calls: i64,
+    pub failures: i64,
+    pub average_latency_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub settings: AnalyticsSettings,
+    pub total_events: i64,
+    pub events_by_type: BTreeMap<String, i64>,
+    pub commands: BTreeMap<String, i64>,
+    pub searches: i64,
+    pub ai_calls: Vec<AiUsage>,
+    /// Items imported per source
+    pub imports: BTreeMap<String, i64>,
+    /// Events per UTC day (YYYY-MM-DD)
+    pub daily_events: BTreeMap<String, i64>,
+}
+
+pub struct AnalyticsRecorder<'a> {
+    database: &'a Database,
+}
+
+impl<'a> AnalyticsRecorder<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        AnalyticsRecorder { database }
+    }
+
+    pub async fn settings(&self) -> Result<AnalyticsSettings> {
+        let enabled = self.database.get_setting(ENABLED_SETTING).await?;
+        let retention_days = self.database.get_setting(RETENTION_SETTING).await?;
+
+        Ok(AnalyticsSettings {
+            enabled: enabled.as_deref() == Some("true"),
+            retention_days: retention_days
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_RETENTION_DAYS),
+        })
+    }
+
+    pub async fn update_settings(&self, settings: &AnalyticsSettings) -> Result<()> {
+        if settings.retention_days == 0 {
+            return Err(anyhow::anyhow!("Retention must be at least one day"));
+        }
+
+        self.database
+            .set_setting(ENABLED_SETTING, &settings.enabled.to_string())
+            .await?;
+        self.database
+            .set_setting(RETENTION_SETTING, &settings.retention_days.to_string())
+            .await?;
+
+        // Opting out also forgets what was collected so far
+        if !settings.enabled {
+            self.clear().await?;
+        } else {
+            self.prune().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Store an event if the user opted in; a no-op otherwise
+    pub async fn record(&self, event: &AnalyticsEvent) -> Result<()> {
+        if !self.settings().await?.enabled {
+            return Ok(());
+        }
+
+        sqlx::query(
+            "INSERT INTO analytics_events (id, event_type, event_data, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(event.event_type())
+        .bind(serde_json::to_string(event)?)
+        .bind(format_timestamp(&Utc::now()))
+        .execute(self.database.pool())
+        .await
+        .context("Failed to record analytics event")?;
+
+        Ok(())
+    }
+
+    /// Record a search by the hash of its query
+    pub async fn record_search(&self, query: &str, result_count: usize) -> Result<()> {
+        if !self.settings().await?.enabled {
+            return Ok(());
+        }
+
+        let query_hash = self.hash_query(query).await?;
+        self.record(&AnalyticsEvent::Search {
+            query_hash,
+            result_count,
+        })
+        .await
+    }
+
+    /// Salted SHA-256 of a normalized search query, so repeated searches can be
+    /// counted without keeping what was searched for
+    pub async fn hash_query(&self, query: &str) -> Result<String> {
+        let salt = match self.database.get_setting(SALT_SETTING).await? {
+            Some(salt) if !salt.is_empty() => salt,
+            _ => {
+                let salt = Uuid::new_v4().to_string();
+                self.database.set_setting(SALT_SETTING, &salt).await?;
+                salt
+            }
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.update(salt.as_bytes());
+        hasher.update(query.trim().to_lowercase().as_bytes());
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Delete events older than the retention window
+    pub async fn prune(&self) -> Result<u64> {
+        let settings = self.settings().await?;
+        let cutoff = Utc::now() - Duration::days(settings.retention_days as i64);
+
+        let result = sqlx::query("DELETE FROM analytics_events WHERE created_at < ?")
+            .bind(format_timestamp(&cutoff))
+            .execute(self.database.pool())
+            .await
+            .context("Failed to prune analytics events")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete every recorded event
+    pub async fn clear(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM analytics_events")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to clear analytics events")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Aggregate usage over the last `days` days (all retained events if `None`)
+    pub async fn summary(&self, days: Option<u32>) -> Result<UsageSummary> {
+        let since = days
+            .map(|d| format_timestamp(&(Utc::now() - Duration::days(d as i64))))
+            .unwrap_or_default();
+
+        let events_by_type = self
+            .count_by(
+                "SELECT event_type AS key, COUNT(*) AS value FROM analytics_events
+                 WHERE created_at >= ? GROUP BY event_type",
+                &since,
+            )
+            .await?;
+
+        let commands = self
+            .count_by(
+                "SELECT json_extract(event_data, '$.name') AS key, COUNT(*) AS value
+                 FROM analytics_events
+                 WHERE event_type = 'command' AND created_at >= ? GROUP BY key",
+                &since,
+            )
+            .await?;
+
+        let imports = self
+            .count_by(
+                "SELECT json_extract(event_data, '$.source') AS key,
+                        SUM(json_extract(event_data, '$.count')) AS value
+                 FROM analytics_events
+                 WHERE event_type = 'import' AND created_at >= ? GROUP BY key",
+                &since,
+            )
+            .await?;
+
+        let daily_events = self
+            .count_by(
+                "SELECT substr(created_at, 1, 10) AS key, COUNT(*) AS value FROM analytics_events
+                 WHERE created_at >= ? GROUP BY key",
+                &since,
+            )
+            .await?;
+
+        let ai_rows = sqlx::query(
+            "SELECT json_extract(event_data, '$.operation') AS operation,
+                    COUNT(*) AS calls,
+                    SUM(CASE WHEN json_extract(event_data, '$.success') THEN 0 ELSE 1 END) AS failures,
+                    AVG(json_extract(event_data, '$.latency_ms')) AS average_latency_ms
+             FROM analytics_events
+             WHERE event_type = 'ai_call' AND created_at >= ?
+             GROUP BY operation
+             ORDER BY calls DESC",
+        )
+        .bind(&since)
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to aggregate AI usage")?;
+
+        let ai_calls = ai_rows
+            .into_iter()
+            .map(|row| AiUsage {
+                operation: row
+                    .get::<Option<String>, _>("operation")
+                    .unwrap_or_default(),
+                calls: row.get("calls"),
+                failures: row.get("failures"),
+                average_latency_ms: row
+                    .get::<Option<f64>, _>("average_latency_ms")
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(UsageSummary {
+            settings: self.settings().await?,
+            total_events: events_by_type.values().sum(),
+            searches: events_by_type.get("search").copied().unwrap_or(0),
+            events_by_type,
+            commands,
+            ai_calls,
+            imports,
+            daily_events,
+        })
+    }
+
+    async fn count_by(&self, sql: &str, since: &str) -> Result<BTreeMap<String, i64>> {
+        let rows = sqlx::query(sql)
+            .bind(since)
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to aggregate analytics events")?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let key: Option<String> = row.get("key");
+                let value: Option<i64> = row.get("value");
+                Some((key?, value.unwrap_or(0)))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempJournal;
+
+    async fn event_count(database: &Database) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM analytics_events")
+            .fetch_one(database.pool())
+            .await
+            .unwrap()
+    }
+
+    fn opted_in(retention_days: u32) -> AnalyticsSettings {
+        AnalyticsSettings {
+            enabled: true,
+            retention_days,
+        }
+    }
+
+    #[tokio::test]
+    async fn nothing_is_recorded_until_opted_in() {
+        let database = TempJournal::new().await;
+        let recorder = AnalyticsRecorder::new(&database);
+        assert!(!recorder.settings().await.unwrap().enabled);
+
+        recorder
+            .record(&AnalyticsEvent::command("open_journal"))
+            .await
+            .unwrap();
+        recorder.record_search("fig tree", 3).await.unwrap();
+        assert_eq!(event_count(&database).await, 0);
+
+        recorder.update_settings(&opted_in(30)).await.unwrap();
+        recorder
+            .record(&AnalyticsEvent::command("open_journal"))
+            .await
+            .unwrap();
+        recorder.record_search("fig tree", 3).await.unwrap();
+        let summary = recorder.summary(None).await.unwrap();
+        assert_eq!(summary.total_events, 2);
+        assert_eq!(summary.commands["open_journal"], 1);
+        assert_eq!(summary.searches, 1);
+
+        // Opting out forgets what was collected
+        recorder
+            .update_settings(&AnalyticsSettings {
+                enabled: false,
+                retention_days: 30,
+            })
+            .await
+            .unwrap();
+        assert_eq!(event_count(&database).await, 0);
+        assert!(recorder.update_settings(&opted_in(0)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn search_queries_are_only_stored_as_salted_hashes() {
+        let database = TempJournal::new().await;
+        let recorder = AnalyticsRecorder::new(&database);
+        recorder.update_settings(&opted_in(30)).await.unwrap();
+
+        let hash = recorder.hash_query("  Fig Tree ").await.unwrap();
+        assert_eq!(hash, recorder.hash_query("fig tree").await.unwrap());
+        assert_ne!(hash, recorder.hash_query("fig").await.unwrap());
+        assert_eq!(hash.len(), 64);
+
+        // Another journal gets its own salt
+        let other = TempJournal::new().await;
+        assert_ne!(
+            hash,
+            AnalyticsRecorder::new(&other)
+                .hash_query("fig tree")
+                .await
+                .unwrap()
+        );
+
+        recorder.record_search("Fig Tree", 2).await.unwrap();
+        let data: String = sqlx::query_scalar("SELECT event_data FROM analytics_events")
+            .fetch_one(database.pool())
+            .await
+            .unwrap();
+        assert!(!data.to_lowercase().contains("fig"));
+        assert!(data.contains(&hash));
+    }
+
+    #[tokio::test]
+    async fn prune_drops_events_past_retention() {
+        let database = TempJournal::new().await;
+        let recorder = AnalyticsRecorder::new(&database);
+        recorder.update_settings(&opted_in(7)).await.unwrap();
+
+        for days_ago in [0, 6, 8, 40] {
+            sqlx::query(
+                "INSERT INTO analytics_events (id, event_type, event_data, created_at)
+                 VALUES (?, 'command', '{\"name\":\"sync\"}', ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(format_timestamp(&(Utc::now() - Duration::days(days_ago))))
+            .execute(database.pool())
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(recorder.prune().await.unwrap(), 2);
+        assert_eq!(event_count(&database).await, 2);
+        assert_eq!(recorder.prune().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,229 @@
+/**
+ * Scheduled backups for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Snapshotting the journal database into a backups folder on a schedule
+ * - Keeping only as many backups as the retention setting allows
+ * - Listing the backups that exist
+ *
+ * Backups sit in a `backups` folder next to the database. Each one is a
+ * complete copy written with VACUUM INTO, so it can be opened as a journal
+ * on its own. Markdown and Git journals keep their entries in files and are
+ * backed up along with their folder instead.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Months, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::database::{format_timestamp, parse_timestamp, Database};
+use crate::maintenance::Maintenance;
+use crate::settings::{BackupSchedule, SettingsStore};
+
+const BACKUPS_DIR: &str = "backups";
+const LAST_BACKUP_SETTING: &str = "last_backup";
+const BACKUP_PREFIX: &str = "journal-";
+const BACKUP_EXTENSION: &str = ".db";
+const BACKUP_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const PARTIAL_SUFFIX: &str = ".partial";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// The folder backups of a journal database are kept in
+pub fn backups_dir(database_path: &Path) -> PathBuf {
+    database_path
+        .parent()
+        .map(|parent| parent.join(BACKUPS_DIR))
+        .unwrap_or_else(|| PathBuf::from(BACKUPS_DIR))
+}
+
+/// Whether a backup made at `last` is old enough that `schedule` wants another at `now`
+fn is_due(schedule: BackupSchedule, last: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    let Some(last) = last else {
+        return schedule != BackupSchedule::Off;
+    };
+
+    let next = match schedule {
+        BackupSchedule::Off => return false,
+        BackupSchedule::Daily => last + Duration::days(1),
+        BackupSchedule::Weekly => last + Duration::weeks(1),
+        BackupSchedule::Monthly => last
+            .checked_add_months(Months::new(1))
+            .unwrap_or(last + Duration::days(30)),
+    };
+    next <= now
+}
+
+pub struct Backups<'a> {
+    database: &'a Database,
+    database_path: &'a Path,
+}
+
+impl<'a> Backups<'a> {
+    pub fn new(database: &'a Database, database_path: &'a Path) -> Self {
+        Backups {
+            database,
+            database_path,
+        }
+    }
+
+    /// Back up the journal if the schedule says one is due
+    pub async fn run_scheduled(&self) -> Result<Option<BackupInfo>> {
+        let settings = SettingsStore::new(self.database).get().await?;
+        let last = self
+            .database
+            .get_setting(LAST_BACKUP_SETTING)
+            .await?
+            .and_then(|last| parse_timestamp(&last).ok());
+        if !is_due(settings.backup_schedule, last, Utc::now()) {
+            return Ok(None);
+        }
+
+        self.create().await.map(Some)
+    }
+
+    /// Back up the journal now and remove backups beyond the retention setting
+    pub async fn create(&self) -> Result<BackupInfo> {
+        let directory = backups_dir(self.database_path);
+        tokio::fs::create_dir_all(&directory)
+            .await
+            .context("Failed to create backups folder")?;
+
+        let created_at = Utc::now();
+        let path = directory.join(format!(
+            "{}{}{}",
+            BACKUP_PREFIX,
+            created_at.format(BACKUP_TIME_FORMAT),
+            BACKUP_EXTENSION
+        ));
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(PARTIAL_SUFFIX);
+        let partial = PathBuf::from(partial);
+
+        Maintenance::new(self.database).checkpoint().await?;
+        let _ = tokio::fs::remove_file(&partial).await;
+        if let Err(e) = sqlx::query("VACUUM INTO ?")
+            .bind(partial.to_string_lossy().to_string())
+            .execute(self.database.pool())
+            .await
+        {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e).context("Failed to back up database");
+        }
+        tokio::fs::rename(&partial, &path)
+            .await
+            .context("Failed to move backup into place")?;
+
+        self.database
+            .set_setting(LAST_BACKUP_SETTING, &format_timestamp(&created_at))
+            .await?;
+
+        let retention = SettingsStore::new(self.database)
+            .get()
+            .await?
+            .backup_retention;
+        self.prune(retention as usize).await?;
+
+        let size = tokio::fs::metadata(&path).await?.len();
+        Ok(BackupInfo {
+            path,
+            created_at,
+            size,
+        })
+    }
+
+    /// Backups of this journal, newest first
+    pub async fn list(&self) -> Result<Vec<BackupInfo>> {
+        let directory = backups_dir(self.database_path);
+        let mut entries = match tokio::fs::read_dir(&directory).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", directory)),
+        };
+
+        let mut backups = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(created_at) = name
+                .strip_prefix(BACKUP_PREFIX)
+                .and_then(|name| name.strip_suffix(BACKUP_EXTENSION))
+                .and_then(|time| NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok())
+            else {
+                continue;
+            };
+
+            backups.push(BackupInfo {
+                path: entry.path(),
+                created_at: created_at.and_utc(),
+                size: entry.metadata().await?.len(),
+            });
+        }
+
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+        Ok(backups)
+    }
+
+    /// Remove all but the newest `keep` backups
+    async fn prune(&self, keep: usize) -> Result<()> {
+        for backup in self.list().await?.into_iter().skip(keep.max(1)) {
+            tokio::fs::remove_file(&backup.path)
+                .await
+                .with_context(|| format!("Failed to remove old backup {:?}", backup.path))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_waits_for_interval() {
+        let last = "2026-01-31T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert!(is_due(BackupSchedule::Daily, None, last));
+        assert!(!is_due(BackupSchedule::Off, None, last));
+        assert!(!is_due(
+            BackupSchedule::Off,
+            Some(last),
+            last + Duration::days(400)
+        ));
+
+        assert!(!is_due(
+            BackupSchedule::Daily,
+            Some(last),
+            last + Duration::hours(23)
+        ));
+        assert!(is_due(
+            BackupSchedule::Daily,
+            Some(last),
+            last + Duration::hours(24)
+        ));
+        assert!(!is_due(
+            BackupSchedule::Weekly,
+            Some(last),
+            last + Duration::days(6)
+        ));
+        assert!(is_due(
+            BackupSchedule::Weekly,
+            Some(last),
+            last + Duration::days(7)
+        ));
+
+        // A month after January 31st is the end of February
+        let end_of_february = "2026-02-28T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!is_due(
+            BackupSchedule::Monthly,
+            Some(last),
+            end_of_february - Duration::hours(1)
+        ));
+        assert!(is_due(BackupSchedule::Monthly, Some(last), end_of_february));
+    }
+}
@@ -0,0 +1,494 @@
+/**
+ * Collections for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Named, ordered groups of entries such as a trip or a book draft
+ * - Adding, removing and reordering the entries of a collection
+ * - Listing and searching within a collection
+ * - Exporting a collection as one Markdown or JSON file
+ *
+ * Unlike tags, a collection keeps its entries in the order they were
+ * arranged, and an entry can belong to any number of collections.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::database::{format_timestamp, parse_timestamp, Database, JournalEntry};
+use crate::storage::EntryFilter;
+
+/// The built-in collection the Documents page keeps its documents in
+pub const DOCUMENTS_COLLECTION: &str = "documents";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub position: i64,
+    pub entry_count: i64,
+    /// Built-in collections can be renamed but not deleted
+    pub is_builtin: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One document with a section per entry, in collection order
+    Markdown,
+    /// The collection and its entries as JSON
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionExport {
+    pub path: String,
+    pub format: ExportFormat,
+    pub entries: usize,
+}
+
+pub struct CollectionStore<'a> {
+    database: &'a Database,
+}
+
+impl<'a> CollectionStore<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        CollectionStore { database }
+    }
+
+    /// Every collection in the order they were arranged
+    pub async fn list(&self) -> Result<Vec<Collection>> {
+        let rows = sqlx::query(&format!(
+            "{} ORDER BY c.position, c.name COLLATE NOCASE",
+            SELECT_COLLECTIONS
+        ))
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to list collections")?;
+
+        rows.iter().map(collection_from_row).collect()
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Collection>> {
+        let row = sqlx::query(&format!("{} WHERE c.id = ?", SELECT_COLLECTIONS))
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await
+            .context("Failed to fetch collection")?;
+
+        row.as_ref().map(collection_from_row).transpose()
+    }
+
+    /// Add a collection after the existing ones
+    pub async fn create(&self, input: &CollectionInput) -> Result<Collection> {
+        let input = normalize(input)?;
+        let id = Uuid::new_v4().to_string();
+        let now = format_timestamp(&Utc::now());
+
+        sqlx::query(
+            r#"
+            INSERT INTO collections (id, name, description, position, created_at, updated_at)
+            VALUES (?, ?, ?, (SELECT COALESCE(MAX(position) + 1, 0) FROM collections), ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.name)
+        .bind(&input.description)
+        .bind(&now)
+        .bind(&now)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to create collection")?;
+
+        self.get(&id)
+            .await?
+            .context("Collection not found after creation")
+    }
+
+    pub async fn update(&self, id: &str, input: &CollectionInput) -> Result<Collection> {
+        let input = normalize(input)?;
+
+        let result = sqlx::query(
+            "UPDATE collections SET name = ?, description = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(&input.name)
+        .bind(&input.description)
+        .bind(format_timestamp(&Utc::now()))
+        .bind(id)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to update collection")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Collection not found: {}", id));
+        }
+
+        self.get(id)
+            .await?
+            .context("Collection not found after update")
+    }
+
+    /// Delete a collection; its entries stay in the journal
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        if id == DOCUMENTS_COLLECTION {
+            return Err(anyhow::anyhow!(
+                "The Documents collection cannot be deleted"
+            ));
+        }
+
+        let result = sqlx::query("DELETE FROM collections WHERE id = ?")
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to delete collection")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Collection not found: {}", id));
+        }
+
+        Ok(())
+    }
+
+    /// Arrange the collections in the order of `ids`, which must name each of them once
+    pub async fn reorder(&self, ids: &[String]) -> Result<Vec<Collection>> {
+        let existing: Vec<String> = sqlx::query_scalar("SELECT id FROM collections")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list collections")?;
+        ensure_same_members(&existing, ids, "collection")?;
+
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        for (position, id) in ids.iter().enumerate() {
+            sqlx::query("UPDATE collections SET position = ? WHERE id = ?")
+                .bind(position as i64)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to reorder collections")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit collection order")?;
+
+        self.list().await
+    }
+
+    /// Append entries to the end of a collection, skipping the ones already in it
+    pub async fn add_entries(&self, id: &str, entry_ids: &[String]) -> Result<Collection> {
+        self.require(id).await?;
+        for entry_id in entry_ids {
+            if self.database.get_entry(entry_id).await?.is_none() {
+                return Err(anyhow::anyhow!("Entry not found: {}", entry_id));
+            }
+        }
+
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        for entry_id in entry_ids {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO collection_entries (collection_id, entry_id, position, added_at)
+                VALUES (?, ?, (SELECT COALESCE(MAX(position) + 1, 0) FROM collection_entries WHERE collection_id = ?), ?)
+                "#,
+            )
+            .bind(id)
+            .bind(entry_id)
+            .bind(id)
+            .bind(format_timestamp(&Utc::now()))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to add entry to collection")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit collection entries")?;
+
+        self.touch(id).await
+    }
+
+    pub async fn remove_entries(&self, id: &str, entry_ids: &[String]) -> Result<Collection> {
+        self.require(id).await?;
+
+        for entry_id in entry_ids {
+            sqlx::query("DELETE FROM collection_entries WHERE collection_id = ? AND entry_id = ?")
+                .bind(id)
+                .bind(entry_id)
+                .execute(self.database.pool())
+                .await
+                .context("Failed to remove entry from collection")?;
+        }
+
+        self.touch(id).await
+    }
+
+    /// Arrange a collection's entries in the order of `entry_ids`, which must name each of them once
+    pub async fn reorder_entries(&self, id: &str, entry_ids: &[String]) -> Result<Collection> {
+        self.require(id).await?;
+        ensure_same_members(&self.entry_ids(id).await?, entry_ids, "entry")?;
+
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        for (position, entry_id) in entry_ids.iter().enumerate() {
+            sqlx::query(
+                "UPDATE collection_entries SET position = ? WHERE collection_id = ? AND entry_id = ?",
+            )
+            .bind(position as i64)
+            .bind(id)
+            .bind(entry_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to reorder collection entries")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit collection order")?;
+
+        self.touch(id).await
+    }
+
+    /// The collection's entries in order, narrowed by `filter`
+    pub async fn entries(&self, id: &str, filter: &EntryFilter) -> Result<Vec<JournalEntry>> {
+        self.require(id).await?;
+
+        let mut entries = Vec::new();
+        for entry_id in self.entry_ids(id).await? {
+            // Memberships outlive entries removed from a Markdown folder by hand
+            if let Some(entry) = self.database.get_entry(&entry_id).await? {
+                if filter.matches(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Entries in the collection matching `query`, in collection order
+    pub async fn search(
+        &self,
+        id: &str,
+        query: &str,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        self.require(id).await?;
+
+        let positions: HashMap<String, usize> = self
+            .entry_ids(id)
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(position, entry_id)| (entry_id, position))
+            .collect();
+
+        let mut entries: Vec<JournalEntry> = self
+            .database
+            .search_entries(query, filter)
+            .await?
+            .into_iter()
+            .filter(|entry| positions.contains_key(&entry.id))
+            .collect();
+        entries.sort_by_key(|entry| positions[&entry.id]);
+
+        Ok(entries)
+    }
+
+    /// The collections an entry belongs to
+    pub async fn for_entry(&self, entry_id: &str) -> Result<Vec<Collection>> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE c.id IN (SELECT collection_id FROM collection_entries WHERE entry_id = ?)
+             ORDER BY c.position, c.name COLLATE NOCASE",
+            SELECT_COLLECTIONS
+        ))
+        .bind(entry_id)
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to list the collections of an entry")?;
+
+        rows.iter().map(collection_from_row).collect()
+    }
+
+    /// Drop a deleted entry from every collection
+    pub async fn forget_entry(&self, entry_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM collection_entries WHERE entry_id = ?")
+            .bind(entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to remove entry from its collections")?;
+
+        Ok(())
+    }
+
+    /// Write the collection and its entries, in order, to `path`
+    pub async fn export(
+        &self,
+        id: &str,
+        path: &Path,
+        format: ExportFormat,
+    ) -> Result<CollectionExport> {
+        let collection = self.require(id).await?;
+        let entries = self.entries(id, &EntryFilter::default()).await?;
+
+        let contents = match format {
+            ExportFormat::Markdown => export_markdown(&collection, &entries),
+            ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+                "collection": collection,
+                "entries": entries,
+            }))?,
+        };
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        tokio::fs::write(path, contents)
+            .await
+            .with_context(|| format!("Failed to write {:?}", path))?;
+
+        Ok(CollectionExport {
+            path: path.to_string_lossy().to_string(),
+            format,
+            entries: entries.len(),
+        })
+    }
+
+    async fn entry_ids(&self, id: &str) -> Result<Vec<String>> {
+        sqlx::query_scalar(
+            "SELECT entry_id FROM collection_entries WHERE collection_id = ? ORDER BY position, added_at",
+        )
+        .bind(id)
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to list collection entries")
+    }
+
+    async fn require(&self, id: &str) -> Result<Collection> {
+        self.get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Collection not found: {}", id))
+    }
+
+    /// Mark the collection as changed and return it
+    async fn touch(&self, id: &str) -> Result<Collection> {
+        sqlx::query("UPDATE collections SET updated_at = ? WHERE id = ?")
+            .bind(format_timestamp(&Utc::now()))
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to update collection")?;
+
+        self.require(id).await
+    }
+}
+
+/// Counts only memberships whose entry still exists
+const SELECT_COLLECTIONS: &str = r#"
+    SELECT c.*, (
+        SELECT COUNT(*) FROM collection_entries ce
+        JOIN journal_entries e ON e.id = ce.entry_id
+        WHERE ce.collection_id = c.id
+    ) AS entry_count
+    FROM collections c
+"#;
+
+fn collection_from_row(row: &SqliteRow) -> Result<Collection> {
+    let id: String = row.get("id");
+    Ok(Collection {
+        is_builtin: id == DOCUMENTS_COLLECTION,
+        id,
+        name: row.get("name"),
+        description: row.get("description"),
+        position: row.get("position"),
+        entry_count: row.get("entry_count"),
+        created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
+        updated_at: parse_timestamp(&row.get::<String, _>("updated_at"))?,
+    })
+}
+
+fn normalize(input: &CollectionInput) -> Result<CollectionInput> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Collection name cannot be empty"));
+    }
+
+    Ok(CollectionInput {
+        name: name.to_string(),
+        description: input
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|description| !description.is_empty())
+            .map(str::to_string),
+    })
+}
+
+/// A new order has to mention every current member exactly once
+pub fn ensure_same_members(existing: &[String], ordered: &[String], kind: &str) -> Result<()> {
+    let existing: HashSet<&String> = existing.iter().collect();
+    let mut seen = HashSet::new();
+
+    for id in ordered {
+        if !existing.contains(id) {
+            return Err(anyhow::anyhow!("Unknown {}: {}", kind, id));
+        }
+        if !seen.insert(id) {
+            return Err(anyhow::anyhow!("The order lists {} {} twice", kind, id));
+        }
+    }
+    if seen.len() != existing.len() {
+        return Err(anyhow::anyhow!("The new order must include every {}", kind));
+    }
+
+    Ok(())
+}
+
+fn export_markdown(collection: &Collection, entries: &[JournalEntry]) -> String {
+    let mut text = format!("# {}\n", collection.name);
+    if let Some(description) = &collection.description {
+        text.push_str(&format!("\n{}\n", description));
+    }
+
+    for entry in entries {
+        text.push_str(&format!(
+            "\n## {}\n\n*{}*\n\n{}\n",
+            entry.title,
+            entry.entry_date.format("%A, %-d %B %Y"),
+            entry.content.trim_end()
+        ));
+    }
+
+    text
+}
@@ -3,32 +3,141 @@
  *
  * This module handles:
  * - SQLite database initialization and management
- * - Journal entry CRUD operations
+ * - Journal entry operations on top of the journal's storage backend
+ * - Collecting entry changes for the entries:// events
  * - Encryption and security
  * - Schema migrations
  */
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqliteConnectOptions, Row, SqlitePool};
+use sqlx::{Row, SqliteConnection, SqlitePool};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::analytics::AnalyticsRecorder;
+use crate::collections::CollectionStore;
+use crate::duplicates::DuplicateDetector;
+use crate::embedding_index::EmbeddingIndex;
+use crate::entry_events::EntryChanges;
+use crate::git_history::{GitCommit, GitRepository};
+use crate::locations::{self, Bounds, EntryLocation, Place};
+use crate::maintenance;
+use crate::markdown_storage::{MarkdownStorage, ReindexReport};
+use crate::moods::MoodRegistry;
+use crate::resurfacing::Resurfacer;
+use crate::semantic_search::VectorIndex;
+use crate::settings::{JournalTimezone, SettingsStore};
+use crate::statistics::StatisticsEngine;
+use crate::storage::{EntryFilter, EntryStorage, SqliteStorage, Storage};
+use crate::sync::SyncEngine;
+
+/// Set when the daily aggregates could not be kept in step with the entries
+const STATISTICS_STALE_SETTING: &str = "statistics_stale";
+
+/// SQL migrations, embedded so packaged builds don't depend on the working directory
+const MIGRATIONS: &[(&str, &str)] = &[
+    ("001_initial_schema.sql", include_str!("../migrations/001_initial_schema.sql")),
+    ("002_add_search_indexes.sql", include_str!("../migrations/002_add_search_indexes.sql")),
+    // 003_add_analytics.sql can't be parsed by SQLite; 003a applies in its place
+    ("003a_add_analytics.sql", include_str!("../migrations/003a_add_analytics.sql")),
+    ("004_add_source_fields.sql", include_str!("../migrations/004_add_source_fields.sql")),
+    ("005_add_mood_registry.sql", include_str!("../migrations/005_add_mood_registry.sql")),
+    ("006_fix_search_triggers.sql", include_str!("../migrations/006_fix_search_triggers.sql")),
+    ("007_ensure_app_settings.sql", include_str!("../migrations/007_ensure_app_settings.sql")),
+    ("008_add_entry_dates.sql", include_str!("../migrations/008_add_entry_dates.sql")),
+    ("009_add_markdown_files.sql", include_str!("../migrations/009_add_markdown_files.sql")),
+    ("011_add_sync.sql", include_str!("../migrations/011_add_sync.sql")),
+    ("012_add_entry_templates.sql", include_str!("../migrations/012_add_entry_templates.sql")),
+    ("013_add_reminders.sql", include_str!("../migrations/013_add_reminders.sql")),
+    ("014_add_entry_locations.sql", include_str!("../migrations/014_add_entry_locations.sql")),
+    ("015_add_entry_states.sql", include_str!("../migrations/015_add_entry_states.sql")),
+    ("016_add_collections.sql", include_str!("../migrations/016_add_collections.sql")),
+    ("017_add_saved_searches.sql", include_str!("../migrations/017_add_saved_searches.sql")),
+    ("018_add_entry_views.sql", include_str!("../migrations/018_add_entry_views.sql")),
+    ("019_add_duplicate_links.sql", include_str!("../migrations/019_add_duplicate_links.sql")),
+    ("020_rebuild_embeddings.sql", include_str!("../migrations/020_rebuild_embeddings.sql")),
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct JournalEntry {
     pub id: String,
     pub title: String,
     pub content: String,
     pub tags: Vec<String>,
     pub mood: Option<String>,
+    /// How strongly the mood was felt, from 0.0 to 1.0
+    #[serde(default)]
+    pub mood_intensity: Option<f64>,
     pub privacy: String,
     pub source: Option<String>,
     pub source_id: Option<String>,
     pub source_url: Option<String>,
     pub metadata: Option<serde_json::Value>,
+    /// Where the entry was written
+    #[serde(default)]
+    pub location: Option<EntryLocation>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub favorite: bool,
+    /// Hidden from the journal unless archived entries are asked for
+    #[serde(default)]
+    pub archived: bool,
+    /// The editor refuses to change the entry until it is unlocked
+    #[serde(default)]
+    pub locked: bool,
+    /// When the entry is about, in the UTC offset it was written in; differs
+    /// from `created_at` for backdated and imported entries. Entries saved
+    /// before it existed read it as `created_at`.
+    pub entry_date: DateTime<FixedOffset>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Serialize for JournalEntry {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        JournalEntry::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JournalEntry {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        if let Some(fields) = value.as_object_mut() {
+            let missing = match fields.get("entry_date") {
+                Some(entry_date) => entry_date.is_null(),
+                None => true,
+            };
+            if missing {
+                if let Some(created_at) = fields.get("created_at").cloned() {
+                    fields.insert("entry_date".to_string(), created_at);
+                }
+            }
+        }
+
+        JournalEntry::deserialize(value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl JournalEntry {
+    /// Brought in from another service rather than written in the app
+    pub fn is_imported(&self) -> bool {
+        is_imported_source(self.source.as_deref())
+    }
+}
+
+/// Entries from any source but the app itself are imported
+pub fn is_imported_source(source: Option<&str>) -> bool {
+    matches!(source, Some(source) if !source.is_empty() && source != "local")
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EchoPattern {
     pub id: String,
@@ -44,8 +153,9 @@ pub struct EchoPattern {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Embedding {
-    pub id: String,
     pub entry_id: String,
+    pub model_name: String,
+    /// Hash of the text the vector was made from
     pub content_hash: String,
     pub embedding_vector: Vec<f32>,
     pub created_at: DateTime<Utc>,
@@ -53,11 +163,36 @@ pub struct Embedding {
 
 pub struct Database {
     pool: SqlitePool,
+    storage: Storage,
+    /// Entry changes not yet announced to the windows
+    changes: Mutex<EntryChanges>,
+    /// Embeddings loaded for the last semantic search
+    vectors: Mutex<Option<Arc<VectorIndex>>>,
+    /// The timezone setting, shared with the storage backend
+    timezone: Arc<RwLock<JournalTimezone>>,
 }
 
 impl Database {
     /// Initialize database connection and run migrations
     pub async fn new(database_path: PathBuf) -> Result<Self> {
+        Self::open(database_path, None, false).await
+    }
+
+    /// Open a journal kept as Markdown files in `folder`, indexed in the database at `index_path`
+    pub async fn with_markdown_folder(index_path: PathBuf, folder: PathBuf) -> Result<Self> {
+        Self::open(index_path, Some(folder), false).await
+    }
+
+    /// Open a Markdown journal whose folder is a git repository with every change committed
+    pub async fn with_git_folder(index_path: PathBuf, folder: PathBuf) -> Result<Self> {
+        Self::open(index_path, Some(folder), true).await
+    }
+
+    async fn open(
+        database_path: PathBuf,
+        markdown_folder: Option<PathBuf>,
+        history: bool,
+    ) -> Result<Self> {
         // Create database directory if it doesn't exist
         if let Some(parent) = database_path.parent() {
             tokio::fs::create_dir_all(parent)
@@ -66,346 +201,546 @@ impl Database {
         }
 
         // Configure SQLite connection
-        let connection_options = SqliteConnectOptions::new()
-            .filename(&database_path)
-            .create_if_missing(true);
+        let connection_options = maintenance::connect_options(&database_path);
 
         let pool = SqlitePool::connect_with(connection_options)
             .await
             .context("Failed to connect to database")?;
 
-        let db = Database { pool };
-
-        // Run SQL migrations from files (or fallback to embedded schema)
+        let timezone = Arc::new(RwLock::new(JournalTimezone::System));
+        let storage = match markdown_folder {
+            Some(folder) => {
+                let git = if history {
+                    Some(GitRepository::open(&folder).await?)
+                } else {
+                    None
+                };
+                Storage::Markdown(MarkdownStorage::new(
+                    pool.clone(),
+                    folder,
+                    git,
+                    timezone.clone(),
+                ))
+            }
+            None => Storage::Sqlite(SqliteStorage::new(pool.clone(), timezone.clone())),
+        };
+        let db = Database {
+            pool,
+            storage,
+            changes: Mutex::new(EntryChanges::default()),
+            vectors: Mutex::new(None),
+            timezone,
+        };
+
+        // Run SQL migrations
         db.run_file_migrations()
             .await
             .context("Failed to run database migrations")?;
 
+        // Entry dates saved without an offset are read in the journal timezone
+        let timezone = SettingsStore::new(&db).timezone().await?;
+        db.set_timezone(timezone);
+
+        // Pick up files that changed while the journal was closed
+        db.reindex()
+            .await
+            .context("Failed to index journal files")?;
+
+        // Backfill statistics for journals written before they were tracked
+        db.rebuild_stale_statistics()
+            .await
+            .context("Failed to rebuild journal statistics")?;
+
+        // Fold legacy free-form moods into the registry
+        MoodRegistry::new(&db)
+            .migrate_legacy_moods()
+            .await
+            .context("Failed to migrate legacy moods")?;
+
+        AnalyticsRecorder::new(&db)
+            .prune()
+            .await
+            .context("Failed to prune analytics events")?;
+
+        // Windows reload the whole journal when it is opened
+        db.take_entry_changes();
+
         Ok(db)
     }
 
-    /// Run database migrations
-    async fn run_sql_migrations(&self) -> Result<()> {
-        // Create journal_entries table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS journal_entries (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                content TEXT NOT NULL,
-                tags TEXT NOT NULL DEFAULT '[]',
-                mood TEXT,
-                privacy TEXT NOT NULL DEFAULT 'private',
-                source TEXT,
-                source_id TEXT,
-                source_url TEXT,
-                metadata TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create journal_entries table")?;
+    /// Run database migrations that have not been applied yet
+    async fn run_file_migrations(&self) -> Result<()> {
+        // Every step runs on one connection: another pooled connection that
+        // loaded the schema before a column was added would prepare
+        // `SELECT *` statements with the old columns
+        let mut connection = self
+            .pool
+            .acquire()
+            .await
+            .context("Failed to acquire a connection for migrations")?;
 
-        // Create echo_patterns table
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS echo_patterns (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                description TEXT NOT NULL,
-                strength REAL NOT NULL,
-                entries TEXT NOT NULL DEFAULT '[]',
-                tags TEXT NOT NULL DEFAULT '[]',
-                pattern_type TEXT NOT NULL,
-                last_seen TEXT NOT NULL,
-                created_at TEXT NOT NULL
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                name TEXT PRIMARY KEY,
+                applied_at TEXT NOT NULL
             )
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *connection)
         .await
-        .context("Failed to create echo_patterns table")?;
+        .context("Failed to create schema_migrations table")?;
+
+        // Databases created by the old embedded schema already have their own
+        // versions of the 001 tables, and its indexes don't apply to them
+        if !Self::migration_applied(&mut connection, MIGRATIONS[0].0).await?
+            && Self::has_legacy_schema(&mut connection).await?
+        {
+            println!("Legacy schema detected, skipping {}", MIGRATIONS[0].0);
+            Self::mark_migration_applied(&mut connection, MIGRATIONS[0].0).await?;
+        }
 
-        // Create embeddings table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS embeddings (
-                id TEXT PRIMARY KEY,
-                entry_id TEXT NOT NULL,
-                content_hash TEXT NOT NULL,
-                embedding_vector TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (entry_id) REFERENCES journal_entries (id)
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create embeddings table")?;
+        for (name, sql) in MIGRATIONS {
+            if Self::migration_applied(&mut connection, name).await? {
+                continue;
+            }
 
-        // Create indexes for better performance
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_journal_entries_created_at ON journal_entries (created_at)")
-            .execute(&self.pool)
-            .await
-            .context("Failed to create journal_entries index")?;
+            println!("Running migration: {}", name);
+            let sql = Self::without_existing_columns(&mut connection, sql).await?;
 
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_journal_entries_tags ON journal_entries (tags)",
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create journal_entries tags index")?;
+            // A migration that fails partway must not leave half its
+            // statements behind, or be recorded as applied
+            sqlx::query("BEGIN IMMEDIATE")
+                .execute(&mut *connection)
+                .await
+                .context("Failed to start migration transaction")?;
+            let applied = async {
+                sqlx::query(&sql)
+                    .execute(&mut *connection)
+                    .await
+                    .with_context(|| format!("Failed to execute migration: {}", name))?;
+                Self::mark_migration_applied(&mut connection, name).await
+            }
+            .await;
+
+            match applied {
+                Ok(()) => {
+                    sqlx::query("COMMIT")
+                        .execute(&mut *connection)
+                        .await
+                        .with_context(|| format!("Failed to commit migration: {}", name))?;
+                }
+                Err(e) => {
+                    let _ = sqlx::query("ROLLBACK").execute(&mut *connection).await;
+                    return Err(e);
+                }
+            }
+            println!("Migration completed: {}", name);
+        }
+
+        println!("All database migrations completed successfully");
+        Ok(())
+    }
+
+    async fn migration_applied(connection: &mut SqliteConnection, name: &str) -> Result<bool> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM schema_migrations WHERE name = ?")
+                .bind(name)
+                .fetch_one(connection)
+                .await
+                .context("Failed to read schema_migrations")?;
+
+        Ok(count > 0)
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_embeddings_entry_id ON embeddings (entry_id)")
-            .execute(&self.pool)
+    async fn mark_migration_applied(connection: &mut SqliteConnection, name: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO schema_migrations (name, applied_at) VALUES (?, ?)")
+            .bind(name)
+            .bind(format_timestamp(&Utc::now()))
+            .execute(connection)
             .await
-            .context("Failed to create embeddings index")?;
+            .context("Failed to record migration")?;
 
-        println!("Legacy schema created successfully");
         Ok(())
     }
 
-    /// Run database migrations using SQL files
-    async fn run_file_migrations(&self) -> Result<()> {
-        // Get the migrations directory path  
-        let migrations_path = PathBuf::from("migrations");
-        
-        if !migrations_path.exists() {
-            println!("No migrations directory found, using embedded schema");
-            return self.run_sql_migrations().await;
+    /// `sql` without the `ALTER TABLE ... ADD COLUMN` statements whose column
+    /// already exists. The old embedded schema created some of the columns
+    /// later migrations add, and SQLite has no `ADD COLUMN IF NOT EXISTS`.
+    async fn without_existing_columns(
+        connection: &mut SqliteConnection,
+        sql: &str,
+    ) -> Result<String> {
+        let mut kept = Vec::new();
+        for line in sql.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let adds_column = words.len() > 5
+                && words[0].eq_ignore_ascii_case("ALTER")
+                && words[1].eq_ignore_ascii_case("TABLE")
+                && words[3].eq_ignore_ascii_case("ADD")
+                && words[4].eq_ignore_ascii_case("COLUMN");
+
+            if adds_column {
+                let count: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?",
+                )
+                .bind(words[2])
+                .bind(words[5].trim_end_matches(';'))
+                .fetch_one(&mut *connection)
+                .await
+                .context("Failed to inspect table columns")?;
+
+                if count > 0 {
+                    println!("Column {}.{} already exists", words[2], words[5]);
+                    continue;
+                }
+            }
+            kept.push(line);
         }
 
-        // Read and execute each migration file in order
-        let mut migration_files = tokio::fs::read_dir(&migrations_path)
-            .await
-            .context("Failed to read migrations directory")?;
+        Ok(kept.join("\n"))
+    }
+
+    /// The old embedded schema stored embeddings as `content_hash` + `embedding_vector`
+    async fn has_legacy_schema(connection: &mut SqliteConnection) -> Result<bool> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('embeddings') WHERE name = 'content_hash'",
+        )
+        .fetch_one(connection)
+        .await
+        .context("Failed to inspect embeddings table")?;
 
-        let mut files = Vec::new();
-        while let Some(entry) = migration_files.next_entry().await? {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("sql") {
-                files.push(path);
+        Ok(count > 0)
+    }
+
+    /// Close every pooled connection, e.g. before switching journals
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// Bring the index of a Markdown journal up to date with its folder; `None` for SQLite journals
+    pub async fn reindex(&self) -> Result<Option<ReindexReport>> {
+        let Storage::Markdown(storage) = &self.storage else {
+            return Ok(None);
+        };
+
+        let report = storage.reindex().await?;
+        if report.changed() {
+            self.rebuild_statistics().await?;
+            self.record_changes(|changes| changes.merge(&report.changes));
+
+            let index = EmbeddingIndex::new(self);
+            for id in &report.changes.deleted {
+                index.forget_entry(id).await?;
             }
+            index.enqueue(&report.changes.created, false).await?;
+            index.enqueue(&report.changes.updated, false).await?;
         }
 
-        // Sort files by name to ensure correct order
-        files.sort();
+        Ok(Some(report))
+    }
 
-        for file_path in files {
-            println!("Running migration: {:?}", file_path.file_name());
-            let sql = tokio::fs::read_to_string(&file_path)
-                .await
-                .context(format!("Failed to read migration file: {:?}", file_path))?;
+    /// The git repository of a journal that keeps its history, if any
+    pub fn git(&self) -> Option<&GitRepository> {
+        match &self.storage {
+            Storage::Markdown(storage) => storage.git(),
+            Storage::Sqlite(_) => None,
+        }
+    }
 
-            // Execute the migration SQL
-            sqlx::query(&sql)
-                .execute(&self.pool)
-                .await
-                .context(format!("Failed to execute migration: {:?}", file_path))?;
+    /// Entries changed since the last call, for the entries:// events
+    pub fn take_entry_changes(&self) -> EntryChanges {
+        std::mem::take(&mut *self.changes.lock().unwrap_or_else(|e| e.into_inner()))
+    }
 
-            println!("Migration completed: {:?}", file_path.file_name());
-        }
+    fn record_changes(&self, record: impl FnOnce(&mut EntryChanges)) {
+        record(&mut self.changes.lock().unwrap_or_else(|e| e.into_inner()));
+    }
 
-        println!("All database migrations completed successfully");
-        Ok(())
+    pub fn cached_vectors(&self) -> Option<Arc<VectorIndex>> {
+        self.vectors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn cache_vectors(&self, vectors: Arc<VectorIndex>) {
+        *self.vectors.lock().unwrap_or_else(|e| e.into_inner()) = Some(vectors);
+    }
+
+    /// The timezone days are counted in, from the settings
+    pub fn timezone(&self) -> JournalTimezone {
+        *self.timezone.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Use a new timezone setting, e.g. after the settings are saved
+    pub fn set_timezone(&self, timezone: JournalTimezone) {
+        *self.timezone.write().unwrap_or_else(|e| e.into_inner()) = timezone;
+    }
+
+    /// Access the underlying connection pool for the subsystems built on top of it
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Daily aggregates kept in `journal_statistics`
+    pub fn statistics(&self) -> StatisticsEngine<'_> {
+        StatisticsEngine::new(&self.pool)
     }
 
     // Journal Entry Operations
     pub async fn create_entry(&self, entry: &JournalEntry) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO journal_entries (id, title, content, tags, mood, privacy, source, source_id, source_url, metadata, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#
-        )
-        .bind(&entry.id)
-        .bind(&entry.title)
-        .bind(&entry.content)
-        .bind(serde_json::to_string(&entry.tags)?)
-        .bind(&entry.mood)
-        .bind(&entry.privacy)
-        .bind(&entry.source)
-        .bind(&entry.source_id)
-        .bind(&entry.source_url)
-        .bind(entry.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_default()))
-        .bind(entry.created_at.to_rfc3339())
-        .bind(entry.updated_at.to_rfc3339())
-        .execute(&self.pool)
-        .await
-        .context("Failed to create journal entry")?;
-
-        Ok(())
+        self.insert_entry(entry).await?;
+        SyncEngine::new(self).record_change(&entry.id, false).await
     }
 
     pub async fn get_entry(&self, id: &str) -> Result<Option<JournalEntry>> {
-        let row = sqlx::query("SELECT * FROM journal_entries WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await
-            .context("Failed to fetch journal entry")?;
+        self.storage.get_entry(id).await
+    }
 
-        if let Some(row) = row {
-            Ok(Some(JournalEntry {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
-                mood: row.get("mood"),
-                privacy: row.get("privacy"),
-                source: row.get("source"),
-                source_id: row.get("source_id"),
-                source_url: row.get("source_url"),
-                metadata: row.get::<Option<String>, _>("metadata")
-                    .and_then(|m| serde_json::from_str(&m).ok()),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .into(),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
-                    .into(),
-            }))
+    pub async fn update_entry(&self, entry: &JournalEntry) -> Result<()> {
+        self.replace_entry(entry).await?;
+        SyncEngine::new(self).record_change(&entry.id, false).await
+    }
+
+    pub async fn delete_entry(&self, id: &str) -> Result<()> {
+        self.remove_entry(id).await?;
+        SyncEngine::new(self).record_change(id, true).await
+    }
+
+    /// Write an entry received from another device without recording it as a local change
+    pub async fn apply_synced_entry(&self, entry: &JournalEntry) -> Result<()> {
+        if self.get_entry(&entry.id).await?.is_some() {
+            self.replace_entry(entry).await
         } else {
-            Ok(None)
+            self.insert_entry(entry).await
         }
     }
 
-    pub async fn update_entry(&self, entry: &JournalEntry) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE journal_entries 
-            SET title = ?, content = ?, tags = ?, mood = ?, privacy = ?, source = ?, source_id = ?, source_url = ?, metadata = ?, updated_at = ?
-            WHERE id = ?
-            "#,
-        )
-        .bind(&entry.title)
-        .bind(&entry.content)
-        .bind(serde_json::to_string(&entry.tags)?)
-        .bind(&entry.mood)
-        .bind(&entry.privacy)
-        .bind(&entry.source)
-        .bind(&entry.source_id)
-        .bind(&entry.source_url)
-        .bind(entry.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_default()))
-        .bind(entry.updated_at.to_rfc3339())
-        .bind(&entry.id)
-        .execute(&self.pool)
-        .await
-        .context("Failed to update journal entry")?;
+    /// Delete an entry removed on another device without recording it as a local change
+    pub async fn apply_synced_delete(&self, id: &str) -> Result<()> {
+        self.remove_entry(id).await
+    }
+
+    async fn insert_entry(&self, entry: &JournalEntry) -> Result<()> {
+        self.storage.create_entry(entry).await?;
+        self.record_changes(|changes| changes.record_created(&entry.id));
+        self.keep_statistics(self.statistics().record_entry(entry).await)
+            .await?;
+        EmbeddingIndex::new(self)
+            .enqueue(std::slice::from_ref(&entry.id), false)
+            .await?;
 
         Ok(())
     }
 
-    pub async fn delete_entry(&self, id: &str) -> Result<()> {
-        sqlx::query("DELETE FROM journal_entries WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .context("Failed to delete journal entry")?;
+    async fn replace_entry(&self, entry: &JournalEntry) -> Result<()> {
+        let previous = self.get_entry(&entry.id).await?;
 
-        // Also delete associated embeddings
-        sqlx::query("DELETE FROM embeddings WHERE entry_id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .context("Failed to delete associated embeddings")?;
+        self.storage.update_entry(entry).await?;
+        self.record_changes(|changes| changes.record_updated(&entry.id));
+        EmbeddingIndex::new(self)
+            .enqueue(std::slice::from_ref(&entry.id), false)
+            .await?;
+
+        if let Some(previous) = previous {
+            self.keep_statistics(self.statistics().update_entry(&previous, entry).await)
+                .await?;
+        }
 
         Ok(())
     }
 
+    async fn remove_entry(&self, id: &str) -> Result<()> {
+        let previous = self.get_entry(id).await?;
+
+        self.storage.delete_entry(id).await?;
+        self.record_changes(|changes| changes.record_deleted(id));
+        CollectionStore::new(self).forget_entry(id).await?;
+        Resurfacer::new(self).forget_entry(id).await?;
+        DuplicateDetector::new(self).forget_entry(id).await?;
+        EmbeddingIndex::new(self).forget_entry(id).await?;
+
+        if let Some(previous) = previous {
+            self.keep_statistics(self.statistics().remove_entry(&previous).await)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Entry History Operations
+    /// Commits that changed an entry, newest first
+    pub async fn entry_history(&self, id: &str) -> Result<Vec<GitCommit>> {
+        self.history_storage()?.history(id).await
+    }
+
+    /// An entry as it was saved in `commit`
+    pub async fn entry_version(&self, id: &str, commit: &str) -> Result<JournalEntry> {
+        self.history_storage()?.version_at(id, commit).await
+    }
+
+    /// Make the version saved in `commit` the current one, bringing the entry back if it was deleted
+    pub async fn restore_entry(&self, id: &str, commit: &str) -> Result<JournalEntry> {
+        let storage = self.history_storage()?;
+        let mut entry = storage.version_at(id, commit).await?;
+        entry.updated_at = Utc::now();
+
+        let previous = self.get_entry(id).await?;
+        storage.restore_entry(&entry, commit).await?;
+        let update = match previous {
+            Some(previous) => {
+                self.record_changes(|changes| changes.record_updated(id));
+                self.statistics().update_entry(&previous, &entry).await
+            }
+            None => {
+                self.record_changes(|changes| changes.record_created(id));
+                self.statistics().record_entry(&entry).await
+            }
+        };
+        self.keep_statistics(update).await?;
+        SyncEngine::new(self).record_change(id, false).await?;
+        EmbeddingIndex::new(self)
+            .enqueue(&[id.to_string()], false)
+            .await?;
+
+        Ok(entry)
+    }
+
+    fn history_storage(&self) -> Result<&MarkdownStorage> {
+        match &self.storage {
+            Storage::Markdown(storage) => Ok(storage),
+            Storage::Sqlite(_) => Err(anyhow::anyhow!("This journal does not keep a git history")),
+        }
+    }
+
     pub async fn list_entries(
         &self,
         limit: Option<i64>,
         offset: Option<i64>,
+        filter: &EntryFilter,
     ) -> Result<Vec<JournalEntry>> {
-        let limit = limit.unwrap_or(100);
-        let offset = offset.unwrap_or(0);
+        self.storage
+            .list_entries(limit.unwrap_or(100), offset.unwrap_or(0), filter)
+            .await
+    }
 
-        let rows =
-            sqlx::query("SELECT * FROM journal_entries ORDER BY created_at DESC LIMIT ? OFFSET ?")
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(&self.pool)
-                .await
-                .context("Failed to list journal entries")?;
+    /// Every entry, newest first
+    pub async fn list_all_entries(&self) -> Result<Vec<JournalEntry>> {
+        // SQLite treats a negative LIMIT as "no limit"
+        self.list_entries(Some(-1), None, &EntryFilter::default())
+            .await
+    }
 
-        let mut entries = Vec::new();
-        for row in rows {
-            entries.push(JournalEntry {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
-                mood: row.get("mood"),
-                privacy: row.get("privacy"),
-                source: row.get("source"),
-                source_id: row.get("source_id"),
-                source_url: row.get("source_url"),
-                metadata: row.get::<Option<String>, _>("metadata")
-                    .and_then(|m| serde_json::from_str(&m).ok()),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .into(),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
-                    .into(),
-            });
+    /// Apply `change` to each of `ids` and save the entries it changed, which are returned
+    pub async fn update_entries(
+        &self,
+        ids: &[String],
+        change: impl Fn(&mut JournalEntry),
+    ) -> Result<Vec<JournalEntry>> {
+        let mut changed = Vec::new();
+        for id in ids {
+            let Some(mut entry) = self.get_entry(id).await? else {
+                continue;
+            };
+
+            let before = serde_json::to_value(&entry)?;
+            change(&mut entry);
+            if serde_json::to_value(&entry)? == before {
+                continue;
+            }
+
+            entry.updated_at = Utc::now();
+            self.update_entry(&entry).await?;
+            changed.push(entry);
         }
 
-        Ok(entries)
+        Ok(changed)
     }
 
-    pub async fn search_entries(&self, query: &str) -> Result<Vec<JournalEntry>> {
-        let search_term = format!("%{}%", query);
+    pub async fn rebuild_statistics(&self) -> Result<()> {
+        let entries = self.list_all_entries().await?;
+        self.statistics().rebuild(&entries).await?;
+        self.set_setting(STATISTICS_STALE_SETTING, "false").await
+    }
 
-        let rows = sqlx::query(
-            "SELECT * FROM journal_entries 
-             WHERE title LIKE ? OR content LIKE ? OR tags LIKE ? 
-             ORDER BY created_at DESC",
-        )
-        .bind(&search_term)
-        .bind(&search_term)
-        .bind(&search_term)
-        .fetch_all(&self.pool)
-        .await
-        .context("Failed to search journal entries")?;
+    /// Rebuild the daily aggregates if they were never built or fell out of step
+    pub async fn rebuild_stale_statistics(&self) -> Result<bool> {
+        let stale = self.get_setting(STATISTICS_STALE_SETTING).await?;
+        if stale.as_deref() != Some("true") && !self.statistics().is_empty().await? {
+            return Ok(false);
+        }
 
-        let mut entries = Vec::new();
-        for row in rows {
-            entries.push(JournalEntry {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
-                mood: row.get("mood"),
-                privacy: row.get("privacy"),
-                source: row.get("source"),
-                source_id: row.get("source_id"),
-                source_url: row.get("source_url"),
-                metadata: row.get::<Option<String>, _>("metadata")
-                    .and_then(|m| serde_json::from_str(&m).ok()),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .into(),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
-                    .into(),
-            });
+        self.rebuild_statistics().await?;
+        Ok(true)
+    }
+
+    /// The daily aggregates are updated after the entry write they follow and
+    /// can't share its transaction, so an update that fails is made good by a
+    /// rebuild, or left for `rebuild_stale_statistics` if that fails as well
+    async fn keep_statistics(&self, update: Result<()>) -> Result<()> {
+        let Err(error) = update else {
+            return Ok(());
+        };
+
+        eprintln!(
+            "Failed to update journal statistics, rebuilding them: {}",
+            error
+        );
+        if self.rebuild_statistics().await.is_ok() {
+            return Ok(());
         }
+        self.set_setting(STATISTICS_STALE_SETTING, "true").await?;
+        Err(error)
+    }
+
+    pub async fn search_entries(
+        &self,
+        query: &str,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        self.storage.search_entries(query, filter).await
+    }
+
+    // Location Operations
+    /// Entries located inside `bounds`, newest first
+    pub async fn list_entries_in_bounds(
+        &self,
+        bounds: &Bounds,
+        limit: Option<i64>,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        bounds.validate()?;
+        self.storage
+            .list_entries_in_bounds(bounds, limit.unwrap_or(500), filter)
+            .await
+    }
 
-        Ok(entries)
+    /// Distinct places entries were written at, with entry counts
+    pub async fn list_places(&self) -> Result<Vec<Place>> {
+        locations::list_places(&self.pool).await
     }
 
     // Embedding Operations
-    #[allow(dead_code)]
     pub async fn store_embedding(&self, embedding: &Embedding) -> Result<()> {
+        let data: Vec<u8> = embedding
+            .embedding_vector
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO embeddings (id, entry_id, content_hash, embedding_vector, created_at)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT OR REPLACE INTO embeddings (entry_id, model_name, content_hash, dimension, embedding_data, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#
         )
-        .bind(&embedding.id)
         .bind(&embedding.entry_id)
+        .bind(&embedding.model_name)
         .bind(&embedding.content_hash)
-        .bind(serde_json::to_string(&embedding.embedding_vector)?)
-        .bind(embedding.created_at.to_rfc3339())
+        .bind(embedding.embedding_vector.len() as i64)
+        .bind(data)
+        .bind(format_timestamp(&embedding.created_at))
         .execute(&self.pool)
         .await
         .context("Failed to store embedding")?;
@@ -413,22 +748,25 @@ impl Database {
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub async fn get_embedding(&self, entry_id: &str) -> Result<Option<Embedding>> {
-        let row = sqlx::query("SELECT * FROM embeddings WHERE entry_id = ?")
+    pub async fn get_embedding(
+        &self,
+        entry_id: &str,
+        model_name: &str,
+    ) -> Result<Option<Embedding>> {
+        let row = sqlx::query("SELECT * FROM embeddings WHERE entry_id = ? AND model_name = ?")
             .bind(entry_id)
+            .bind(model_name)
             .fetch_optional(&self.pool)
             .await
             .context("Failed to fetch embedding")?;
 
         if let Some(row) = row {
             Ok(Some(Embedding {
-                id: row.get("id"),
                 entry_id: row.get("entry_id"),
+                model_name: row.get("model_name"),
                 content_hash: row.get("content_hash"),
-                embedding_vector: serde_json::from_str(&row.get::<String, _>("embedding_vector"))?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .into(),
+                embedding_vector: decode_embedding(&row.get::<Vec<u8>, _>("embedding_data")),
+                created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
             }))
         } else {
             Ok(None)
@@ -451,8 +789,8 @@ impl Database {
         .bind(serde_json::to_string(&pattern.entries)?)
         .bind(serde_json::to_string(&pattern.tags)?)
         .bind(&pattern.pattern_type)
-        .bind(pattern.last_seen.to_rfc3339())
-        .bind(pattern.created_at.to_rfc3339())
+        .bind(format_timestamp(&pattern.last_seen))
+        .bind(format_timestamp(&pattern.created_at))
         .execute(&self.pool)
         .await
         .context("Failed to create echo pattern")?;
@@ -478,17 +816,41 @@ impl Database {
                 entries: serde_json::from_str(&row.get::<String, _>("entries")).unwrap_or_default(),
                 tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
                 pattern_type: row.get("pattern_type"),
-                last_seen: DateTime::parse_from_rfc3339(&row.get::<String, _>("last_seen"))?.into(),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .into(),
+                last_seen: parse_timestamp(&row.get::<String, _>("last_seen"))?,
+                created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
             });
         }
 
         Ok(patterns)
     }
 
+    // App Settings Operations
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        sqlx::query_scalar("SELECT value FROM app_settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to read app setting")
+    }
+
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO app_settings (key, value, updated_at) VALUES (?, ?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(key)
+        .bind(value)
+        .bind(format_timestamp(&Utc::now()))
+        .execute(&self.pool)
+        .await
+        .context("Failed to write app setting")?;
+
+        Ok(())
+    }
+
     /// Get database statistics
-    #[allow(dead_code)]
     pub async fn get_stats(&self) -> Result<serde_json::Value> {
         let entry_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM journal_entries")
             .fetch_one(&self.pool)
@@ -512,3 +874,132 @@ impl Database {
         }))
     }
 }
+
+/// Format a timestamp the way every timestamp is stored: UTC RFC 3339 with milliseconds
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Combine a stored UTC entry date with its offset in minutes; entries
+/// without an offset are shown in the journal timezone
+pub fn parse_entry_date(
+    value: &str,
+    offset_minutes: Option<i32>,
+    timezone: &JournalTimezone,
+) -> Result<DateTime<FixedOffset>> {
+    let utc = parse_timestamp(value)?;
+    match offset_minutes {
+        Some(minutes) => {
+            let offset = FixedOffset::east_opt(minutes * 60)
+                .with_context(|| format!("Invalid UTC offset: {} minutes", minutes))?;
+            Ok(utc.with_timezone(&offset))
+        }
+        None => Ok(timezone.at(utc)),
+    }
+}
+
+/// Parse an RFC 3339 timestamp, or the UTC `YYYY-MM-DD HH:MM:SS` written by SQL defaults and triggers
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.into());
+    }
+
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|naive| naive.and_utc())
+        .with_context(|| format!("Invalid timestamp: {}", value))
+}
+
+/// Read an embedding stored as little-endian f32 values
+pub fn decode_embedding(data: &[u8]) -> Vec<f32> {
+    data.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, TempJournal};
+
+    fn entry_json(entry_date: Option<&str>) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "id": "1",
+            "title": "Title",
+            "content": "Content",
+            "tags": [],
+            "mood": null,
+            "privacy": "private",
+            "source": null,
+            "source_id": null,
+            "source_url": null,
+            "metadata": null,
+            "created_at": "2024-05-01T08:30:00Z",
+            "updated_at": "2024-05-02T08:30:00Z",
+        });
+        if let Some(entry_date) = entry_date {
+            value["entry_date"] = entry_date.into();
+        }
+        value
+    }
+
+    #[test]
+    fn missing_entry_date_falls_back_to_created_at() {
+        let entry: JournalEntry = serde_json::from_value(entry_json(None)).unwrap();
+        assert_eq!(entry.entry_date, entry.created_at.fixed_offset());
+
+        let mut value = entry_json(None);
+        value["entry_date"] = serde_json::Value::Null;
+        let entry: JournalEntry = serde_json::from_value(value).unwrap();
+        assert_eq!(entry.entry_date, entry.created_at.fixed_offset());
+    }
+
+    #[test]
+    fn entry_date_keeps_its_offset() {
+        let entry: JournalEntry =
+            serde_json::from_value(entry_json(Some("2020-01-01T23:00:00+09:00"))).unwrap();
+        assert_eq!(entry.entry_date.offset().local_minus_utc(), 9 * 3600);
+
+        let round_trip: JournalEntry =
+            serde_json::from_value(serde_json::to_value(&entry).unwrap()).unwrap();
+        assert_eq!(round_trip.entry_date, entry.entry_date);
+        assert_eq!(round_trip.created_at, entry.created_at);
+    }
+
+    #[tokio::test]
+    async fn statistics_that_fail_to_update_are_rebuilt_later() {
+        let database = TempJournal::new().await;
+        sqlx::query(
+            "CREATE TRIGGER fail_statistics BEFORE INSERT ON journal_statistics
+             BEGIN SELECT RAISE(ABORT, 'disk full'); END",
+        )
+        .execute(database.pool())
+        .await
+        .unwrap();
+
+        // The entry is saved even though its statistics are not
+        assert!(database
+            .create_entry(&test_support::entry("first", "Hello there"))
+            .await
+            .is_err());
+        assert!(database.get_entry("first").await.unwrap().is_some());
+        assert!(database.statistics().is_empty().await.unwrap());
+
+        sqlx::query("DROP TRIGGER fail_statistics")
+            .execute(database.pool())
+            .await
+            .unwrap();
+        database
+            .create_entry(&test_support::entry("second", "Hello again"))
+            .await
+            .unwrap();
+        assert!(database.rebuild_stale_statistics().await.unwrap());
+        assert!(!database.rebuild_stale_statistics().await.unwrap());
+
+        let days = database
+            .statistics()
+            .daily_totals(&Default::default(), &[])
+            .await
+            .unwrap();
+        assert_eq!(days.values().map(|day| day.entries).sum::<i64>(), 2);
+    }
+}
@@ -0,0 +1,750 @@
+/**
+ * Near-duplicate detection for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Finding entries with nearly the same text, whatever their source
+ * - Merging duplicates into one canonical entry
+ * - Linking entries as duplicates, or dismissing a suggestion
+ *
+ * Cross-posting puts the same thought in the journal several times, as a
+ * toot, a Bluesky post and a Substack note. Text is normalized (markup, links
+ * and mentions removed), split into overlapping three-word shingles and
+ * MinHashed; locality-sensitive hashing over the signatures finds candidate
+ * pairs, whose shingle sets are then compared exactly. No AI is involved.
+ */
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::collections::CollectionStore;
+use crate::database::{Database, JournalEntry};
+
+/// Jaccard similarity of two entries' shingles above which they're suggested
+pub const DEFAULT_SIMILARITY: f64 = 0.6;
+
+/// Entries with fewer words than this are too short to tell apart
+const MIN_WORDS: usize = 5;
+
+const SHINGLE_WORDS: usize = 3;
+
+/// Signature length is BANDS * ROWS; more rows per band means fewer, closer candidates
+const BANDS: usize = 16;
+const ROWS: usize = 4;
+
+/// Entries that look like copies of each other
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    /// Oldest first
+    pub entries: Vec<JournalEntry>,
+    /// Lowest similarity between two entries that put them in the cluster
+    pub similarity: f64,
+    /// The entry to keep if the cluster is merged
+    pub suggested_canonical_id: String,
+}
+
+pub struct DuplicateDetector<'a> {
+    database: &'a Database,
+}
+
+impl<'a> DuplicateDetector<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        DuplicateDetector { database }
+    }
+
+    /// Clusters of near-identical entries, largest first
+    pub async fn find(&self, similarity: Option<f64>) -> Result<Vec<DuplicateCluster>> {
+        let threshold = similarity.unwrap_or(DEFAULT_SIMILARITY);
+        if threshold <= 0.0 || threshold > 1.0 || threshold.is_nan() {
+            return Err(anyhow::anyhow!("Similarity must be above 0 and at most 1"));
+        }
+
+        let entries = self.database.list_all_entries().await?;
+        let shingles: Vec<HashSet<u64>> = entries
+            .iter()
+            .map(|entry| shingles(&entry.content))
+            .collect();
+        let signatures: Vec<Option<Vec<u64>>> = shingles
+            .iter()
+            .map(|set| (!set.is_empty()).then(|| minhash(set)))
+            .collect();
+
+        // Entries sharing any band of their signature are candidates
+        let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+        for (index, signature) in signatures.iter().enumerate() {
+            let Some(signature) = signature else {
+                continue;
+            };
+            for (band, key) in band_keys(signature).into_iter().enumerate() {
+                buckets.entry((band, key)).or_default().push(index);
+            }
+        }
+
+        let groups = self.linked_groups().await?;
+        let dismissed = self.dismissed().await?;
+
+        let mut checked = HashSet::new();
+        let mut clusters = UnionFind::new(entries.len());
+        let mut lowest: HashMap<usize, f64> = HashMap::new();
+        for members in buckets.values().filter(|members| members.len() > 1) {
+            for (i, &a) in members.iter().enumerate() {
+                for &b in &members[i + 1..] {
+                    if !checked.insert((a, b)) {
+                        continue;
+                    }
+
+                    let (first, second) = (&entries[a].id, &entries[b].id);
+                    let linked = matches!(
+                        (groups.get(first), groups.get(second)),
+                        (Some(x), Some(y)) if x == y
+                    );
+                    if linked || dismissed.contains(&ordered_pair(first, second)) {
+                        continue;
+                    }
+
+                    let score = jaccard(&shingles[a], &shingles[b]);
+                    if score >= threshold {
+                        clusters.union(a, b);
+                        for index in [a, b] {
+                            let low = lowest.entry(index).or_insert(score);
+                            *low = low.min(score);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut grouped: HashMap<usize, Vec<usize>> = HashMap::new();
+        for index in lowest.keys() {
+            grouped
+                .entry(clusters.find(*index))
+                .or_default()
+                .push(*index);
+        }
+
+        let mut result: Vec<DuplicateCluster> = grouped
+            .into_values()
+            .map(|indexes| {
+                let similarity = indexes
+                    .iter()
+                    .map(|index| lowest[index])
+                    .fold(1.0, f64::min);
+                let mut members: Vec<JournalEntry> = indexes
+                    .into_iter()
+                    .map(|index| entries[index].clone())
+                    .collect();
+                members.sort_by_key(|entry| entry.entry_date);
+
+                DuplicateCluster {
+                    suggested_canonical_id: suggest_canonical(&members).id.clone(),
+                    entries: members,
+                    similarity,
+                }
+            })
+            .collect();
+
+        result.sort_by(|a, b| {
+            b.entries
+                .len()
+                .cmp(&a.entries.len())
+                .then(b.similarity.total_cmp(&a.similarity))
+        });
+
+        Ok(result)
+    }
+
+    /// Fold `duplicate_ids` into the canonical entry and delete them. The
+    /// canonical entry keeps its text; it gains the duplicates' tags, and its
+    /// metadata records every source URL and the entries merged into it.
+    ///
+    /// Entries can't be changed in one transaction, so a duplicate that fails
+    /// to delete is taken back out of the canonical entry: afterwards it
+    /// records exactly the duplicates that are gone.
+    pub async fn merge(
+        &self,
+        canonical_id: &str,
+        duplicate_ids: &[String],
+    ) -> Result<JournalEntry> {
+        let canonical = self
+            .database
+            .get_entry(canonical_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Entry not found: {}", canonical_id))?;
+
+        let mut duplicates = Vec::new();
+        for id in duplicate_ids {
+            if id == canonical_id {
+                return Err(anyhow::anyhow!("An entry cannot be merged into itself"));
+            }
+            if duplicates
+                .iter()
+                .any(|duplicate: &JournalEntry| &duplicate.id == id)
+            {
+                continue;
+            }
+            let duplicate = self
+                .database
+                .get_entry(id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Entry not found: {}", id))?;
+            duplicates.push(duplicate);
+        }
+        if duplicates.is_empty() {
+            return Err(anyhow::anyhow!("Choose at least one duplicate to merge"));
+        }
+
+        let merged = fold_duplicates(&canonical, &duplicates);
+        self.database.update_entry(&merged).await?;
+
+        // The canonical entry takes the duplicates' places in collections
+        let collections = CollectionStore::new(self.database);
+        for (deleted, duplicate) in duplicates.iter().enumerate() {
+            let removed = async {
+                for collection in collections.for_entry(&duplicate.id).await? {
+                    collections
+                        .add_entries(&collection.id, std::slice::from_ref(&canonical.id))
+                        .await?;
+                }
+                self.database.delete_entry(&duplicate.id).await
+            }
+            .await;
+
+            if let Err(e) = removed {
+                let partial = fold_duplicates(&canonical, &duplicates[..deleted]);
+                if let Err(undo) = self.database.update_entry(&partial).await {
+                    eprintln!("Failed to undo merge into {}: {}", canonical.id, undo);
+                }
+                return Err(e.context(format!("Failed to merge {}", duplicate.id)));
+            }
+        }
+
+        Ok(merged)
+    }
+
+    /// Mark entries as copies of each other while keeping them all
+    pub async fn link(&self, entry_ids: &[String]) -> Result<()> {
+        if entry_ids.len() < 2 {
+            return Err(anyhow::anyhow!("Choose at least two entries to link"));
+        }
+        for id in entry_ids {
+            if self.database.get_entry(id).await?.is_none() {
+                return Err(anyhow::anyhow!("Entry not found: {}", id));
+            }
+        }
+
+        // Groups the entries already belong to are joined into one
+        let groups = self.linked_groups().await?;
+        let joined: Vec<&String> = entry_ids.iter().filter_map(|id| groups.get(id)).collect();
+        let group_id = joined
+            .first()
+            .map(|group| group.to_string())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        for group in joined {
+            sqlx::query("UPDATE duplicate_links SET group_id = ? WHERE group_id = ?")
+                .bind(&group_id)
+                .bind(group)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to join duplicate groups")?;
+        }
+
+        for id in entry_ids {
+            sqlx::query(
+                "INSERT INTO duplicate_links (entry_id, group_id) VALUES (?, ?)
+                 ON CONFLICT(entry_id) DO UPDATE SET group_id = excluded.group_id",
+            )
+            .bind(id)
+            .bind(&group_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to link duplicates")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit duplicate links")?;
+        Ok(())
+    }
+
+    /// Take an entry out of its duplicate group
+    pub async fn unlink(&self, entry_id: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM duplicate_links WHERE entry_id = ?")
+            .bind(entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to unlink duplicate")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!(
+                "Entry is not linked to any duplicates: {}",
+                entry_id
+            ));
+        }
+
+        self.drop_lone_links().await
+    }
+
+    /// The other entries linked as copies of an entry
+    pub async fn linked(&self, entry_id: &str) -> Result<Vec<JournalEntry>> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT entry_id FROM duplicate_links
+            WHERE group_id = (SELECT group_id FROM duplicate_links WHERE entry_id = ?)
+              AND entry_id != ?
+            ORDER BY linked_at, entry_id
+            "#,
+        )
+        .bind(entry_id)
+        .bind(entry_id)
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to list linked duplicates")?;
+
+        let mut entries = Vec::new();
+        for id in ids {
+            if let Some(entry) = self.database.get_entry(&id).await? {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Stop suggesting these entries as duplicates of each other
+    pub async fn dismiss(&self, entry_ids: &[String]) -> Result<()> {
+        if entry_ids.len() < 2 {
+            return Err(anyhow::anyhow!("Choose at least two entries to dismiss"));
+        }
+
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        for (i, first) in entry_ids.iter().enumerate() {
+            for second in &entry_ids[i + 1..] {
+                let (first, second) = ordered_pair(first, second);
+                sqlx::query(
+                    "INSERT OR IGNORE INTO duplicate_dismissals (first_id, second_id) VALUES (?, ?)",
+                )
+                .bind(first)
+                .bind(second)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to dismiss duplicates")?;
+            }
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit dismissed duplicates")?;
+        Ok(())
+    }
+
+    /// Drop a deleted entry's links and dismissals
+    pub async fn forget_entry(&self, entry_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM duplicate_links WHERE entry_id = ?")
+            .bind(entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to remove duplicate links")?;
+
+        sqlx::query("DELETE FROM duplicate_dismissals WHERE first_id = ? OR second_id = ?")
+            .bind(entry_id)
+            .bind(entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to remove dismissed duplicates")?;
+
+        self.drop_lone_links().await
+    }
+
+    /// A group of one links nothing
+    async fn drop_lone_links(&self) -> Result<()> {
+        sqlx::query(
+            "DELETE FROM duplicate_links WHERE group_id IN
+             (SELECT group_id FROM duplicate_links GROUP BY group_id HAVING COUNT(*) < 2)",
+        )
+        .execute(self.database.pool())
+        .await
+        .context("Failed to clean up duplicate links")?;
+
+        Ok(())
+    }
+
+    async fn linked_groups(&self) -> Result<HashMap<String, String>> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT entry_id, group_id FROM duplicate_links")
+                .fetch_all(self.database.pool())
+                .await
+                .context("Failed to read duplicate links")?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn dismissed(&self) -> Result<HashSet<(String, String)>> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT first_id, second_id FROM duplicate_dismissals")
+                .fetch_all(self.database.pool())
+                .await
+                .context("Failed to read dismissed duplicates")?;
+
+        Ok(rows.into_iter().collect())
+    }
+}
+
+/// Lowercased words with markup, links, mentions and punctuation removed
+fn normalized_words(text: &str) -> Vec<String> {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                plain.push(' ');
+            }
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+
+    plain
+        .split_whitespace()
+        .filter(|word| {
+            !(word.starts_with('@')
+                || word.starts_with("http://")
+                || word.starts_with("https://")
+                || word.starts_with("www."))
+        })
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Hashes of each run of SHINGLE_WORDS words
+fn shingles(text: &str) -> HashSet<u64> {
+    let words = normalized_words(text);
+    if words.len() < MIN_WORDS {
+        return HashSet::new();
+    }
+
+    words
+        .windows(SHINGLE_WORDS)
+        .map(|window| {
+            window.iter().fold(FNV_OFFSET, |hash, word| {
+                fnv(fnv(hash, word.as_bytes()), b" ")
+            })
+        })
+        .collect()
+}
+
+/// The smallest value of each of BANDS * ROWS hash functions over the shingles
+fn minhash(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..(BANDS * ROWS) as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|shingle| splitmix(shingle ^ splitmix(seed)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        0.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+/// One hash per band of a MinHash signature; entries sharing any are candidates
+fn band_keys(signature: &[u64]) -> Vec<u64> {
+    signature
+        .chunks(ROWS)
+        .map(|rows| {
+            rows.iter()
+                .fold(FNV_OFFSET, |hash, row| fnv(hash, &row.to_le_bytes()))
+        })
+        .collect()
+}
+
+/// `canonical` with the tags, location and source URLs of `duplicates`
+/// folded in, and a record of each duplicate in its metadata
+fn fold_duplicates(canonical: &JournalEntry, duplicates: &[JournalEntry]) -> JournalEntry {
+    let mut canonical = canonical.clone();
+    if duplicates.is_empty() {
+        return canonical;
+    }
+
+    let mut metadata = match canonical.metadata.take() {
+        Some(Value::Object(fields)) => fields,
+        Some(other) => Map::from_iter([("original".to_string(), other)]),
+        None => Map::new(),
+    };
+
+    let mut source_urls = string_array(&metadata, "source_urls");
+    let mut merged = match metadata.remove("merged_entries") {
+        Some(Value::Array(merged)) => merged,
+        _ => Vec::new(),
+    };
+    push_unique(&mut source_urls, canonical.source_url.as_deref());
+
+    for duplicate in duplicates {
+        push_unique(&mut source_urls, duplicate.source_url.as_deref());
+        if let Some(Value::Object(fields)) = &duplicate.metadata {
+            for url in string_array(fields, "source_urls") {
+                push_unique(&mut source_urls, Some(&url));
+            }
+            if let Some(Value::Array(earlier)) = fields.get("merged_entries") {
+                merged.extend(earlier.iter().cloned());
+            }
+        }
+        merged.push(json!({
+            "id": duplicate.id,
+            "source": duplicate.source,
+            "source_id": duplicate.source_id,
+            "source_url": duplicate.source_url,
+            "entry_date": duplicate.entry_date,
+        }));
+
+        for tag in &duplicate.tags {
+            if !canonical.tags.contains(tag) {
+                canonical.tags.push(tag.clone());
+            }
+        }
+        if canonical.location.is_none() {
+            canonical.location = duplicate.location.clone();
+        }
+    }
+
+    metadata.insert("source_urls".to_string(), json!(source_urls));
+    metadata.insert("merged_entries".to_string(), Value::Array(merged));
+    canonical.metadata = Some(Value::Object(metadata));
+    canonical.updated_at = Utc::now();
+    canonical
+}
+
+/// Keep what was written in the app, else the longest text, else the oldest
+fn suggest_canonical(entries: &[JournalEntry]) -> &JournalEntry {
+    entries
+        .iter()
+        .min_by(|a, b| {
+            a.is_imported()
+                .cmp(&b.is_imported())
+                .then(b.content.len().cmp(&a.content.len()))
+                .then(a.entry_date.cmp(&b.entry_date))
+        })
+        .expect("clusters have at least two entries")
+}
+
+fn ordered_pair(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+fn string_array(fields: &Map<String, Value>, key: &str) -> Vec<String> {
+    match fields.get(key) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, value: Option<&str>) {
+    if let Some(value) = value.filter(|value| !value.is_empty()) {
+        if !values.iter().any(|existing| existing == value) {
+            values.push(value.to_string());
+        }
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn splitmix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        UnionFind {
+            parents: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let parent = self.parents[index];
+        if parent == index {
+            return index;
+        }
+        let root = self.find(parent);
+        self.parents[index] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[b] = a;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    const POST: &str = "Spent the morning repotting the fig tree and it finally \
+                        has room to grow. Next weekend the basil gets the same treatment.";
+
+    fn entry(id: &str, source_url: Option<&str>, metadata: Option<Value>) -> JournalEntry {
+        JournalEntry {
+            tags: vec![format!("tag-{}", id)],
+            source: source_url.map(|_| "mastodon".to_string()),
+            source_url: source_url.map(str::to_string),
+            metadata,
+            ..test_support::entry(id, POST)
+        }
+    }
+
+    #[test]
+    fn short_texts_have_no_shingles() {
+        assert!(shingles("Only four words here").is_empty());
+        assert_eq!(shingles("Now there are five words").len(), 3);
+        // Links and mentions don't count as words
+        assert!(shingles("@friend look https://example.com at this").is_empty());
+    }
+
+    #[test]
+    fn cross_posts_pass_the_threshold() {
+        let cross_post = format!(
+            "<p>{} @friend@social.example</p> https://example.com/fig",
+            POST.to_uppercase()
+        );
+        assert_eq!(shingles(POST), shingles(&cross_post));
+
+        // A small edit still reads as the same post
+        let edited = POST.replace("finally", "at last");
+        let score = jaccard(&shingles(POST), &shingles(&edited));
+        assert!((DEFAULT_SIMILARITY..1.0).contains(&score), "{}", score);
+
+        let unrelated = "The train was late again so I read half a novel on the platform \
+                         and missed the start of the meeting.";
+        assert!(jaccard(&shingles(POST), &shingles(unrelated)) < 0.1);
+    }
+
+    #[test]
+    fn similar_signatures_share_a_band() {
+        let original = minhash(&shingles(POST));
+        let edited = minhash(&shingles(&POST.replace("finally", "at last")));
+        let unrelated = minhash(&shingles(
+            "The train was late again so I read half a novel on the platform \
+             and missed the start of the meeting.",
+        ));
+        assert_eq!(original.len(), BANDS * ROWS);
+
+        let shared_bands = |a: &[u64], b: &[u64]| {
+            band_keys(a)
+                .iter()
+                .zip(band_keys(b))
+                .filter(|(x, y)| **x == *y)
+                .count()
+        };
+        assert_eq!(shared_bands(&original, &original), BANDS);
+        assert!(shared_bands(&original, &edited) > 0);
+        assert_eq!(shared_bands(&original, &unrelated), 0);
+    }
+
+    #[test]
+    fn folding_collects_source_urls_and_merged_entries() {
+        let canonical = entry(
+            "canonical",
+            Some("https://a.example/1"),
+            Some(json!({ "source_urls": ["https://a.example/1", "https://b.example/2"] })),
+        );
+        let first = entry(
+            "first",
+            Some("https://c.example/3"),
+            Some(json!({
+                "source_urls": ["https://d.example/4"],
+                "merged_entries": [{ "id": "earlier" }],
+            })),
+        );
+        let second = entry("second", Some("https://b.example/2"), None);
+
+        let merged = fold_duplicates(&canonical, &[first, second]);
+        let metadata = merged.metadata.as_ref().unwrap();
+        assert_eq!(
+            metadata["source_urls"],
+            json!([
+                "https://a.example/1",
+                "https://b.example/2",
+                "https://c.example/3",
+                "https://d.example/4",
+            ])
+        );
+        let merged_ids: Vec<&str> = metadata["merged_entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|record| record["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(merged_ids, ["earlier", "first", "second"]);
+        assert_eq!(merged.tags, ["tag-canonical", "tag-first", "tag-second"]);
+        assert_eq!(merged.content, POST);
+    }
+
+    #[test]
+    fn folding_keeps_other_metadata() {
+        let canonical = entry("canonical", None, Some(json!("imported note")));
+        let merged = fold_duplicates(&canonical, &[entry("other", None, None)]);
+        assert_eq!(merged.metadata.unwrap()["original"], json!("imported note"));
+
+        // Nothing to fold leaves the entry as it was
+        let unchanged = fold_duplicates(&canonical, &[]);
+        assert_eq!(unchanged.metadata, canonical.metadata);
+        assert_eq!(unchanged.updated_at, canonical.updated_at);
+    }
+}
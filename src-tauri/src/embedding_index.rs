@@ -0,0 +1,547 @@
+/**
+ * Embedding index for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Queueing entries to be embedded when they are created or edited
+ * - Skipping entries whose text and model haven't changed since they were embedded
+ * - Retrying failed entries with backoff
+ * - Reporting how much of the journal is embedded
+ *
+ * The AI requests themselves are made by the background indexer in main.rs,
+ * which takes due entries from here in batches, embeds them without holding
+ * the database, and hands the vectors back.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use std::collections::HashMap;
+
+use crate::database::{format_timestamp, Database, Embedding, JournalEntry};
+
+/// Event carrying an `EmbeddingIndexStatus` after each round of indexing
+pub const EMBEDDINGS_PROGRESS: &str = "embeddings://progress";
+
+/// Texts sent to the model in one request
+pub const BATCH_SIZE: usize = 16;
+
+/// Embedding requests in flight at once
+pub const CONCURRENCY: usize = 2;
+
+/// How often the indexer looks for failed entries that are due for a retry
+pub const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Failures after which an entry waits for an edit or a reindex
+const MAX_ATTEMPTS: i64 = 5;
+
+/// Longer entries are cut off so they fit the model's context
+const MAX_TEXT_CHARS: usize = 8000;
+
+/// How much of the journal has embeddings from the current model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingIndexStatus {
+    pub model: String,
+    pub total_entries: i64,
+    pub indexed: i64,
+    /// Waiting to be embedded, including failures that will be retried
+    pub queued: i64,
+    /// Gave up after repeated failures
+    pub failed: i64,
+    pub last_error: Option<String>,
+}
+
+/// An entry taken from the queue to be embedded
+#[derive(Debug, Clone)]
+pub struct PendingEmbedding {
+    pub entry_id: String,
+    pub text: String,
+    content_hash: String,
+    /// When the entry was queued, so a newer edit isn't dequeued with it
+    queued_at: String,
+}
+
+pub struct EmbeddingIndex<'a> {
+    database: &'a Database,
+}
+
+impl<'a> EmbeddingIndex<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        EmbeddingIndex { database }
+    }
+
+    /// Queue entries to be embedded; `force` embeds them even if their text is unchanged
+    pub async fn enqueue(&self, entry_ids: &[String], force: bool) -> Result<()> {
+        let queued_at = format_timestamp(&Utc::now());
+        for entry_id in entry_ids {
+            sqlx::query(
+                r#"
+                INSERT INTO embedding_queue (entry_id, force, queued_at) VALUES (?, ?, ?)
+                ON CONFLICT(entry_id) DO UPDATE SET
+                    force = MAX(force, excluded.force), attempts = 0, last_error = NULL,
+                    next_attempt_at = NULL, queued_at = excluded.queued_at
+                "#,
+            )
+            .bind(entry_id)
+            .bind(force)
+            .bind(&queued_at)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to queue entry for embedding")?;
+        }
+
+        Ok(())
+    }
+
+    /// Queue every entry to be embedded again
+    pub async fn enqueue_all(&self, force: bool) -> Result<()> {
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM journal_entries")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list entries")?;
+
+        self.enqueue(&ids, force).await
+    }
+
+    /// Queue entries with no embedding from `model`, e.g. after switching models
+    pub async fn enqueue_missing(&self, model: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO embedding_queue (entry_id, queued_at)
+            SELECT id, ? FROM journal_entries
+            WHERE id NOT IN (SELECT entry_id FROM embeddings WHERE model_name = ?)
+            "#,
+        )
+        .bind(format_timestamp(&Utc::now()))
+        .bind(model)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to queue entries for embedding")?;
+
+        Ok(())
+    }
+
+    /// Up to `limit` queued entries that need embedding with `model`.
+    /// Entries that are gone, empty, or already embedded from the same text
+    /// are dropped from the queue on the way, and more of the queue is read
+    /// until the batch is full or the queue runs out.
+    pub async fn take_due(&self, model: &str, limit: usize) -> Result<Vec<PendingEmbedding>> {
+        let now = format_timestamp(&Utc::now());
+        let page = (limit * 2).max(1);
+        let mut after: Option<(String, String)> = None;
+        let mut pending = Vec::new();
+        while pending.len() < limit {
+            let (after_queued_at, after_entry_id) = after.clone().unwrap_or_default();
+            let rows = sqlx::query(
+                r#"
+                SELECT entry_id, force, queued_at FROM embedding_queue
+                WHERE attempts < ? AND (next_attempt_at IS NULL OR next_attempt_at <= ?)
+                    AND (queued_at, entry_id) > (?, ?)
+                ORDER BY queued_at, entry_id
+                LIMIT ?
+                "#,
+            )
+            .bind(MAX_ATTEMPTS)
+            .bind(&now)
+            .bind(&after_queued_at)
+            .bind(&after_entry_id)
+            .bind(page as i64)
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to read embedding queue")?;
+            let exhausted = rows.len() < page;
+
+            for row in rows {
+                if pending.len() >= limit {
+                    break;
+                }
+
+                let entry_id: String = row.get("entry_id");
+                let force: bool = row.get("force");
+                let queued_at: String = row.get("queued_at");
+                after = Some((queued_at.clone(), entry_id.clone()));
+
+                if let Some(item) = self.prepare(model, entry_id, force, queued_at).await? {
+                    pending.push(item);
+                }
+            }
+
+            if exhausted {
+                break;
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// The text to embed for a queued entry, or `None` after dropping it
+    /// from the queue because there is nothing new to embed
+    async fn prepare(
+        &self,
+        model: &str,
+        entry_id: String,
+        force: bool,
+        queued_at: String,
+    ) -> Result<Option<PendingEmbedding>> {
+        let Some(entry) = self.database.get_entry(&entry_id).await? else {
+            self.dequeue(&entry_id, &queued_at).await?;
+            return Ok(None);
+        };
+        let text = embedding_text(&entry);
+        if text.is_empty() {
+            self.remove_embeddings(&entry_id).await?;
+            self.dequeue(&entry_id, &queued_at).await?;
+            return Ok(None);
+        }
+
+        let content_hash = content_hash(&text);
+        let unchanged = self
+            .database
+            .get_embedding(&entry_id, model)
+            .await?
+            .is_some_and(|embedding| embedding.content_hash == content_hash);
+        if unchanged && !force {
+            self.dequeue(&entry_id, &queued_at).await?;
+            return Ok(None);
+        }
+
+        Ok(Some(PendingEmbedding {
+            entry_id,
+            content_hash,
+            text,
+            queued_at,
+        }))
+    }
+
+    /// Save the vectors made for `pending` and take the entries off the queue
+    pub async fn store(
+        &self,
+        model: &str,
+        pending: &[PendingEmbedding],
+        vectors: Vec<Vec<f32>>,
+    ) -> Result<()> {
+        let created_at = Utc::now();
+        for (item, vector) in pending.iter().zip(vectors) {
+            // The entry may have been deleted while it was being embedded
+            if self.database.get_entry(&item.entry_id).await?.is_none() {
+                continue;
+            }
+
+            self.database
+                .store_embedding(&Embedding {
+                    entry_id: item.entry_id.clone(),
+                    model_name: model.to_string(),
+                    content_hash: item.content_hash.clone(),
+                    embedding_vector: vector,
+                    created_at,
+                })
+                .await?;
+            self.dequeue(&item.entry_id, &item.queued_at).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Note that embedding `pending` failed; each is retried later with a growing delay
+    pub async fn record_failure(&self, pending: &[PendingEmbedding], error: &str) -> Result<()> {
+        let now = Utc::now();
+        for item in pending {
+            let attempts: Option<i64> = sqlx::query_scalar(
+                "SELECT attempts FROM embedding_queue WHERE entry_id = ? AND queued_at = ?",
+            )
+            .bind(&item.entry_id)
+            .bind(&item.queued_at)
+            .fetch_optional(self.database.pool())
+            .await
+            .context("Failed to read embedding queue")?;
+
+            // Edited since it was taken, so it starts over anyway
+            let Some(attempts) = attempts else {
+                continue;
+            };
+
+            sqlx::query(
+                r#"
+                UPDATE embedding_queue SET attempts = ?, last_error = ?, next_attempt_at = ?
+                WHERE entry_id = ?
+                "#,
+            )
+            .bind(attempts + 1)
+            .bind(error)
+            .bind(format_timestamp(&retry_at(now, attempts + 1)))
+            .bind(&item.entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to record embedding failure")?;
+        }
+
+        Ok(())
+    }
+
+    /// Progress of embedding the journal with `model`
+    pub async fn status(&self, model: &str) -> Result<EmbeddingIndexStatus> {
+        let pool = self.database.pool();
+        let total_entries: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM journal_entries")
+            .fetch_one(pool)
+            .await
+            .context("Failed to count entries")?;
+        let indexed: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM embeddings
+            WHERE model_name = ? AND entry_id IN (SELECT id FROM journal_entries)
+            "#,
+        )
+        .bind(model)
+        .fetch_one(pool)
+        .await
+        .context("Failed to count embeddings")?;
+
+        let mut counts: HashMap<bool, i64> = HashMap::new();
+        for row in sqlx::query(
+            "SELECT attempts >= ? AS failed, COUNT(*) AS count FROM embedding_queue GROUP BY 1",
+        )
+        .bind(MAX_ATTEMPTS)
+        .fetch_all(pool)
+        .await
+        .context("Failed to read embedding queue")?
+        {
+            counts.insert(row.get("failed"), row.get("count"));
+        }
+
+        let last_error: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT last_error FROM embedding_queue WHERE last_error IS NOT NULL
+            ORDER BY next_attempt_at DESC LIMIT 1
+            "#,
+        )
+        .fetch_optional(pool)
+        .await
+        .context("Failed to read embedding queue")?;
+
+        Ok(EmbeddingIndexStatus {
+            model: model.to_string(),
+            total_entries,
+            indexed,
+            queued: counts.get(&false).copied().unwrap_or_default(),
+            failed: counts.get(&true).copied().unwrap_or_default(),
+            last_error,
+        })
+    }
+
+    /// Drop the embeddings and queue entry of a deleted entry
+    pub async fn forget_entry(&self, entry_id: &str) -> Result<()> {
+        self.remove_embeddings(entry_id).await?;
+        sqlx::query("DELETE FROM embedding_queue WHERE entry_id = ?")
+            .bind(entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to remove entry from embedding queue")?;
+
+        Ok(())
+    }
+
+    async fn remove_embeddings(&self, entry_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM embeddings WHERE entry_id = ?")
+            .bind(entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to delete embeddings")?;
+
+        Ok(())
+    }
+
+    async fn dequeue(&self, entry_id: &str, queued_at: &str) -> Result<()> {
+        sqlx::query("DELETE FROM embedding_queue WHERE entry_id = ? AND queued_at = ?")
+            .bind(entry_id)
+            .bind(queued_at)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to update embedding queue")?;
+
+        Ok(())
+    }
+}
+
+/// The text of an entry that gets embedded
+fn embedding_text(entry: &JournalEntry) -> String {
+    let text = format!("{}\n\n{}", entry.title.trim(), entry.content.trim());
+    text.trim().chars().take(MAX_TEXT_CHARS).collect()
+}
+
+fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Wait 30 seconds after the first failure, doubling each time
+fn retry_at(now: DateTime<Utc>, attempts: i64) -> DateTime<Utc> {
+    now + Duration::seconds(30 << (attempts - 1).clamp(0, 10))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, TempJournal};
+
+    const MODEL: &str = "nomic-embed-text";
+
+    async fn queued(database: &Database) -> Vec<(String, i64)> {
+        sqlx::query("SELECT entry_id, attempts FROM embedding_queue ORDER BY entry_id")
+            .fetch_all(database.pool())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.get("entry_id"), row.get("attempts")))
+            .collect()
+    }
+
+    fn ids(pending: &[PendingEmbedding]) -> Vec<&str> {
+        pending.iter().map(|item| item.entry_id.as_str()).collect()
+    }
+
+    /// Queue times are kept to the millisecond, so later queueing must wait for the next one
+    async fn next_millisecond() {
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    #[tokio::test]
+    async fn unchanged_entries_are_skipped_and_the_batch_refilled() {
+        let database = TempJournal::new().await;
+        let index = EmbeddingIndex::new(&database);
+        for id in ["a", "b", "c", "d", "e"] {
+            database
+                .create_entry(&entry(id, &format!("Entry {}", id)))
+                .await
+                .unwrap();
+        }
+        let pending = index.take_due(MODEL, 5).await.unwrap();
+        assert_eq!(ids(&pending), ["a", "b", "c", "d", "e"]);
+        index
+            .store(MODEL, &pending, vec![vec![1.0, 0.0]; pending.len()])
+            .await
+            .unwrap();
+        assert!(queued(&database).await.is_empty());
+
+        // Requeued but unchanged entries sit ahead of the new ones
+        let all: Vec<String> = pending.iter().map(|item| item.entry_id.clone()).collect();
+        index.enqueue(&all, false).await.unwrap();
+        next_millisecond().await;
+        database.create_entry(&entry("f", "Entry f")).await.unwrap();
+        database.create_entry(&entry("g", "Entry g")).await.unwrap();
+        database.create_entry(&entry("h", "   ")).await.unwrap();
+
+        // Reads past the first page of unchanged entries to fill the batch
+        let pending = index.take_due(MODEL, 2).await.unwrap();
+        assert_eq!(ids(&pending), ["f", "g"]);
+        assert_eq!(
+            queued(&database).await,
+            [
+                ("f".to_string(), 0),
+                ("g".to_string(), 0),
+                ("h".to_string(), 0)
+            ]
+        );
+
+        // Empty entries are dropped, and forcing embeds unchanged text again
+        next_millisecond().await;
+        index.enqueue(&["a".to_string()], true).await.unwrap();
+        let pending = index.take_due(MODEL, 10).await.unwrap();
+        assert_eq!(ids(&pending), ["f", "g", "a"]);
+        assert_eq!(
+            queued(&database)
+                .await
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>(),
+            ["a", "f", "g"]
+        );
+    }
+
+    #[tokio::test]
+    async fn edits_made_while_embedding_stay_queued() {
+        let database = TempJournal::new().await;
+        let index = EmbeddingIndex::new(&database);
+        database
+            .create_entry(&entry("stored", "First draft"))
+            .await
+            .unwrap();
+        database
+            .create_entry(&entry("failed", "First draft"))
+            .await
+            .unwrap();
+        let pending = index.take_due(MODEL, 10).await.unwrap();
+        assert_eq!(pending.len(), 2);
+
+        next_millisecond().await;
+        database
+            .update_entry(&entry("stored", "Second draft"))
+            .await
+            .unwrap();
+        database
+            .update_entry(&entry("failed", "Second draft"))
+            .await
+            .unwrap();
+
+        // The old text is saved, but the edit is still waiting to be embedded
+        index
+            .store(MODEL, &pending[..1], vec![vec![1.0, 0.0]])
+            .await
+            .unwrap();
+        index
+            .record_failure(&pending[1..], "connection refused")
+            .await
+            .unwrap();
+        assert_eq!(
+            queued(&database).await,
+            [("failed".to_string(), 0), ("stored".to_string(), 0)]
+        );
+
+        let mut pending = index.take_due(MODEL, 10).await.unwrap();
+        pending.sort_by(|a, b| a.entry_id.cmp(&b.entry_id));
+        assert_eq!(ids(&pending), ["failed", "stored"]);
+        assert!(pending.iter().all(|item| item.text == "Second draft"));
+    }
+
+    #[tokio::test]
+    async fn failures_wait_before_retrying() {
+        let database = TempJournal::new().await;
+        let index = EmbeddingIndex::new(&database);
+        database.create_entry(&entry("a", "Entry a")).await.unwrap();
+
+        let pending = index.take_due(MODEL, 10).await.unwrap();
+        index
+            .record_failure(&pending, "connection refused")
+            .await
+            .unwrap();
+        assert_eq!(queued(&database).await, [("a".to_string(), 1)]);
+        assert!(index.take_due(MODEL, 10).await.unwrap().is_empty());
+
+        let status = index.status(MODEL).await.unwrap();
+        assert_eq!(status.queued, 1);
+        assert_eq!(status.last_error.as_deref(), Some("connection refused"));
+
+        // Giving up after the last attempt
+        sqlx::query("UPDATE embedding_queue SET attempts = ?, next_attempt_at = NULL")
+            .bind(MAX_ATTEMPTS)
+            .execute(database.pool())
+            .await
+            .unwrap();
+        assert!(index.take_due(MODEL, 10).await.unwrap().is_empty());
+        let status = index.status(MODEL).await.unwrap();
+        assert_eq!((status.queued, status.failed), (0, 1));
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_a_limit() {
+        let now = Utc::now();
+        let delay = |attempts| (retry_at(now, attempts) - now).num_seconds();
+
+        assert_eq!(delay(1), 30);
+        assert_eq!(delay(2), 60);
+        assert_eq!(delay(3), 120);
+        assert_eq!(delay(11), 30 << 10);
+        assert_eq!(delay(50), 30 << 10);
+    }
+}
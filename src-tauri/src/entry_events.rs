@@ -0,0 +1,183 @@
+/**
+ * Entry change events for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Recording which entries were created, updated or deleted
+ * - Coalescing the changes made by one operation into a single batch
+ * - The payload sent with the entries:// events
+ *
+ * The database records every change it makes; commands take the batch when
+ * they finish and emit it, so imports, syncs and bulk deletes reach the
+ * other windows as one event per kind rather than one per entry.
+ */
+use serde::{Deserialize, Serialize};
+
+pub const ENTRIES_CREATED: &str = "entries://created";
+pub const ENTRIES_UPDATED: &str = "entries://updated";
+pub const ENTRIES_DELETED: &str = "entries://deleted";
+
+/// What made the change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeSource {
+    /// Written or deleted in the app
+    Editor,
+    /// Imported from a feed or another service
+    Import,
+    /// Received from another device
+    Sync,
+    /// Changed in a Markdown journal's folder outside the app
+    Folder,
+    /// Restored from the journal's git history
+    History,
+}
+
+/// Payload of the entries:// events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntriesChanged {
+    pub ids: Vec<String>,
+    pub source: ChangeSource,
+}
+
+/// The net effect of a series of changes, in the order entries were first touched
+#[derive(Debug, Clone, Default)]
+pub struct EntryChanges {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+impl EntryChanges {
+    pub fn record_created(&mut self, id: &str) {
+        // Deleted and created again, e.g. a conflict copy replacing an entry
+        if remove(&mut self.deleted, id) {
+            push(&mut self.updated, id);
+        } else {
+            push(&mut self.created, id);
+        }
+    }
+
+    pub fn record_updated(&mut self, id: &str) {
+        if !self.created.iter().any(|created| created == id) {
+            push(&mut self.updated, id);
+        }
+    }
+
+    pub fn record_deleted(&mut self, id: &str) {
+        remove(&mut self.updated, id);
+        // Nobody else has seen an entry created in the same batch
+        if !remove(&mut self.created, id) {
+            push(&mut self.deleted, id);
+        }
+    }
+
+    /// Apply the changes of a later batch on top of these
+    pub fn merge(&mut self, later: &EntryChanges) {
+        for id in &later.created {
+            self.record_created(id);
+        }
+        for id in &later.updated {
+            self.record_updated(id);
+        }
+        for id in &later.deleted {
+            self.record_deleted(id);
+        }
+    }
+
+    /// The events to emit, skipping kinds with no changes
+    pub fn events(self, source: ChangeSource) -> Vec<(&'static str, EntriesChanged)> {
+        [
+            (ENTRIES_CREATED, self.created),
+            (ENTRIES_UPDATED, self.updated),
+            (ENTRIES_DELETED, self.deleted),
+        ]
+        .into_iter()
+        .filter(|(_, ids)| !ids.is_empty())
+        .map(|(name, ids)| (name, EntriesChanged { ids, source }))
+        .collect()
+    }
+}
+
+fn push(ids: &mut Vec<String>, id: &str) {
+    if !ids.iter().any(|existing| existing == id) {
+        ids.push(id.to_string());
+    }
+}
+
+fn remove(ids: &mut Vec<String>, id: &str) -> bool {
+    let before = ids.len();
+    ids.retain(|existing| existing != id);
+    ids.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[String]) -> Vec<&str> {
+        ids.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn created_then_deleted_cancels_out() {
+        let mut changes = EntryChanges::default();
+        changes.record_created("a");
+        changes.record_updated("a");
+        changes.record_deleted("a");
+
+        assert!(changes.created.is_empty());
+        assert!(changes.updated.is_empty());
+        assert!(changes.deleted.is_empty());
+        assert!(changes.events(ChangeSource::Editor).is_empty());
+    }
+
+    #[test]
+    fn deleted_then_created_is_an_update() {
+        let mut changes = EntryChanges::default();
+        changes.record_updated("a");
+        changes.record_deleted("a");
+        changes.record_created("a");
+
+        assert!(changes.created.is_empty());
+        assert_eq!(ids(&changes.updated), ["a"]);
+        assert!(changes.deleted.is_empty());
+    }
+
+    #[test]
+    fn updates_to_new_entries_stay_creations() {
+        let mut changes = EntryChanges::default();
+        changes.record_created("a");
+        changes.record_updated("a");
+        changes.record_updated("b");
+        changes.record_updated("b");
+
+        assert_eq!(ids(&changes.created), ["a"]);
+        assert_eq!(ids(&changes.updated), ["b"]);
+    }
+
+    #[test]
+    fn merge_applies_later_changes_on_top() {
+        let mut earlier = EntryChanges::default();
+        earlier.record_created("a");
+        earlier.record_updated("b");
+        earlier.record_deleted("c");
+
+        let mut later = EntryChanges::default();
+        later.record_deleted("a");
+        later.record_deleted("b");
+        later.record_created("c");
+        later.record_created("d");
+
+        earlier.merge(&later);
+        assert_eq!(ids(&earlier.created), ["d"]);
+        assert_eq!(ids(&earlier.updated), ["c"]);
+        assert_eq!(ids(&earlier.deleted), ["b"]);
+
+        let events = earlier.events(ChangeSource::Sync);
+        let names: Vec<&str> = events.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, [ENTRIES_CREATED, ENTRIES_UPDATED, ENTRIES_DELETED]);
+        assert!(events
+            .iter()
+            .all(|(_, payload)| payload.source == ChangeSource::Sync));
+    }
+}
@@ -0,0 +1,288 @@
+/**
+ * Git history for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Turning a Markdown journal folder into a git repository
+ * - Committing each entry file when it is created, updated or deleted
+ * - Reading an entry's history and its file at a given commit
+ * - Pushing to a configured remote
+ *
+ * Everything goes through the `git` command line, so the repository is an
+ * ordinary one that can be inspected, cloned or repaired with plain git.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+const REMOTE: &str = "origin";
+const GITIGNORE: &str = "# Temporary files written while saving entries\n.*.tmp\n";
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCommit {
+    pub hash: String,
+    pub author: String,
+    pub date: DateTime<Utc>,
+    pub message: String,
+    /// The entry's file in this commit, relative to the journal folder
+    pub path: String,
+    /// The entry was deleted by this commit
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushResult {
+    pub remote: Option<String>,
+    pub pushed: bool,
+    pub message: String,
+}
+
+pub struct GitRepository {
+    folder: PathBuf,
+}
+
+impl GitRepository {
+    /// Use the repository in `folder`, creating it if needed
+    pub async fn open(folder: &Path) -> Result<Self> {
+        tokio::fs::create_dir_all(folder)
+            .await
+            .with_context(|| format!("Failed to create journal folder {:?}", folder))?;
+
+        let repository = GitRepository {
+            folder: folder.to_path_buf(),
+        };
+        if !tokio::fs::try_exists(folder.join(".git")).await? {
+            repository.init().await?;
+        }
+
+        Ok(repository)
+    }
+
+    async fn init(&self) -> Result<()> {
+        self.git(&["init", "--quiet"]).await?;
+
+        // Commits need an author; keep the user's own if git already has one
+        if self.git(&["config", "user.email"]).await.is_err() {
+            self.git(&["config", "user.name", "MyFace SnapJournal"])
+                .await?;
+            self.git(&["config", "user.email", "journal@localhost"])
+                .await?;
+        }
+
+        let gitignore = self.folder.join(".gitignore");
+        if !tokio::fs::try_exists(&gitignore).await? {
+            tokio::fs::write(&gitignore, GITIGNORE)
+                .await
+                .context("Failed to write .gitignore")?;
+        }
+
+        // Entries already in the folder become the first commit
+        self.git(&["add", "--", ".gitignore"]).await?;
+        self.commit_all("Start journal history").await?;
+
+        Ok(())
+    }
+
+    /// Commit the current state of `paths`; `None` when nothing changed
+    pub async fn commit(&self, paths: &[&str], message: &str) -> Result<Option<String>> {
+        let mut tracked = Vec::new();
+        for path in paths {
+            let exists = tokio::fs::try_exists(self.folder.join(path)).await?;
+            let known = !self.git(&["ls-files", "--", path]).await?.trim().is_empty();
+            // A file that was never committed and is already gone leaves no trace
+            if exists || known {
+                tracked.push(*path);
+            }
+        }
+        if tracked.is_empty() {
+            return Ok(None);
+        }
+
+        let mut add = vec!["add", "--all", "--"];
+        add.extend(&tracked);
+        self.git(&add).await?;
+
+        let mut staged = vec!["diff", "--cached", "--name-only", "--"];
+        staged.extend(&tracked);
+        if self.git(&staged).await?.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut commit = vec!["commit", "--quiet", "--message", message, "--"];
+        commit.extend(&tracked);
+        self.git(&commit).await?;
+
+        Ok(Some(
+            self.git(&["rev-parse", "HEAD"]).await?.trim().to_string(),
+        ))
+    }
+
+    /// Commit every added, edited or removed Markdown file in the folder
+    pub async fn commit_all(&self, message: &str) -> Result<Option<String>> {
+        self.git(&["add", "--all", "--", "*.md"]).await?;
+        if self
+            .git(&["diff", "--cached", "--name-only"])
+            .await?
+            .trim()
+            .is_empty()
+        {
+            return Ok(None);
+        }
+
+        self.git(&["commit", "--quiet", "--message", message])
+            .await?;
+        Ok(Some(
+            self.git(&["rev-parse", "HEAD"]).await?.trim().to_string(),
+        ))
+    }
+
+    /// The last file that held the entry, found through the commits that
+    /// added or removed its id
+    pub async fn find_entry_path(&self, id: &str) -> Result<Option<String>> {
+        let output = self
+            .git(&[
+                "log",
+                "--format=",
+                "--name-only",
+                "-1",
+                "-S",
+                id,
+                "--",
+                "*.md",
+            ])
+            .await?;
+
+        Ok(output
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string))
+    }
+
+    /// Commits that touched `path`, newest first, following renames
+    pub async fn log(&self, path: &str) -> Result<Vec<GitCommit>> {
+        let format = format!("--format=%x1e%H{0}%an{0}%aI{0}%s", FIELD_SEPARATOR);
+        let output = self
+            .git(&["log", &format, "--name-status", "--follow", "--", path])
+            .await?;
+
+        let mut commits = Vec::new();
+        for record in output.split('\u{1e}').filter(|r| !r.trim().is_empty()) {
+            let mut lines = record.lines();
+            let header = lines.next().unwrap_or_default();
+            let fields: Vec<&str> = header.split(FIELD_SEPARATOR).collect();
+            let [hash, author, date, message] = fields[..] else {
+                continue;
+            };
+
+            // "M\tpath", "D\tpath" or "R100\told\tnew"
+            let status = lines.map(str::trim).find(|line| !line.is_empty());
+            let (deleted, file) = match status {
+                Some(status) => (
+                    status.starts_with('D'),
+                    status.rsplit('\t').next().unwrap_or(path).to_string(),
+                ),
+                None => (false, path.to_string()),
+            };
+
+            commits.push(GitCommit {
+                hash: hash.to_string(),
+                author: author.to_string(),
+                date: DateTime::parse_from_rfc3339(date)
+                    .with_context(|| format!("Invalid commit date: {}", date))?
+                    .with_timezone(&Utc),
+                message: message.to_string(),
+                path: file,
+                deleted,
+            });
+        }
+
+        Ok(commits)
+    }
+
+    /// The contents of `path` as of `commit`
+    pub async fn show(&self, commit: &str, path: &str) -> Result<String> {
+        if commit.starts_with('-') || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!("Invalid commit: {}", commit));
+        }
+
+        self.git(&["show", &format!("{}:{}", commit, path)])
+            .await
+            .with_context(|| format!("{} has no {}", commit, path))
+    }
+
+    pub async fn remote_url(&self) -> Result<Option<String>> {
+        Ok(self
+            .git(&["remote", "get-url", REMOTE])
+            .await
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty()))
+    }
+
+    /// Point the repository at `url`, or stop pushing anywhere
+    pub async fn set_remote(&self, url: Option<&str>) -> Result<()> {
+        let current = self.remote_url().await?;
+        match (current, url) {
+            (None, None) => Ok(()),
+            (Some(_), None) => self.git(&["remote", "remove", REMOTE]).await.map(|_| ()),
+            (None, Some(url)) => self.git(&["remote", "add", REMOTE, url]).await.map(|_| ()),
+            (Some(_), Some(url)) => self
+                .git(&["remote", "set-url", REMOTE, url])
+                .await
+                .map(|_| ()),
+        }
+    }
+
+    /// Push the current branch if a remote is configured
+    pub async fn push(&self) -> Result<PushResult> {
+        let Some(remote) = self.remote_url().await? else {
+            return Ok(PushResult {
+                remote: None,
+                pushed: false,
+                message: "No remote is configured".to_string(),
+            });
+        };
+
+        match self
+            .git(&["push", "--porcelain", "--set-upstream", REMOTE, "HEAD"])
+            .await
+        {
+            Ok(output) => Ok(PushResult {
+                remote: Some(remote),
+                pushed: true,
+                message: output.trim().to_string(),
+            }),
+            // An unreachable remote is expected while offline
+            Err(e) => Ok(PushResult {
+                remote: Some(remote),
+                pushed: false,
+                message: format!("{:#}", e),
+            }),
+        }
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.folder)
+            .args(args)
+            // Never wait for credentials on a terminal nobody sees
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .await
+            .context("Failed to run git; is it installed?")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
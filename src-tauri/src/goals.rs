@@ -0,0 +1,306 @@
+/**
+ * Writing goals for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Current and longest writing streaks
+ * - Configurable goals (entries per week, words per day) and their progress
+ * - Per-day calendar heatmap data
+ *
+ * Everything is computed from the daily aggregates in journal_statistics,
+ * which are bucketed by the day each entry was written on where it was
+ * written. "Today" is the day in the journal timezone.
+ */
+use anyhow::{Context, Result};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::database::Database;
+use crate::settings::SettingsStore;
+use crate::statistics::{DailyTotals, StatisticsRange};
+
+const GOALS_SETTING: &str = "writing_goals";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WritingGoals {
+    pub entries_per_week: Option<u32>,
+    pub words_per_day: Option<u32>,
+    /// Sources (e.g. "mastodon") that don't count toward streaks, goals or the heatmap
+    #[serde(default)]
+    pub excluded_sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreakSummary {
+    /// Consecutive days up to today, or up to yesterday if nothing was written today yet
+    pub current: u32,
+    pub longest: u32,
+    pub longest_start: Option<NaiveDate>,
+    pub longest_end: Option<NaiveDate>,
+    pub last_entry_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub week_start: NaiveDate,
+    pub entries_this_week: i64,
+    pub entries_per_week: Option<u32>,
+    pub words_today: i64,
+    pub words_per_day: Option<u32>,
+    /// Fraction of each goal reached, capped at 1.0
+    pub entries_progress: Option<f64>,
+    pub words_progress: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapDay {
+    pub date: NaiveDate,
+    pub entries: i64,
+    pub words: i64,
+}
+
+pub struct GoalTracker<'a> {
+    database: &'a Database,
+}
+
+impl<'a> GoalTracker<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        GoalTracker { database }
+    }
+
+    pub async fn goals(&self) -> Result<WritingGoals> {
+        match self.database.get_setting(GOALS_SETTING).await? {
+            Some(value) if !value.is_empty() => {
+                serde_json::from_str(&value).context("Invalid writing goals setting")
+            }
+            _ => Ok(WritingGoals::default()),
+        }
+    }
+
+    pub async fn set_goals(&self, goals: &WritingGoals) -> Result<()> {
+        self.database
+            .set_setting(GOALS_SETTING, &serde_json::to_string(goals)?)
+            .await
+    }
+
+    pub async fn streaks(&self) -> Result<StreakSummary> {
+        let goals = self.goals().await?;
+        let days = self
+            .database
+            .statistics()
+            .daily_totals(&StatisticsRange::default(), &goals.excluded_sources)
+            .await?;
+        let today = SettingsStore::new(self.database).timezone().await?.today();
+
+        Ok(streaks(&days, today))
+    }
+
+    pub async fn progress(&self) -> Result<GoalProgress> {
+        let goals = self.goals().await?;
+        let today = SettingsStore::new(self.database).timezone().await?.today();
+        let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+
+        let days = self
+            .database
+            .statistics()
+            .daily_totals(
+                &StatisticsRange {
+                    start: Some(week_start),
+                    end: Some(today),
+                },
+                &goals.excluded_sources,
+            )
+            .await?;
+
+        let entries_this_week = days.values().map(|d| d.entries).sum();
+        let words_today = days.get(&today).map_or(0, |d| d.words);
+
+        Ok(GoalProgress {
+            week_start,
+            entries_this_week,
+            entries_per_week: goals.entries_per_week,
+            words_today,
+            words_per_day: goals.words_per_day,
+            entries_progress: fraction(entries_this_week, goals.entries_per_week),
+            words_progress: fraction(words_today, goals.words_per_day),
+        })
+    }
+
+    /// Per-day counts for every day of `year` that has entries
+    pub async fn heatmap(&self, year: i32) -> Result<Vec<HeatmapDay>> {
+        let goals = self.goals().await?;
+        let range = StatisticsRange {
+            start: NaiveDate::from_ymd_opt(year, 1, 1),
+            end: NaiveDate::from_ymd_opt(year, 12, 31),
+        };
+        if range.start.is_none() {
+            return Err(anyhow::anyhow!("Invalid year: {}", year));
+        }
+
+        let days = self
+            .database
+            .statistics()
+            .daily_totals(&range, &goals.excluded_sources)
+            .await?;
+
+        Ok(days
+            .into_iter()
+            .map(|(date, totals)| HeatmapDay {
+                date,
+                entries: totals.entries,
+                words: totals.words,
+            })
+            .collect())
+    }
+}
+
+/// Streaks through the days that have entries, as of `today`
+fn streaks(days: &BTreeMap<NaiveDate, DailyTotals>, today: NaiveDate) -> StreakSummary {
+    let mut longest = 0;
+    let mut longest_start = None;
+    let mut longest_end = None;
+    let mut run = 0;
+    let mut run_start = None;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &date in days.keys() {
+        if previous.is_some_and(|p| p + Duration::days(1) == date) {
+            run += 1;
+        } else {
+            run = 1;
+            run_start = Some(date);
+        }
+        if run > longest {
+            longest = run;
+            longest_start = run_start;
+            longest_end = Some(date);
+        }
+        previous = Some(date);
+    }
+
+    let mut cursor = if days.contains_key(&today) {
+        today
+    } else {
+        today - Duration::days(1)
+    };
+    let mut current = 0;
+    while days.contains_key(&cursor) {
+        current += 1;
+        cursor -= Duration::days(1);
+    }
+
+    StreakSummary {
+        current,
+        longest,
+        longest_start,
+        longest_end,
+        last_entry_date: previous,
+    }
+}
+
+fn fraction(value: i64, target: Option<u32>) -> Option<f64> {
+    target
+        .filter(|t| *t > 0)
+        .map(|t| (value as f64 / t as f64).min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::JournalTimezone;
+    use crate::test_support::{entry_at, TempJournal};
+    use chrono::{FixedOffset, TimeZone};
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    fn written_on(days: &[u32]) -> BTreeMap<NaiveDate, DailyTotals> {
+        days.iter()
+            .map(|&d| {
+                (
+                    day(d),
+                    DailyTotals {
+                        entries: 1,
+                        words: 10,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn streaks_count_consecutive_days() {
+        let days = written_on(&[2, 3, 4, 5, 9, 10, 11]);
+
+        let summary = streaks(&days, day(11));
+        assert_eq!((summary.current, summary.longest), (3, 4));
+        assert_eq!(summary.longest_start, Some(day(2)));
+        assert_eq!(summary.longest_end, Some(day(5)));
+        assert_eq!(summary.last_entry_date, Some(day(11)));
+
+        // Nothing written yet today keeps yesterday's streak going
+        assert_eq!(streaks(&days, day(12)).current, 3);
+        assert_eq!(streaks(&days, day(13)).current, 0);
+        assert_eq!(streaks(&BTreeMap::new(), day(13)).longest, 0);
+    }
+
+    #[tokio::test]
+    async fn streaks_follow_the_day_each_entry_was_written() {
+        let database = TempJournal::new().await;
+        let mut patch = serde_json::Map::new();
+        patch.insert("timezone".to_string(), "+14:00".into());
+        SettingsStore::new(&database).update(patch).await.unwrap();
+        let today = JournalTimezone::parse("+14:00").unwrap().today();
+
+        // Written late yesterday in Samoa, hours after today began in Kiribati
+        let at = |offset_hours: i32, date: NaiveDate, hour: u32| {
+            FixedOffset::east_opt(offset_hours * 3600)
+                .unwrap()
+                .from_local_datetime(&date.and_hms_opt(hour, 30, 0).unwrap())
+                .unwrap()
+                .to_rfc3339()
+        };
+        let yesterday = today - Duration::days(1);
+        for (id, date) in [
+            ("kiribati", at(14, today, 0)),
+            ("samoa", at(-11, yesterday, 23)),
+        ] {
+            database
+                .create_entry(&entry_at(id, "Short note", &date))
+                .await
+                .unwrap();
+        }
+
+        let summary = GoalTracker::new(&database).streaks().await.unwrap();
+        assert_eq!(summary.current, 2);
+        assert_eq!(summary.longest_start, Some(yesterday));
+        assert_eq!(summary.last_entry_date, Some(today));
+    }
+
+    #[tokio::test]
+    async fn excluded_sources_ignore_case() {
+        let database = TempJournal::new().await;
+        let mut post = entry_at("post", "Cross-posted", "2026-03-10T09:00:00Z");
+        post.source = Some("Mastodon".to_string());
+        database.create_entry(&post).await.unwrap();
+        database
+            .create_entry(&entry_at("note", "Written here", "2026-03-11T09:00:00Z"))
+            .await
+            .unwrap();
+
+        let tracker = GoalTracker::new(&database);
+        tracker
+            .set_goals(&WritingGoals {
+                excluded_sources: vec![" mastodon".to_string()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let heatmap = tracker.heatmap(2026).await.unwrap();
+        assert_eq!(
+            heatmap.iter().map(|day| day.date).collect::<Vec<_>>(),
+            [day(11)]
+        );
+    }
+}
@@ -0,0 +1,259 @@
+/**
+ * Journal registry for MyFace SnapJournal
+ *
+ * This module handles:
+ * - The list of journals (notebooks/vaults) kept in the app config dir
+ * - Creating, renaming and removing journals
+ * - Remembering the last opened journal
+ *
+ * Each journal is its own database file, so opening one never exposes
+ * entries or AI context from another.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::storage::StorageKind;
+
+const REGISTRY_FILE: &str = "journals.json";
+const CREDENTIALS_DIR: &str = "credentials";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalInfo {
+    pub id: String,
+    pub name: String,
+    /// The database file, or the folder of a Markdown journal
+    pub path: PathBuf,
+    #[serde(default)]
+    pub storage: StorageKind,
+    #[serde(default)]
+    pub encrypted: bool,
+    pub color: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_opened_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryFile {
+    journals: Vec<JournalInfo>,
+    last_opened: Option<String>,
+}
+
+pub struct JournalRegistry {
+    file_path: PathBuf,
+    journals_dir: PathBuf,
+    data: RegistryFile,
+}
+
+impl JournalRegistry {
+    /// Load the registry from `config_dir`; new journals default to `journals_dir`
+    pub async fn load(config_dir: &Path, journals_dir: PathBuf) -> Result<Self> {
+        let file_path = config_dir.join(REGISTRY_FILE);
+
+        let data = match tokio::fs::read_to_string(&file_path).await {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid journal registry: {:?}", file_path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => RegistryFile::default(),
+            Err(e) => return Err(e).context("Failed to read journal registry"),
+        };
+
+        Ok(JournalRegistry {
+            file_path,
+            journals_dir,
+            data,
+        })
+    }
+
+    pub fn list(&self) -> &[JournalInfo] {
+        &self.data.journals
+    }
+
+    pub fn get(&self, id: &str) -> Option<&JournalInfo> {
+        self.data.journals.iter().find(|j| j.id == id)
+    }
+
+    pub fn find_by_path(&self, path: &Path) -> Option<&JournalInfo> {
+        self.data.journals.iter().find(|j| j.path == path)
+    }
+
+    /// The journal to open on startup
+    pub fn last_opened(&self) -> Option<&JournalInfo> {
+        self.data
+            .last_opened
+            .as_deref()
+            .and_then(|id| self.get(id))
+            .or_else(|| self.data.journals.first())
+    }
+
+    /// Where a journal's database, or Markdown folder, goes when no path is given
+    pub fn default_path(&self, id: &str, storage: StorageKind) -> PathBuf {
+        match storage {
+            StorageKind::Sqlite => self.journals_dir.join(id).join("journal.db"),
+            StorageKind::Markdown | StorageKind::Git => self.journals_dir.join(id).join("entries"),
+        }
+    }
+
+    /// The index database of a Markdown journal, kept out of its folder
+    pub fn index_path(&self, id: &str) -> PathBuf {
+        self.journals_dir.join(id).join("index.db")
+    }
+
+    /// Local copy of the change logs exchanged with a WebDAV server
+    pub fn webdav_mirror_dir(&self, id: &str) -> PathBuf {
+        self.journals_dir.join(id).join("webdav")
+    }
+
+    /// WebDAV account of a journal, kept with the registry rather than in the journal
+    pub fn webdav_credentials_path(&self, id: &str) -> PathBuf {
+        self.file_path
+            .with_file_name(CREDENTIALS_DIR)
+            .join(format!("{}.json", id))
+    }
+
+    pub async fn create(
+        &mut self,
+        name: &str,
+        path: Option<PathBuf>,
+        storage: StorageKind,
+        encrypted: bool,
+        color: Option<String>,
+    ) -> Result<JournalInfo> {
+        let name = validate_name(name)?;
+        validate_color(color.as_deref())?;
+
+        let id = Uuid::new_v4().to_string();
+        let path = path.unwrap_or_else(|| self.default_path(&id, storage));
+        if let Some(existing) = self.find_by_path(&path) {
+            return Err(anyhow::anyhow!(
+                "{:?} is already registered as \"{}\"",
+                path,
+                existing.name
+            ));
+        }
+
+        let journal = JournalInfo {
+            id,
+            name,
+            path,
+            storage,
+            encrypted,
+            color,
+            created_at: Utc::now(),
+            last_opened_at: None,
+        };
+
+        self.data.journals.push(journal.clone());
+        self.save().await?;
+
+        Ok(journal)
+    }
+
+    pub async fn rename(&mut self, id: &str, name: &str) -> Result<JournalInfo> {
+        let name = validate_name(name)?;
+        let journal = self.get_mut(id)?;
+        journal.name = name;
+        let journal = journal.clone();
+
+        self.save().await?;
+        Ok(journal)
+    }
+
+    /// Point a journal at the new location of its database
+    pub async fn set_path(&mut self, id: &str, path: PathBuf) -> Result<JournalInfo> {
+        let journal = self.get_mut(id)?;
+        journal.path = path;
+        let journal = journal.clone();
+
+        self.save().await?;
+        Ok(journal)
+    }
+
+    /// Forget a journal; its files are left alone
+    pub async fn remove(&mut self, id: &str) -> Result<JournalInfo> {
+        let index = self
+            .data
+            .journals
+            .iter()
+            .position(|j| j.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Journal not found: {}", id))?;
+
+        let journal = self.data.journals.remove(index);
+        if self.data.last_opened.as_deref() == Some(id) {
+            self.data.last_opened = None;
+        }
+
+        self.save().await?;
+        Ok(journal)
+    }
+
+    pub async fn mark_opened(&mut self, id: &str) -> Result<JournalInfo> {
+        let journal = self.get_mut(id)?;
+        journal.last_opened_at = Some(Utc::now());
+        let journal = journal.clone();
+
+        self.data.last_opened = Some(journal.id.clone());
+        self.save().await?;
+        Ok(journal)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Result<&mut JournalInfo> {
+        self.data
+            .journals
+            .iter_mut()
+            .find(|j| j.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Journal not found: {}", id))
+    }
+
+    /// Write the registry via a temporary file so a crash never leaves it half written
+    async fn save(&self) -> Result<()> {
+        if let Some(parent) = self.file_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create config directory")?;
+        }
+
+        let temp_path = self.file_path.with_extension("json.tmp");
+        tokio::fs::write(&temp_path, serde_json::to_vec_pretty(&self.data)?)
+            .await
+            .context("Failed to write journal registry")?;
+        tokio::fs::rename(&temp_path, &self.file_path)
+            .await
+            .context("Failed to replace journal registry")?;
+
+        Ok(())
+    }
+}
+
+/// Remove a journal's database file and its WAL/SHM side files
+pub async fn delete_journal_files(path: &Path) -> Result<()> {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        match tokio::fs::remove_file(&file).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to delete {:?}", file)),
+        }
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Journal name cannot be empty"));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_color(color: Option<&str>) -> Result<()> {
+    if let Some(color) = color {
+        let hex = color.strip_prefix('#').unwrap_or("");
+        if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!("Invalid journal color: {}", color));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,449 @@
+/**
+ * Entry locations for MyFace SnapJournal
+ *
+ * This module handles:
+ * - The structured location attached to an entry
+ * - Reading GPS coordinates from a photo's EXIF data
+ * - Reading the location of an entry imported from Day One
+ * - Offline reverse geocoding against the bundled GeoNames cities
+ * - Map bounds and the places aggregation
+ *
+ * Reverse geocoding never leaves the machine: coordinates are matched to
+ * the nearest city in geonames/cities.tsv. The bundled file is only a
+ * hand-picked sample of a few hundred cities, so places far from them get
+ * no name; build-cities.sh regenerates it from a full GeoNames dump.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Row, SqlitePool};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::database::parse_timestamp;
+
+const CITIES: &str = include_str!("../geonames/cities.tsv");
+
+/// The `source` of entries imported from Day One
+pub const DAY_ONE_SOURCE: &str = "dayone";
+
+/// Coordinates further than this from every known city get no place name
+const MAX_PLACE_DISTANCE_KM: f64 = 100.0;
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// EXIF tags
+const GPS_IFD_POINTER: u16 = 0x8825;
+const GPS_LATITUDE_REF: u16 = 0x0001;
+const GPS_LATITUDE: u16 = 0x0002;
+const GPS_LONGITUDE_REF: u16 = 0x0003;
+const GPS_LONGITUDE: u16 = 0x0004;
+const GPS_POSITIONING_ERROR: u16 = 0x001f;
+
+/// Where an entry's location came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationSource {
+    #[default]
+    Manual,
+    /// GPS coordinates of an attached photo
+    Exif,
+    /// The location of an entry imported from Day One
+    DayOne,
+}
+
+impl LocationSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LocationSource::Manual => "manual",
+            LocationSource::Exif => "exif",
+            LocationSource::DayOne => "day_one",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "manual" => Some(LocationSource::Manual),
+            "exif" => Some(LocationSource::Exif),
+            "day_one" => Some(LocationSource::DayOne),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Radius in metres the position is known to be within
+    #[serde(default)]
+    pub accuracy: Option<f64>,
+    #[serde(default)]
+    pub place_name: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub source: LocationSource,
+}
+
+impl EntryLocation {
+    pub fn validate(&self) -> Result<()> {
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            return Err(anyhow::anyhow!("Invalid latitude: {}", self.latitude));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(anyhow::anyhow!("Invalid longitude: {}", self.longitude));
+        }
+        if let Some(accuracy) = self.accuracy {
+            if !accuracy.is_finite() || accuracy < 0.0 {
+                return Err(anyhow::anyhow!("Invalid accuracy: {}", accuracy));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate and complete a location sent by the frontend or an importer
+    pub fn prepare(self) -> Result<Self> {
+        self.validate()?;
+        Ok(self.complete())
+    }
+
+    /// Trim the names and fill in whichever are missing from the nearest city
+    pub fn complete(mut self) -> Self {
+        self.place_name = clean(self.place_name);
+        self.country = clean(self.country);
+
+        if self.place_name.is_none() || self.country.is_none() {
+            if let Some(place) = reverse_geocode(self.latitude, self.longitude) {
+                self.place_name.get_or_insert(place.name);
+                self.country.get_or_insert(place.country);
+            }
+        }
+
+        self
+    }
+
+    /// The `location` of a Day One JSON export entry
+    pub fn from_day_one(location: &Value) -> Option<Self> {
+        let text = |key: &str| {
+            location
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        Some(EntryLocation {
+            latitude: location.get("latitude")?.as_f64()?,
+            longitude: location.get("longitude")?.as_f64()?,
+            accuracy: location
+                .get("region")
+                .and_then(|region| region.get("radius"))
+                .and_then(Value::as_f64),
+            place_name: text("placeName").or_else(|| text("localityName")),
+            country: text("country"),
+            source: LocationSource::DayOne,
+        })
+    }
+}
+
+/// The city nearest to a pair of coordinates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeocodedPlace {
+    pub name: String,
+    pub country_code: String,
+    pub country: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub distance_km: f64,
+}
+
+/// A place entries were written at, with how many
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    pub country: Option<String>,
+    /// The average position of the entries written there
+    pub latitude: f64,
+    pub longitude: f64,
+    pub entry_count: i64,
+    pub first_entry_date: DateTime<Utc>,
+    pub last_entry_date: DateTime<Utc>,
+}
+
+/// A map area; `west` is greater than `east` when it crosses the antimeridian
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Bounds {
+    pub north: f64,
+    pub south: f64,
+    pub east: f64,
+    pub west: f64,
+}
+
+impl Bounds {
+    pub fn validate(&self) -> Result<()> {
+        if !(-90.0..=90.0).contains(&self.south) || !(-90.0..=90.0).contains(&self.north) {
+            return Err(anyhow::anyhow!("Latitudes must be between -90 and 90"));
+        }
+        if self.south > self.north {
+            return Err(anyhow::anyhow!("The south edge is north of the north edge"));
+        }
+        if !(-180.0..=180.0).contains(&self.west) || !(-180.0..=180.0).contains(&self.east) {
+            return Err(anyhow::anyhow!("Longitudes must be between -180 and 180"));
+        }
+
+        Ok(())
+    }
+
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.west > self.east
+    }
+}
+
+/// Distinct places with entry counts, most written about first
+///
+/// Entries too far from any city to have a place name are left out.
+pub async fn list_places(pool: &SqlitePool) -> Result<Vec<Place>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT location_name, location_country,
+               AVG(location_latitude) AS latitude, AVG(location_longitude) AS longitude,
+               COUNT(*) AS entry_count,
+               MIN(entry_date) AS first_entry_date, MAX(entry_date) AS last_entry_date
+        FROM journal_entries
+        WHERE location_name IS NOT NULL AND location_latitude IS NOT NULL
+        GROUP BY location_name, location_country
+        ORDER BY entry_count DESC, location_name
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to list places")?;
+
+    let mut places = Vec::new();
+    for row in rows {
+        places.push(Place {
+            name: row.get("location_name"),
+            country: row.get("location_country"),
+            latitude: row.get("latitude"),
+            longitude: row.get("longitude"),
+            entry_count: row.get("entry_count"),
+            first_entry_date: parse_timestamp(&row.get::<String, _>("first_entry_date"))?,
+            last_entry_date: parse_timestamp(&row.get::<String, _>("last_entry_date"))?,
+        });
+    }
+
+    Ok(places)
+}
+
+struct City {
+    name: &'static str,
+    country_code: &'static str,
+    country: &'static str,
+    latitude: f64,
+    longitude: f64,
+}
+
+fn cities() -> &'static [City] {
+    static PARSED: OnceLock<Vec<City>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        CITIES
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let [name, country_code, country, latitude, longitude] = fields[..] else {
+                    return None;
+                };
+                Some(City {
+                    name,
+                    country_code,
+                    country,
+                    latitude: latitude.parse().ok()?,
+                    longitude: longitude.parse().ok()?,
+                })
+            })
+            .collect()
+    })
+}
+
+/// The nearest bundled city, if one is close enough to name the place
+pub fn reverse_geocode(latitude: f64, longitude: f64) -> Option<GeocodedPlace> {
+    let (city, distance_km) = cities()
+        .iter()
+        .map(|city| {
+            let distance = distance_km(latitude, longitude, city.latitude, city.longitude);
+            (city, distance)
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+    if distance_km > MAX_PLACE_DISTANCE_KM {
+        return None;
+    }
+
+    Some(GeocodedPlace {
+        name: city.name.to_string(),
+        country_code: city.country_code.to_string(),
+        country: city.country.to_string(),
+        latitude: city.latitude,
+        longitude: city.longitude,
+        distance_km,
+    })
+}
+
+/// Great-circle distance by the haversine formula
+fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// The GPS position recorded in a JPEG or TIFF photo, if any
+pub async fn photo_location(path: &Path) -> Result<Option<EntryLocation>> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read photo {:?}", path))?;
+
+    Ok(exif_data(&bytes)
+        .and_then(gps_location)
+        .map(EntryLocation::complete))
+}
+
+/// The TIFF structure holding the EXIF data, found in a JPEG's APP1 segment
+fn exif_data(bytes: &[u8]) -> Option<&[u8]> {
+    if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        return Some(bytes);
+    }
+    if !bytes.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+
+    let mut position = 2;
+    while let Some(&[0xff, marker, high, low]) = bytes.get(position..position + 4) {
+        // Image data starts at the scan; metadata always comes before it
+        if marker == 0xda || marker == 0xd9 {
+            return None;
+        }
+
+        let length = u16::from_be_bytes([high, low]) as usize;
+        let segment = bytes.get(position + 4..position + 2 + length)?;
+        if marker == 0xe1 {
+            if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                return Some(tiff);
+            }
+        }
+        position += 2 + length;
+    }
+
+    None
+}
+
+fn gps_location(tiff: &[u8]) -> Option<EntryLocation> {
+    let reader = TiffReader {
+        data: tiff,
+        little_endian: match tiff.get(0..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        },
+    };
+
+    let first_ifd = reader.u32(4)? as usize;
+    let gps_ifd = reader.u32(reader.find(first_ifd, GPS_IFD_POINTER)? + 8)? as usize;
+
+    let coordinate = |value_tag, ref_tag, negative| -> Option<f64> {
+        let [degrees, minutes, seconds] = reader.rationals(reader.find(gps_ifd, value_tag)?)?[..]
+        else {
+            return None;
+        };
+        let hemisphere = *reader.data.get(reader.find(gps_ifd, ref_tag)? + 8)?;
+        let value = degrees + minutes / 60.0 + seconds / 3600.0;
+        Some(if hemisphere == negative {
+            -value
+        } else {
+            value
+        })
+    };
+
+    let latitude = coordinate(GPS_LATITUDE, GPS_LATITUDE_REF, b'S')?;
+    let longitude = coordinate(GPS_LONGITUDE, GPS_LONGITUDE_REF, b'W')?;
+    // Cameras without a fix sometimes write zeros rather than leaving the tags out
+    if latitude == 0.0 && longitude == 0.0 {
+        return None;
+    }
+
+    let accuracy = reader
+        .find(gps_ifd, GPS_POSITIONING_ERROR)
+        .and_then(|entry| reader.rationals(entry))
+        .and_then(|values| values.first().copied());
+
+    let location = EntryLocation {
+        latitude,
+        longitude,
+        accuracy,
+        place_name: None,
+        country: None,
+        source: LocationSource::Exif,
+    };
+    location.validate().ok().map(|_| location)
+}
+
+struct TiffReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl TiffReader<'_> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// The offset of the 12-byte directory entry for `tag`
+    fn find(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let count = self.u16(ifd)? as usize;
+        (0..count)
+            .map(|index| ifd + 2 + index * 12)
+            .find(|&entry| self.u16(entry) == Some(tag))
+    }
+
+    /// The values of a RATIONAL entry, which never fit inline
+    fn rationals(&self, entry: usize) -> Option<Vec<f64>> {
+        const RATIONAL: u16 = 5;
+        if self.u16(entry + 2)? != RATIONAL {
+            return None;
+        }
+
+        let count = self.u32(entry + 4)? as usize;
+        let start = self.u32(entry + 8)? as usize;
+        (0..count)
+            .map(|index| {
+                let numerator = self.u32(start + index * 8)?;
+                let denominator = self.u32(start + index * 8 + 4)?;
+                (denominator != 0).then(|| numerator as f64 / denominator as f64)
+            })
+            .collect()
+    }
+}
+
+fn clean(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
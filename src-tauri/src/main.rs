@@ -1,4 +1,7 @@
 mod ai_service;
+mod analytics;
+mod backups;
+mod collections;
 /**
  * MyFace SnapJournal - Tauri Backend
  *
@@ -11,19 +14,70 @@ mod ai_service;
     windows_subsystem = "windows"
 )]
 mod database;
+mod duplicates;
+mod embedding_index;
+mod entry_events;
+mod git_history;
 mod github_service;
-
-use ai_service::{AIService, ChatRequest, EmbeddingRequest};
-use anyhow::Result;
-use chrono::Utc;
+mod goals;
+mod journals;
+mod locations;
+mod maintenance;
+mod markdown_storage;
+mod moods;
+mod relocation;
+mod reminders;
+mod resurfacing;
+mod saved_searches;
+mod semantic_search;
+mod settings;
+mod statistics;
+mod storage;
+mod sync;
+mod templates;
+#[cfg(test)]
+mod test_support;
+mod webdav;
+
+use ai_service::{
+    AIService, ChatRequest, EmbeddingBatchRequest, EmbeddingBatchResponse, EmbeddingRequest,
+};
+use analytics::{AnalyticsEvent, AnalyticsRecorder, AnalyticsSettings, UsageSummary};
+use anyhow::{Context, Result};
+use backups::{BackupInfo, Backups};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use collections::{Collection, CollectionExport, CollectionInput, CollectionStore, ExportFormat};
 use database::{Database, JournalEntry};
-use github_service::{CreateIssueRequest, CreateIssueResponse, GitHubIssue, GitHubService};
+use duplicates::{DuplicateCluster, DuplicateDetector};
+use embedding_index::{EmbeddingIndex, EmbeddingIndexStatus};
+use entry_events::ChangeSource;
+use git_history::{GitCommit, PushResult};
+use goals::{GoalProgress, GoalTracker, HeatmapDay, StreakSummary, WritingGoals};
+use journals::{JournalInfo, JournalRegistry};
+use locations::{Bounds, EntryLocation, GeocodedPlace, Place};
+use maintenance::{Maintenance, MaintenanceResult};
+use markdown_storage::ReindexReport;
+use moods::{Mood, MoodInput, MoodRegistry, MoodTimelinePoint};
+use relocation::RelocationReport;
+use reminders::{Reminder, ReminderAction, ReminderHistoryItem, ReminderInput, ReminderScheduler};
+use resurfacing::{OnThisDayYear, ResurfacedEntry, Resurfacer};
+use saved_searches::{SavedSearch, SavedSearchInput, SavedSearchStore, SearchSpec};
+use semantic_search::{SemanticSearch, SemanticSearchResult};
 use serde_json::Value;
+use settings::{Settings, SettingsStore};
+use github_service::{CreateIssueRequest, CreateIssueResponse, GitHubIssue, GitHubService};
+use statistics::{Granularity, StatisticsBucket, StatisticsRange};
 use std::path::PathBuf;
+use storage::{EntryFilter, StorageKind};
+use sync::{ConflictResolution, SyncConflict, SyncEngine, SyncReport, SyncStatus};
+use templates::{EntryTemplate, TemplateInput, TemplateStore};
 use std::sync::Arc;
-use tauri::{Manager, State, WebviewWindowBuilder, WebviewUrl};
-use tokio::sync::Mutex;
+use std::time::Instant;
+use tauri::{Emitter, Manager, State, WebviewWindowBuilder, WebviewUrl};
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinSet;
 use uuid::Uuid;
+use webdav::{WebDavAccount, WebDavCredentials, WebDavStatus, WebDavSync};
 
 // App state
 struct AppState {
@@ -31,6 +85,9 @@ struct AppState {
     ai_service: Arc<Mutex<Option<AIService>>>,
     github_service: Arc<Mutex<Option<GitHubService>>>,
     database_path: Arc<Mutex<Option<PathBuf>>>,
+    journals: Arc<Mutex<Option<JournalRegistry>>>,
+    saved_search_counts: Arc<Notify>,
+    embedding_index: Arc<Notify>,
 }
 
 fn main() {
@@ -41,6 +98,9 @@ fn main() {
             ai_service: Arc::new(Mutex::new(None)),
             github_service: Arc::new(Mutex::new(None)),
             database_path: Arc::new(Mutex::new(None)),
+            journals: Arc::new(Mutex::new(None)),
+            saved_search_counts: Arc::new(Notify::new()),
+            embedding_index: Arc::new(Notify::new()),
         })
         .setup(|app| {
             // Set window title
@@ -55,6 +115,22 @@ fn main() {
                 }
             });
 
+            // Run light database maintenance in the background
+            let app_handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(idle_maintenance(app_handle));
+
+            // Fire journaling reminders as they come due
+            let app_handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(reminder_loop(app_handle));
+
+            // Recount saved searches after entries change
+            let app_handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(saved_search_count_loop(app_handle));
+
+            // Embed entries for semantic search as they are written
+            let app_handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(embedding_index_loop(app_handle));
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -63,6 +139,17 @@ fn main() {
             init_database,
             get_database_path,
             set_database_path,
+            move_database,
+            get_settings,
+            update_settings,
+            reset_settings,
+            list_journals,
+            get_current_journal,
+            create_journal,
+            open_journal,
+            rename_journal,
+            remove_journal,
+            reindex_journal,
             create_journal_entry,
             get_journal_entry,
             update_journal_entry,
@@ -70,7 +157,101 @@ fn main() {
             delete_journal_entries,
             list_journal_entries,
             search_journal_entries,
+            semantic_search,
+            set_entries_pinned,
+            set_entries_favorite,
+            set_entries_archived,
+            set_entries_locked,
+            set_entry_location,
+            read_photo_location,
+            reverse_geocode,
+            list_entries_in_bounds,
+            list_places,
+            get_statistics,
+            get_stats,
+            get_writing_streaks,
+            get_writing_goals,
+            set_writing_goals,
+            get_goal_progress,
+            get_calendar_heatmap,
+            get_analytics_settings,
+            update_analytics_settings,
+            get_usage_summary,
+            clear_analytics,
+            record_import,
+            list_moods,
+            create_mood,
+            update_mood,
+            delete_mood,
+            add_mood_alias,
+            remove_mood_alias,
+            get_mood_timeline,
+            list_templates,
+            create_template,
+            update_template,
+            delete_template,
+            create_entry_from_template,
+            list_collections,
+            create_collection,
+            update_collection,
+            delete_collection,
+            reorder_collections,
+            add_entries_to_collection,
+            remove_entries_from_collection,
+            reorder_collection_entries,
+            list_collection_entries,
+            search_collection_entries,
+            get_entry_collections,
+            export_collection,
+            list_reminders,
+            create_reminder,
+            update_reminder,
+            delete_reminder,
+            snooze_reminder,
+            record_reminder_action,
+            get_reminder_history,
+            list_saved_searches,
+            create_saved_search,
+            update_saved_search,
+            delete_saved_search,
+            reorder_saved_searches,
+            run_saved_search,
+            preview_saved_search,
+            get_on_this_day,
+            get_resurfaced_entries,
+            record_entry_view,
+            find_duplicate_entries,
+            merge_duplicate_entries,
+            link_duplicate_entries,
+            unlink_duplicate_entry,
+            get_linked_duplicates,
+            dismiss_duplicate_entries,
+            check_integrity,
+            optimize,
+            vacuum,
+            rebuild_search_index,
+            analyze,
+            create_backup,
+            list_backups,
+            sync_journal,
+            get_sync_status,
+            list_sync_conflicts,
+            resolve_sync_conflict,
+            set_webdav_account,
+            get_webdav_account,
+            remove_webdav_account,
+            sync_webdav,
+            get_webdav_status,
+            get_entry_history,
+            get_entry_version,
+            restore_entry_version,
+            get_history_remote,
+            set_history_remote,
+            push_journal_history,
             generate_embedding,
+            generate_embeddings,
+            reindex_embeddings,
+            embedding_index_status,
             generate_chat_response,
             analyze_echo_patterns,
             get_ai_models,
@@ -88,14 +269,35 @@ fn main() {
 }
 
 async fn initialize_services(app_handle: tauri::AppHandle) -> Result<()> {
-    // Initialize database
+    // Load the journal registry, registering the original journal.db on first run
     let data_dir = app_handle.path().app_data_dir()?;
-    let db_path = data_dir.join("journal.db");
+    let config_dir = app_handle.path().app_config_dir()?;
+    let mut registry = JournalRegistry::load(&config_dir, data_dir.join("journals")).await?;
+    if registry.list().is_empty() {
+        registry
+            .create(
+                "Personal",
+                Some(data_dir.join("journal.db")),
+                StorageKind::Sqlite,
+                false,
+                None,
+            )
+            .await?;
+    }
 
-    let database = Database::new(db_path.clone()).await?;
+    // Initialize database for the last opened journal
+    let journal = registry
+        .last_opened()
+        .cloned()
+        .context("No journal to open")?;
+    let db_path = journal.path.clone();
 
-    // Initialize AI service (default to Ollama)
-    let ai_service = AIService::new("ollama", "")?;
+    let database = open_journal_database(&registry, &journal).await?;
+    registry.mark_opened(&journal.id).await?;
+
+    // Initialize AI service from the journal's settings
+    let settings = SettingsStore::new(&database).get().await?;
+    let ai_service = build_ai_service(&settings)?;
 
     // Initialize GitHub service
     let github_service = GitHubService::new();
@@ -106,10 +308,201 @@ async fn initialize_services(app_handle: tauri::AppHandle) -> Result<()> {
     *state.ai_service.lock().await = Some(ai_service);
     *state.github_service.lock().await = Some(github_service);
     *state.database_path.lock().await = Some(db_path);
+    *state.journals.lock().await = Some(registry);
 
     Ok(())
 }
 
+async fn idle_maintenance(app_handle: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(maintenance::IDLE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let state = app_handle.state::<AppState>();
+        let backup_path = backup_path(&state).await.ok();
+
+        // Skip this round if a command is using the database
+        let Ok(db_guard) = state.database.try_lock() else {
+            continue;
+        };
+        if let Some(database) = db_guard.as_ref() {
+            if let Err(e) = Maintenance::new(database).run_idle_maintenance().await {
+                eprintln!("Idle maintenance failed: {}", e);
+            }
+            if let Some(path) = &backup_path {
+                if let Err(e) = Backups::new(database, path).run_scheduled().await {
+                    eprintln!("Scheduled backup failed: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// The database file of the open journal, when it is one that gets backed up
+async fn backup_path(state: &AppState) -> Result<PathBuf, String> {
+    let registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_ref().ok_or("Journals not initialized")?;
+    let journal = registry.last_opened().ok_or("No journal is open")?;
+    if journal.storage.uses_folder() {
+        return Err("Markdown journals are backed up with their folder".to_string());
+    }
+
+    Ok(journal.path.clone())
+}
+
+async fn reminder_loop(app_handle: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(reminders::REMINDER_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let state = app_handle.state::<AppState>();
+        let db_guard = state.database.lock().await;
+        let Some(database) = db_guard.as_ref() else {
+            continue;
+        };
+
+        match ReminderScheduler::new(database)
+            .fire_due(Utc::now())
+            .await
+        {
+            Ok(notifications) => {
+                for notification in notifications {
+                    let _ = app_handle.emit(reminders::REMINDERS_DUE, &notification);
+                }
+            }
+            Err(e) => eprintln!("Failed to check reminders: {}", e),
+        }
+    }
+}
+
+async fn saved_search_count_loop(app_handle: tauri::AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let changed = state.saved_search_counts.clone();
+    loop {
+        // Changes made while counting leave a permit, so one more pass follows
+        changed.notified().await;
+
+        // Wait for a burst of changes to settle before counting once
+        loop {
+            tokio::select! {
+                _ = changed.notified() => {}
+                _ = tokio::time::sleep(saved_searches::COUNT_DEBOUNCE) => break,
+            }
+        }
+
+        let db_guard = state.database.lock().await;
+        let Some(database) = db_guard.as_ref() else {
+            continue;
+        };
+
+        match SavedSearchStore::new(database).refresh_counts().await {
+            Ok(counts) => {
+                let _ = app_handle.emit(saved_searches::SAVED_SEARCH_COUNTS, &counts);
+            }
+            Err(e) => eprintln!("Failed to count saved searches: {}", e),
+        }
+    }
+}
+
+async fn embedding_index_loop(app_handle: tauri::AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let queued = state.embedding_index.clone();
+    let mut retry = tokio::time::interval(embedding_index::RETRY_INTERVAL);
+    // The journal and model the queue was last filled for
+    let mut scanned = None;
+    loop {
+        // Failed entries come due again without anything new being queued
+        tokio::select! {
+            _ = queued.notified() => {}
+            _ = retry.tick() => {}
+        }
+
+        if let Err(e) = index_embeddings(&app_handle, &mut scanned).await {
+            eprintln!("Failed to index embeddings: {}", e);
+        }
+    }
+}
+
+/// Embed queued entries until none are due, a few batches at a time. The
+/// database is only locked to read the queue and save results, not while
+/// the model is working. Entries missing an embedding are queued the first
+/// time a journal is indexed with a model, e.g. at startup or after
+/// switching models, rather than on every pass.
+async fn index_embeddings(
+    app_handle: &tauri::AppHandle,
+    scanned: &mut Option<(Option<PathBuf>, String)>,
+) -> Result<()> {
+    let state = app_handle.state::<AppState>();
+    let Some(ai_service) = state.ai_service.lock().await.clone() else {
+        return Ok(());
+    };
+    let model = ai_service.embedding_model().await?;
+
+    let journal = state.database_path.lock().await.clone();
+    let target = Some((journal, model.clone()));
+    if *scanned != target {
+        let db_guard = state.database.lock().await;
+        let Some(database) = db_guard.as_ref() else {
+            return Ok(());
+        };
+        EmbeddingIndex::new(database)
+            .enqueue_missing(&model)
+            .await?;
+        *scanned = target;
+    }
+
+    loop {
+        let pending = {
+            let db_guard = state.database.lock().await;
+            let Some(database) = db_guard.as_ref() else {
+                return Ok(());
+            };
+            EmbeddingIndex::new(database)
+                .take_due(
+                    &model,
+                    embedding_index::BATCH_SIZE * embedding_index::CONCURRENCY,
+                )
+                .await?
+        };
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut requests = JoinSet::new();
+        for batch in pending.chunks(embedding_index::BATCH_SIZE) {
+            let ai_service = ai_service.clone();
+            let batch = batch.to_vec();
+            requests.spawn(async move {
+                let request = EmbeddingBatchRequest {
+                    texts: batch.iter().map(|item| item.text.clone()).collect(),
+                    model: None,
+                };
+                let result = ai_service.generate_embeddings(request).await;
+                (batch, result)
+            });
+        }
+        let mut results = Vec::new();
+        while let Some(joined) = requests.join_next().await {
+            results.push(joined?);
+        }
+
+        let db_guard = state.database.lock().await;
+        let Some(database) = db_guard.as_ref() else {
+            return Ok(());
+        };
+        let index = EmbeddingIndex::new(database);
+        for (batch, result) in results {
+            match result {
+                Ok(response) => index.store(&model, &batch, response.embeddings).await?,
+                Err(e) => index.record_failure(&batch, &e.to_string()).await?,
+            }
+        }
+
+        let status = index.status(&model).await?;
+        let _ = app_handle.emit(embedding_index::EMBEDDINGS_PROGRESS, &status);
+    }
+}
+
 // Utility commands
 #[tauri::command]
 async fn get_app_info() -> Result<serde_json::Value, String> {
@@ -133,13 +526,7 @@ async fn get_system_info() -> Result<serde_json::Value, String> {
 // Database commands
 #[tauri::command]
 async fn init_database(state: State<'_, AppState>, db_path: String) -> Result<(), String> {
-    let path = PathBuf::from(db_path);
-    let database = Database::new(path.clone())
-        .await
-        .map_err(|e| e.to_string())?;
-
-    *state.database.lock().await = Some(database);
-    *state.database_path.lock().await = Some(path);
+    open_journal_at_path(&state, PathBuf::from(db_path)).await?;
     Ok(())
 }
 
@@ -151,53 +538,500 @@ async fn get_database_path(state: State<'_, AppState>) -> Result<Option<String>,
 
 #[tauri::command]
 async fn set_database_path(state: State<'_, AppState>, db_path: String) -> Result<(), String> {
-    let path = PathBuf::from(db_path);
-    let database = Database::new(path.clone())
+    open_journal_at_path(&state, PathBuf::from(db_path)).await?;
+    Ok(())
+}
+
+/// Move the current journal's database to `new_path`
+#[tauri::command]
+async fn move_database(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    new_path: String,
+    delete_original: Option<bool>,
+) -> Result<RelocationReport, String> {
+    let mut registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_mut().ok_or("Journals not initialized")?;
+    let journal = registry.last_opened().cloned().ok_or("No journal is open")?;
+    if journal.storage.uses_folder() {
+        return Err("Markdown journals are moved by moving their folder".to_string());
+    }
+
+    let new_path = PathBuf::from(new_path);
+    if let Some(existing) = registry.find_by_path(&new_path) {
+        return Err(format!("{:?} is already used by \"{}\"", new_path, existing.name));
+    }
+
+    // Hold the database for the whole move so nothing is written to the old copy
+    let mut db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut report = relocation::copy_journal(database, &journal.path, &new_path)
         .await
         .map_err(|e| e.to_string())?;
 
-    *state.database.lock().await = Some(database);
-    *state.database_path.lock().await = Some(path);
+    let moved = Database::new(new_path.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(previous) = db_guard.replace(moved) {
+        previous.close().await;
+    }
+    drop(db_guard);
+
+    let journal = registry
+        .set_path(&journal.id, new_path.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    *state.database_path.lock().await = Some(new_path.clone());
+
+    if delete_original.unwrap_or(false) {
+        relocation::delete_original(&report.old_path)
+            .await
+            .map_err(|e| e.to_string())?;
+        report.original_deleted = true;
+    }
+
+    let _ = app_handle.emit("journals://opened", &journal);
+
+    Ok(report)
+}
+
+// Settings commands
+fn build_ai_service(settings: &Settings) -> Result<AIService> {
+    let model_path = settings.llama_cpp_path.as_deref().unwrap_or_default();
+    Ok(AIService::new(settings.ai_backend.as_str(), model_path)?
+        .with_models(
+            &settings.chat_model,
+            &settings.embedding_model,
+            &settings.ollama_url,
+        )
+        .with_llama_server(&settings.llama_server_url))
+}
+
+/// Reconfigure the services that depend on settings
+async fn apply_settings(state: &AppState, settings: &Settings) -> Result<(), String> {
+    let ai_service = build_ai_service(settings).map_err(|e| e.to_string())?;
+    *state.ai_service.lock().await = Some(ai_service);
+    // A different journal or embedding model may have entries to embed
+    state.embedding_index.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_settings(state: State<'_, AppState>) -> Result<Settings, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SettingsStore::new(database)
+        .get()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_settings(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    patch: serde_json::Map<String, Value>,
+) -> Result<Settings, String> {
+    let settings = {
+        let db_guard = state.database.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+        SettingsStore::new(database)
+            .update(patch)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    apply_settings(&state, &settings).await?;
+    let _ = app_handle.emit("settings://changed", &settings);
+
+    Ok(settings)
+}
+
+#[tauri::command]
+async fn reset_settings(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Settings, String> {
+    let settings = {
+        let db_guard = state.database.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+        SettingsStore::new(database)
+            .reset()
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    apply_settings(&state, &settings).await?;
+    let _ = app_handle.emit("settings://changed", &settings);
+
+    Ok(settings)
+}
+
+// Journal commands
+/// Open a journal with its storage backend; Markdown journals keep their index in the app data dir
+async fn open_journal_database(
+    registry: &JournalRegistry,
+    journal: &JournalInfo,
+) -> Result<Database> {
+    match journal.storage {
+        StorageKind::Sqlite => Database::new(journal.path.clone()).await,
+        StorageKind::Markdown => {
+            Database::with_markdown_folder(registry.index_path(&journal.id), journal.path.clone())
+                .await
+        }
+        StorageKind::Git => {
+            Database::with_git_folder(registry.index_path(&journal.id), journal.path.clone()).await
+        }
+    }
+}
+
+/// Open a journal's database and make it the active one
+async fn activate_journal(
+    state: &AppState,
+    registry: &mut JournalRegistry,
+    id: &str,
+) -> Result<JournalInfo, String> {
+    let journal = registry.get(id).cloned().ok_or("Journal not found")?;
+    let database = open_journal_database(registry, &journal)
+        .await
+        .map_err(|e| e.to_string())?;
+    let settings = SettingsStore::new(&database)
+        .get()
+        .await
+        .map_err(|e| e.to_string())?;
+    let journal = registry
+        .mark_opened(&journal.id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let previous = state.database.lock().await.replace(database);
+    if let Some(previous) = previous {
+        previous.close().await;
+    }
+    *state.database_path.lock().await = Some(journal.path.clone());
+    apply_settings(state, &settings).await?;
+
+    Ok(journal)
+}
+
+/// Open the journal stored at `path`, registering it if it is new
+async fn open_journal_at_path(state: &AppState, path: PathBuf) -> Result<JournalInfo, String> {
+    let mut registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_mut().ok_or("Journals not initialized")?;
+
+    let id = match registry.find_by_path(&path) {
+        Some(journal) => journal.id.clone(),
+        None => {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Journal".to_string());
+            registry
+                .create(&name, Some(path), StorageKind::Sqlite, false, None)
+                .await
+                .map_err(|e| e.to_string())?
+                .id
+        }
+    };
+
+    activate_journal(state, registry, &id).await
+}
+
+#[tauri::command]
+async fn list_journals(state: State<'_, AppState>) -> Result<Vec<JournalInfo>, String> {
+    let registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_ref().ok_or("Journals not initialized")?;
+
+    Ok(registry.list().to_vec())
+}
+
+#[tauri::command]
+async fn get_current_journal(state: State<'_, AppState>) -> Result<Option<JournalInfo>, String> {
+    let registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_ref().ok_or("Journals not initialized")?;
+
+    Ok(registry.last_opened().cloned())
+}
+
+#[tauri::command]
+async fn create_journal(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    path: Option<String>,
+    storage: Option<StorageKind>,
+    encrypted: Option<bool>,
+    color: Option<String>,
+    open: Option<bool>,
+) -> Result<JournalInfo, String> {
+    let mut registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_mut().ok_or("Journals not initialized")?;
+
+    let journal = registry
+        .create(
+            &name,
+            path.map(PathBuf::from),
+            storage.unwrap_or_default(),
+            encrypted.unwrap_or(false),
+            color,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if open.unwrap_or(true) {
+        let journal = activate_journal(&state, registry, &journal.id).await?;
+        let _ = app_handle.emit("journals://opened", &journal);
+        return Ok(journal);
+    }
+
+    Ok(journal)
+}
+
+#[tauri::command]
+async fn open_journal(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<JournalInfo, String> {
+    let mut registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_mut().ok_or("Journals not initialized")?;
+
+    let journal = activate_journal(&state, registry, &id).await?;
+    let _ = app_handle.emit("journals://opened", &journal);
+
+    Ok(journal)
+}
+
+#[tauri::command]
+async fn rename_journal(
+    state: State<'_, AppState>,
+    id: String,
+    name: String,
+) -> Result<JournalInfo, String> {
+    let mut registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_mut().ok_or("Journals not initialized")?;
+
+    registry
+        .rename(&id, &name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Pick up Markdown files added, edited or deleted outside the app
+#[tauri::command]
+async fn reindex_journal(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<ReindexReport>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Folder);
+
+    database.reindex().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_journal(
+    state: State<'_, AppState>,
+    id: String,
+    delete_files: Option<bool>,
+) -> Result<(), String> {
+    let mut registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_mut().ok_or("Journals not initialized")?;
+
+    if registry.last_opened().map(|j| j.id.as_str()) == Some(id.as_str()) {
+        return Err("Open another journal before removing this one".to_string());
+    }
+
+    let index_path = registry.index_path(&id);
+    webdav::delete_credentials(&registry.webdav_credentials_path(&id), &id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let journal = registry.remove(&id).await.map_err(|e| e.to_string())?;
+    if delete_files.unwrap_or(false) {
+        // A Markdown folder belongs to the user; only the index is ours to delete
+        let path = match journal.storage {
+            StorageKind::Sqlite => journal.path,
+            StorageKind::Markdown | StorageKind::Git => index_path,
+        };
+        journals::delete_journal_files(&path)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+
+/// Parse an entry date sent by the frontend, keeping its UTC offset
+fn parse_entry_date(value: Option<String>) -> Result<Option<DateTime<FixedOffset>>, String> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map_err(|e| format!("Invalid entry date {}: {}", value, e))
+        })
+        .transpose()
+}
+
+/// Locked entries can only be changed after they are unlocked
+fn ensure_unlocked(entry: &JournalEntry) -> Result<(), String> {
+    if entry.locked {
+        return Err("This entry is locked; unlock it to make changes".to_string());
+    }
     Ok(())
 }
 
+/// Tell every window which entries the last operation created, updated or deleted
+fn emit_entry_changes(app_handle: &tauri::AppHandle, database: &Database, source: ChangeSource) {
+    let events = database.take_entry_changes().events(source);
+    if events.is_empty() {
+        return;
+    }
+
+    for (event, payload) in events {
+        let _ = app_handle.emit(event, &payload);
+    }
+    let state = app_handle.state::<AppState>();
+    state.saved_search_counts.notify_one();
+    state.embedding_index.notify_one();
+}
+
+/// Emits the entry changes a command made when it goes out of scope, so one
+/// that fails partway still announces the entries it did change
+struct EntryChangeGuard<'a> {
+    app_handle: &'a tauri::AppHandle,
+    database: &'a Database,
+    source: ChangeSource,
+}
+
+impl<'a> EntryChangeGuard<'a> {
+    fn new(app_handle: &'a tauri::AppHandle, database: &'a Database, source: ChangeSource) -> Self {
+        EntryChangeGuard {
+            app_handle,
+            database,
+            source,
+        }
+    }
+}
+
+impl Drop for EntryChangeGuard<'_> {
+    fn drop(&mut self) {
+        emit_entry_changes(self.app_handle, self.database, self.source);
+    }
+}
 
 #[tauri::command]
 async fn create_journal_entry(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     title: String,
     content: String,
     tags: Vec<String>,
     mood: Option<String>,
+    mood_intensity: Option<f64>,
     privacy: String,
     source: Option<String>,
     source_id: Option<String>,
     source_url: Option<String>,
     metadata: Option<serde_json::Value>,
+    entry_date: Option<String>,
+    location: Option<EntryLocation>,
+    locked: Option<bool>,
+    collection_ids: Option<Vec<String>>,
 ) -> Result<JournalEntry, String> {
     let db_guard = state.database.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
+    let collection_ids = collection_ids.unwrap_or_default();
+    let collections = CollectionStore::new(database);
+    for collection_id in &collection_ids {
+        if collections
+            .get(collection_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .is_none()
+        {
+            return Err(format!("Collection not found: {}", collection_id));
+        }
+    }
+
+    moods::validate_intensity(mood_intensity).map_err(|e| e.to_string())?;
+    let entry_date = match parse_entry_date(entry_date)? {
+        Some(entry_date) => entry_date,
+        None => SettingsStore::new(database)
+            .timezone()
+            .await
+            .map_err(|e| e.to_string())?
+            .now(),
+    };
+    // Day One exports keep the location next to the rest of the imported entry
+    let location = location.or_else(|| match source.as_deref() {
+        Some(locations::DAY_ONE_SOURCE) => metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("location"))
+            .and_then(EntryLocation::from_day_one),
+        _ => None,
+    });
+    let location = location
+        .map(EntryLocation::prepare)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let mood = MoodRegistry::new(database)
+        .resolve_or_create(mood.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Imported posts are kept as they were published unless asked otherwise
+    let locked = locked.unwrap_or_else(|| database::is_imported_source(source.as_deref()));
+
     let entry = JournalEntry {
         id: Uuid::new_v4().to_string(),
         title,
         content,
         tags,
+        mood_intensity: mood.as_ref().and(mood_intensity),
         mood,
         privacy,
         source,
         source_id,
         source_url,
         metadata,
+        location,
+        pinned: false,
+        favorite: false,
+        archived: false,
+        locked,
+        entry_date,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     };
 
+    let source = if entry.is_imported() {
+        ChangeSource::Import
+    } else {
+        ChangeSource::Editor
+    };
+    let _changes = EntryChangeGuard::new(&app_handle, database, source);
+
     database
         .create_entry(&entry)
         .await
         .map_err(|e| e.to_string())?;
 
+    for collection_id in &collection_ids {
+        collections
+            .add_entries(collection_id, std::slice::from_ref(&entry.id))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    track(database, AnalyticsEvent::command("create_journal_entry")).await;
+
     Ok(entry)
 }
 
@@ -214,17 +1048,20 @@ async fn get_journal_entry(
 
 #[tauri::command]
 async fn update_journal_entry(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     id: String,
     title: String,
     content: String,
     tags: Vec<String>,
     mood: Option<String>,
+    mood_intensity: Option<f64>,
     privacy: String,
     source: Option<String>,
     source_id: Option<String>,
     source_url: Option<String>,
     metadata: Option<serde_json::Value>,
+    entry_date: Option<String>,
 ) -> Result<(), String> {
     let db_guard = state.database.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
@@ -234,79 +1071,1560 @@ async fn update_journal_entry(
         .await
         .map_err(|e| e.to_string())?
         .ok_or("Entry not found")?;
+    ensure_unlocked(&entry)?;
+
+    moods::validate_intensity(mood_intensity).map_err(|e| e.to_string())?;
+    let entry_date = parse_entry_date(entry_date)?;
+    let mood = MoodRegistry::new(database)
+        .resolve_or_create(mood.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
 
     entry.title = title;
     entry.content = content;
     entry.tags = tags;
+    entry.mood_intensity = mood.as_ref().and(mood_intensity);
     entry.mood = mood;
     entry.privacy = privacy;
     entry.source = source;
     entry.source_id = source_id;
     entry.source_url = source_url;
     entry.metadata = metadata;
+    if let Some(entry_date) = entry_date {
+        entry.entry_date = entry_date;
+    }
     entry.updated_at = Utc::now();
 
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Editor);
     database
         .update_entry(&entry)
         .await
-        .map_err(|e| e.to_string())
-}
+        .map_err(|e| e.to_string())?;
 
-#[tauri::command]
-async fn delete_journal_entry(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    let db_guard = state.database.lock().await;
-    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+    track(database, AnalyticsEvent::command("update_journal_entry")).await;
 
-    database.delete_entry(&id).await.map_err(|e| e.to_string())
+    Ok(())
+}
+
+#[tauri::command]
+async fn delete_journal_entry(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Editor);
+
+    database
+        .delete_entry(&id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    track(database, AnalyticsEvent::command("delete_journal_entry")).await;
+
+    Ok(())
 }
 
 #[tauri::command]
 async fn delete_journal_entries(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    // One event for the whole batch, including the entries deleted before a failure
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Editor);
+
+    for id in &ids {
+        database.delete_entry(id).await.map_err(|e| e.to_string())?;
+    }
+
+    track(database, AnalyticsEvent::command("delete_journal_entries")).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_journal_entries(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    filter: Option<EntryFilter>,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database
+        .list_entries(limit, offset, &filter.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn search_journal_entries(
+    state: State<'_, AppState>,
+    query: String,
+    filter: Option<EntryFilter>,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let entries = database
+        .search_entries(&query, &filter.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Err(e) = AnalyticsRecorder::new(database)
+        .record_search(&query, entries.len())
+        .await
+    {
+        eprintln!("Failed to record analytics event: {}", e);
+    }
+
+    Ok(entries)
+}
+
+/// Entries closest in meaning to `query`, using the stored embeddings of the current model
+#[tauri::command]
+async fn semantic_search(
+    state: State<'_, AppState>,
+    query: String,
+    k: Option<usize>,
+    filter: Option<EntryFilter>,
+) -> Result<Vec<SemanticSearchResult>, String> {
+    if query.trim().is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+
+    let ai_service = current_ai_service(&state).await?;
+    let model = ai_service
+        .embedding_model()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let request = EmbeddingRequest {
+        text: query,
+        model: None,
+    };
+    let started = Instant::now();
+    let result = ai_service.generate_embedding(request).await;
+
+    track_ai_call(
+        &state,
+        "semantic_search",
+        Some(model.clone()),
+        started,
+        result.is_ok(),
+    )
+    .await;
+    let embedding = result.map_err(|e| e.to_string())?.embedding;
+
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SemanticSearch::new(database)
+        .search(
+            &model,
+            &embedding,
+            k.unwrap_or(semantic_search::DEFAULT_RESULTS),
+            &filter.unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Entry state commands
+/// Change a state of several entries, announcing them as one batch; returns the entries that changed
+async fn update_entry_states(
+    app_handle: &tauri::AppHandle,
+    state: &AppState,
+    ids: &[String],
+    change: impl Fn(&mut JournalEntry),
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    // Include the entries changed before a failure
+    let _changes = EntryChangeGuard::new(app_handle, database, ChangeSource::Editor);
+
+    database
+        .update_entries(ids, change)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_entries_pinned(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    pinned: bool,
+) -> Result<Vec<JournalEntry>, String> {
+    update_entry_states(&app_handle, &state, &ids, |entry| entry.pinned = pinned).await
+}
+
+#[tauri::command]
+async fn set_entries_favorite(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    favorite: bool,
+) -> Result<Vec<JournalEntry>, String> {
+    update_entry_states(&app_handle, &state, &ids, |entry| entry.favorite = favorite).await
+}
+
+#[tauri::command]
+async fn set_entries_archived(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    archived: bool,
+) -> Result<Vec<JournalEntry>, String> {
+    update_entry_states(&app_handle, &state, &ids, |entry| entry.archived = archived).await
+}
+
+#[tauri::command]
+async fn set_entries_locked(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    locked: bool,
+) -> Result<Vec<JournalEntry>, String> {
+    update_entry_states(&app_handle, &state, &ids, |entry| entry.locked = locked).await
+}
+
+// Location commands
+/// Set or clear where an entry was written
+#[tauri::command]
+async fn set_entry_location(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    location: Option<EntryLocation>,
+) -> Result<JournalEntry, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut entry = database
+        .get_entry(&id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Entry not found")?;
+    ensure_unlocked(&entry)?;
+
+    entry.location = location
+        .map(EntryLocation::prepare)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    entry.updated_at = Utc::now();
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Editor);
+    database
+        .update_entry(&entry)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(entry)
+}
+
+/// The GPS position in a photo's EXIF data, named after the nearest city
+#[tauri::command]
+async fn read_photo_location(path: String) -> Result<Option<EntryLocation>, String> {
+    locations::photo_location(&PathBuf::from(path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn reverse_geocode(latitude: f64, longitude: f64) -> Result<Option<GeocodedPlace>, String> {
+    Ok(locations::reverse_geocode(latitude, longitude))
+}
+
+#[tauri::command]
+async fn list_entries_in_bounds(
+    state: State<'_, AppState>,
+    bounds: Bounds,
+    limit: Option<i64>,
+    filter: Option<EntryFilter>,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database
+        .list_entries_in_bounds(&bounds, limit, &filter.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_places(state: State<'_, AppState>) -> Result<Vec<Place>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database.list_places().await.map_err(|e| e.to_string())
+}
+
+// Statistics commands
+#[tauri::command]
+async fn get_statistics(
+    state: State<'_, AppState>,
+    range: Option<StatisticsRange>,
+    granularity: Option<Granularity>,
+) -> Result<Vec<StatisticsBucket>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database
+        .statistics()
+        .query(&range.unwrap_or_default(), granularity.unwrap_or(Granularity::Day))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_stats(state: State<'_, AppState>) -> Result<Value, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database.get_stats().await.map_err(|e| e.to_string())
+}
+
+// Goal commands
+#[tauri::command]
+async fn get_writing_streaks(state: State<'_, AppState>) -> Result<StreakSummary, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    GoalTracker::new(database)
+        .streaks()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_writing_goals(state: State<'_, AppState>) -> Result<WritingGoals, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    GoalTracker::new(database)
+        .goals()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_writing_goals(state: State<'_, AppState>, goals: WritingGoals) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    GoalTracker::new(database)
+        .set_goals(&goals)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_goal_progress(state: State<'_, AppState>) -> Result<GoalProgress, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    GoalTracker::new(database)
+        .progress()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_calendar_heatmap(
+    state: State<'_, AppState>,
+    year: i32,
+) -> Result<Vec<HeatmapDay>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    GoalTracker::new(database)
+        .heatmap(year)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Analytics commands
+/// Record a local usage event; analytics never fail the command that triggered it
+async fn track(database: &Database, event: AnalyticsEvent) {
+    if let Err(e) = AnalyticsRecorder::new(database).record(&event).await {
+        eprintln!("Failed to record analytics event: {}", e);
+    }
+}
+
+async fn track_ai_call(
+    state: &AppState,
+    operation: &str,
+    model: Option<String>,
+    started: Instant,
+    success: bool,
+) {
+    let db_guard = state.database.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let event = AnalyticsEvent::AiCall {
+            operation: operation.to_string(),
+            model,
+            latency_ms: started.elapsed().as_millis() as u64,
+            success,
+        };
+        track(database, event).await;
+    }
+}
+
+#[tauri::command]
+async fn get_analytics_settings(state: State<'_, AppState>) -> Result<AnalyticsSettings, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    AnalyticsRecorder::new(database)
+        .settings()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_analytics_settings(
+    state: State<'_, AppState>,
+    settings: AnalyticsSettings,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    AnalyticsRecorder::new(database)
+        .update_settings(&settings)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_usage_summary(
+    state: State<'_, AppState>,
+    days: Option<u32>,
+) -> Result<UsageSummary, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    AnalyticsRecorder::new(database)
+        .summary(days)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn clear_analytics(state: State<'_, AppState>) -> Result<u64, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    AnalyticsRecorder::new(database)
+        .clear()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn record_import(
+    state: State<'_, AppState>,
+    source: String,
+    count: u64,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    track(database, AnalyticsEvent::Import { source, count }).await;
+    Ok(())
+}
+
+// Mood commands
+#[tauri::command]
+async fn list_moods(state: State<'_, AppState>) -> Result<Vec<Mood>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    MoodRegistry::new(database)
+        .list()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_mood(state: State<'_, AppState>, mood: MoodInput) -> Result<Mood, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    MoodRegistry::new(database)
+        .create(&mood)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_mood(
+    state: State<'_, AppState>,
+    id: String,
+    mood: MoodInput,
+) -> Result<Mood, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    MoodRegistry::new(database)
+        .update(&id, &mood)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_mood(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Editor);
+    MoodRegistry::new(database)
+        .delete(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn add_mood_alias(
+    state: State<'_, AppState>,
+    alias: String,
+    mood_id: String,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    MoodRegistry::new(database)
+        .add_alias(&alias, &mood_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_mood_alias(state: State<'_, AppState>, alias: String) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    MoodRegistry::new(database)
+        .remove_alias(&alias)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_mood_timeline(
+    state: State<'_, AppState>,
+    range: Option<StatisticsRange>,
+    bucket: Option<Granularity>,
+) -> Result<Vec<MoodTimelinePoint>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    MoodRegistry::new(database)
+        .timeline(&range.unwrap_or_default(), bucket.unwrap_or(Granularity::Week))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Template commands
+#[tauri::command]
+async fn list_templates(state: State<'_, AppState>) -> Result<Vec<EntryTemplate>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    TemplateStore::new(database)
+        .list()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_template(
+    state: State<'_, AppState>,
+    template: TemplateInput,
+) -> Result<EntryTemplate, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    TemplateStore::new(database)
+        .create(&template)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_template(
+    state: State<'_, AppState>,
+    id: String,
+    template: TemplateInput,
+) -> Result<EntryTemplate, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    TemplateStore::new(database)
+        .update(&id, &template)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_template(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    TemplateStore::new(database)
+        .delete(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Render a template into a new entry; `use_ai` asks the AI model for the prompt of the day
+#[tauri::command]
+async fn create_entry_from_template(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    entry_date: Option<String>,
+    use_ai: Option<bool>,
+) -> Result<JournalEntry, String> {
+    let entry_date = parse_entry_date(entry_date)?;
+
+    let mut prompt = None;
+    if use_ai.unwrap_or(false) {
+        let (template, timezone) = {
+            let db_guard = state.database.lock().await;
+            let database = db_guard.as_ref().ok_or("Database not initialized")?;
+            let template = TemplateStore::new(database)
+                .get(&id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or("Template not found")?;
+            let timezone = SettingsStore::new(database)
+                .timezone()
+                .await
+                .map_err(|e| e.to_string())?;
+            (template, timezone)
+        };
+
+        if template.uses("prompt_of_the_day") {
+            let date = entry_date.unwrap_or_else(|| timezone.now()).date_naive();
+            let ai_service = state.ai_service.lock().await.clone();
+            if let Some(ai_service) = ai_service {
+                let started = Instant::now();
+                let result = ai_service
+                    .generate_chat(templates::prompt_request(&template, date))
+                    .await;
+
+                track_ai_call(&state, "template_prompt", None, started, result.is_ok()).await;
+                // The built-in prompt of the day stands in when the model is unavailable
+                match result {
+                    Ok(response) => prompt = templates::clean_prompt(&response.response),
+                    Err(e) => eprintln!("Failed to generate a template prompt: {}", e),
+                }
+            }
+        }
+    }
+
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Editor);
+
+    let entry = TemplateStore::new(database)
+        .create_entry(&id, entry_date, prompt)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    track(
+        database,
+        AnalyticsEvent::command("create_entry_from_template"),
+    )
+    .await;
+
+    Ok(entry)
+}
+
+// Collection commands
+#[tauri::command]
+async fn list_collections(state: State<'_, AppState>) -> Result<Vec<Collection>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .list()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_collection(
+    state: State<'_, AppState>,
+    collection: CollectionInput,
+) -> Result<Collection, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .create(&collection)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_collection(
+    state: State<'_, AppState>,
+    id: String,
+    collection: CollectionInput,
+) -> Result<Collection, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .update(&id, &collection)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_collection(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .delete(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn reorder_collections(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<Vec<Collection>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .reorder(&ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn add_entries_to_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    entry_ids: Vec<String>,
+) -> Result<Collection, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .add_entries(&collection_id, &entry_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_entries_from_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    entry_ids: Vec<String>,
+) -> Result<Collection, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .remove_entries(&collection_id, &entry_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn reorder_collection_entries(
+    state: State<'_, AppState>,
+    collection_id: String,
+    entry_ids: Vec<String>,
+) -> Result<Collection, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .reorder_entries(&collection_id, &entry_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_collection_entries(
+    state: State<'_, AppState>,
+    collection_id: String,
+    filter: Option<EntryFilter>,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .entries(&collection_id, &filter.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn search_collection_entries(
+    state: State<'_, AppState>,
+    collection_id: String,
+    query: String,
+    filter: Option<EntryFilter>,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .search(&collection_id, &query, &filter.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_entry_collections(
+    state: State<'_, AppState>,
+    entry_id: String,
+) -> Result<Vec<Collection>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .for_entry(&entry_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn export_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    path: String,
+    format: ExportFormat,
+) -> Result<CollectionExport, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    CollectionStore::new(database)
+        .export(&collection_id, &PathBuf::from(path), format)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Reminder commands
+#[tauri::command]
+async fn list_reminders(state: State<'_, AppState>) -> Result<Vec<Reminder>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    ReminderScheduler::new(database)
+        .list()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_reminder(
+    state: State<'_, AppState>,
+    reminder: ReminderInput,
+) -> Result<Reminder, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    ReminderScheduler::new(database)
+        .create(&reminder)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_reminder(
+    state: State<'_, AppState>,
+    id: String,
+    reminder: ReminderInput,
+) -> Result<Reminder, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    ReminderScheduler::new(database)
+        .update(&id, &reminder)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_reminder(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    ReminderScheduler::new(database)
+        .delete(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn snooze_reminder(
+    state: State<'_, AppState>,
+    id: String,
+    minutes: u32,
+) -> Result<Reminder, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    ReminderScheduler::new(database)
+        .snooze(&id, minutes)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Record what the user did with a reminder shown by a reminders://due event
+#[tauri::command]
+async fn record_reminder_action(
+    state: State<'_, AppState>,
+    history_id: i64,
+    action: ReminderAction,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    ReminderScheduler::new(database)
+        .record_action(history_id, action)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_reminder_history(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<ReminderHistoryItem>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    ReminderScheduler::new(database)
+        .history(limit.unwrap_or(100))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Saved search commands
+#[tauri::command]
+async fn list_saved_searches(state: State<'_, AppState>) -> Result<Vec<SavedSearch>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SavedSearchStore::new(database)
+        .list()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_saved_search(
+    state: State<'_, AppState>,
+    search: SavedSearchInput,
+) -> Result<SavedSearch, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SavedSearchStore::new(database)
+        .create(&search)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_saved_search(
+    state: State<'_, AppState>,
+    id: String,
+    search: SavedSearchInput,
+) -> Result<SavedSearch, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SavedSearchStore::new(database)
+        .update(&id, &search)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_saved_search(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SavedSearchStore::new(database)
+        .delete(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn reorder_saved_searches(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<Vec<SavedSearch>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SavedSearchStore::new(database)
+        .reorder(&ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn run_saved_search(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SavedSearchStore::new(database)
+        .run(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn preview_saved_search(
+    state: State<'_, AppState>,
+    spec: SearchSpec,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SavedSearchStore::new(database)
+        .preview(&spec)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Resurfacing commands
+#[tauri::command]
+async fn get_on_this_day(
+    state: State<'_, AppState>,
+    date: Option<String>,
+) -> Result<Vec<OnThisDayYear>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let date = match date {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date {}: {}", date, e))?,
+        None => SettingsStore::new(database)
+            .timezone()
+            .await
+            .map_err(|e| e.to_string())?
+            .today(),
+    };
+
+    Resurfacer::new(database)
+        .on_this_day(date)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_resurfaced_entries(
+    state: State<'_, AppState>,
+) -> Result<Vec<ResurfacedEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Resurfacer::new(database)
+        .resurface(Utc::now())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn record_entry_view(state: State<'_, AppState>, entry_id: String) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Resurfacer::new(database)
+        .record_view(&entry_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Duplicate commands
+#[tauri::command]
+async fn find_duplicate_entries(
+    state: State<'_, AppState>,
+    similarity: Option<f64>,
+) -> Result<Vec<DuplicateCluster>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    DuplicateDetector::new(database)
+        .find(similarity)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn merge_duplicate_entries(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    canonical_id: String,
+    duplicate_ids: Vec<String>,
+) -> Result<JournalEntry, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Editor);
+
+    DuplicateDetector::new(database)
+        .merge(&canonical_id, &duplicate_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn link_duplicate_entries(
+    state: State<'_, AppState>,
+    entry_ids: Vec<String>,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    DuplicateDetector::new(database)
+        .link(&entry_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn unlink_duplicate_entry(
+    state: State<'_, AppState>,
+    entry_id: String,
+) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    DuplicateDetector::new(database)
+        .unlink(&entry_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_linked_duplicates(
+    state: State<'_, AppState>,
+    entry_id: String,
+) -> Result<Vec<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    DuplicateDetector::new(database)
+        .linked(&entry_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn dismiss_duplicate_entries(
     state: State<'_, AppState>,
-    ids: Vec<String>,
+    entry_ids: Vec<String>,
 ) -> Result<(), String> {
     let db_guard = state.database.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
-    for id in ids {
-        database
-            .delete_entry(&id)
-            .await
-            .map_err(|e| e.to_string())?;
+    DuplicateDetector::new(database)
+        .dismiss(&entry_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Maintenance commands
+#[tauri::command]
+async fn check_integrity(state: State<'_, AppState>) -> Result<MaintenanceResult, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Maintenance::new(database)
+        .check_integrity()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn optimize(state: State<'_, AppState>) -> Result<MaintenanceResult, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Maintenance::new(database)
+        .optimize()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn vacuum(state: State<'_, AppState>) -> Result<MaintenanceResult, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Maintenance::new(database)
+        .vacuum()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn rebuild_search_index(state: State<'_, AppState>) -> Result<MaintenanceResult, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Maintenance::new(database)
+        .rebuild_search_index()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn analyze(state: State<'_, AppState>) -> Result<MaintenanceResult, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Maintenance::new(database)
+        .analyze()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Backup commands
+/// Back up the open journal now, regardless of the schedule
+#[tauri::command]
+async fn create_backup(state: State<'_, AppState>) -> Result<BackupInfo, String> {
+    let path = backup_path(&state).await?;
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Backups::new(database, &path)
+        .create()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_backups(state: State<'_, AppState>) -> Result<Vec<BackupInfo>, String> {
+    let path = backup_path(&state).await?;
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Backups::new(database, &path)
+        .list()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Sync commands
+/// Exchange entry changes with other devices through the configured sync folder
+#[tauri::command]
+async fn sync_journal(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncReport, String> {
+    let registry_guard = state.journals.lock().await;
+    let registry = registry_guard.as_ref().ok_or("Journals not initialized")?;
+    let journal = registry.last_opened().ok_or("No journal is open")?;
+    if journal.storage.uses_folder() {
+        return Err("Markdown journals sync through their folder".to_string());
+    }
+    if registry.webdav_credentials_path(&journal.id).exists() {
+        return Err(
+            "This journal syncs with WebDAV; remove the account to use a folder".to_string(),
+        );
     }
 
-    Ok(())
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let settings = SettingsStore::new(database)
+        .get()
+        .await
+        .map_err(|e| e.to_string())?;
+    let folder = settings
+        .sync_folder
+        .ok_or("Choose a sync folder in settings first")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Sync);
+
+    SyncEngine::new(database)
+        .sync(&PathBuf::from(folder))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn list_journal_entries(
+async fn get_sync_status(state: State<'_, AppState>) -> Result<SyncStatus, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SyncEngine::new(database)
+        .status()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_sync_conflicts(state: State<'_, AppState>) -> Result<Vec<SyncConflict>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    SyncEngine::new(database)
+        .list_conflicts()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn resolve_sync_conflict(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-    limit: Option<i64>,
-    offset: Option<i64>,
-) -> Result<Vec<JournalEntry>, String> {
+    id: String,
+    resolution: ConflictResolution,
+) -> Result<Option<JournalEntry>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Sync);
+
+    SyncEngine::new(database)
+        .resolve_conflict(&id, resolution)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// WebDAV commands
+/// The open journal's id, its WebDAV credentials file and its log mirror
+fn current_webdav_paths(
+    journals: &Option<JournalRegistry>,
+) -> Result<(String, PathBuf, PathBuf), String> {
+    let registry = journals.as_ref().ok_or("Journals not initialized")?;
+    let journal = registry.last_opened().ok_or("No journal is open")?;
+    if journal.storage.uses_folder() {
+        return Err("Markdown journals sync through their folder".to_string());
+    }
+
+    Ok((
+        journal.id.clone(),
+        registry.webdav_credentials_path(&journal.id),
+        registry.webdav_mirror_dir(&journal.id),
+    ))
+}
+
+/// Save the WebDAV account for the open journal after checking it against the server
+#[tauri::command]
+async fn set_webdav_account(
+    state: State<'_, AppState>,
+    url: String,
+    username: String,
+    password: String,
+    passphrase: String,
+) -> Result<WebDavAccount, String> {
+    let registry_guard = state.journals.lock().await;
+    let (journal_id, credentials_path, mirror) = current_webdav_paths(&registry_guard)?;
+
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let settings = SettingsStore::new(database)
+        .get()
+        .await
+        .map_err(|e| e.to_string())?;
+    if settings.sync_folder.is_some() {
+        return Err("This journal syncs through a folder; clear it to use WebDAV".to_string());
+    }
+
+    let credentials = WebDavCredentials {
+        url,
+        username,
+        password,
+        passphrase,
+    };
+    credentials.validate().map_err(|e| e.to_string())?;
+    WebDavSync::new(database, &credentials, mirror)
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    webdav::save_credentials(&credentials_path, &journal_id, &credentials)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(credentials.account())
+}
+
+#[tauri::command]
+async fn get_webdav_account(state: State<'_, AppState>) -> Result<Option<WebDavAccount>, String> {
+    let registry_guard = state.journals.lock().await;
+    let (_, credentials_path, _) = current_webdav_paths(&registry_guard)?;
+
+    webdav::load_account(&credentials_path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_webdav_account(state: State<'_, AppState>) -> Result<(), String> {
+    let registry_guard = state.journals.lock().await;
+    let (journal_id, credentials_path, _) = current_webdav_paths(&registry_guard)?;
+
+    webdav::delete_credentials(&credentials_path, &journal_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Exchange entry changes with other devices through the journal's WebDAV account
+#[tauri::command]
+async fn sync_webdav(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncReport, String> {
+    let registry_guard = state.journals.lock().await;
+    let (journal_id, credentials_path, mirror) = current_webdav_paths(&registry_guard)?;
+    let credentials = webdav::load_credentials(&credentials_path, &journal_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Add a WebDAV account first")?;
+
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::Sync);
+
+    WebDavSync::new(database, &credentials, mirror)
+        .map_err(|e| e.to_string())?
+        .sync()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_webdav_status(state: State<'_, AppState>) -> Result<WebDavStatus, String> {
+    let registry_guard = state.journals.lock().await;
+    let (_, credentials_path, mirror) = current_webdav_paths(&registry_guard)?;
+
+    let account = webdav::load_account(&credentials_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    webdav::status(&mirror, account)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// History commands
+/// Commits that changed an entry in a git-backed journal, newest first
+#[tauri::command]
+async fn get_entry_history(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<GitCommit>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database.entry_history(&id).await.map_err(|e| e.to_string())
+}
+
+/// An entry as it was saved in one of its commits, without restoring it
+#[tauri::command]
+async fn get_entry_version(
+    state: State<'_, AppState>,
+    id: String,
+    commit: String,
+) -> Result<JournalEntry, String> {
     let db_guard = state.database.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
     database
-        .list_entries(limit, offset)
+        .entry_version(&id, &commit)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn search_journal_entries(
+async fn restore_entry_version(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-    query: String,
-) -> Result<Vec<JournalEntry>, String> {
+    id: String,
+    commit: String,
+) -> Result<JournalEntry, String> {
     let db_guard = state.database.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
+    let _changes = EntryChangeGuard::new(&app_handle, database, ChangeSource::History);
+
     database
-        .search_entries(&query)
+        .restore_entry(&id, &commit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_history_remote(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+    let git = database
+        .git()
+        .ok_or("This journal does not keep a git history")?;
+
+    git.remote_url().await.map_err(|e| e.to_string())
+}
+
+/// Set or clear the remote that push_journal_history pushes to
+#[tauri::command]
+async fn set_history_remote(state: State<'_, AppState>, url: Option<String>) -> Result<(), String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+    let git = database
+        .git()
+        .ok_or("This journal does not keep a git history")?;
+
+    let url = url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    if url.as_deref().is_some_and(|url| url.starts_with('-')) {
+        return Err("Invalid remote URL".to_string());
+    }
+    git.set_remote(url.as_deref())
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Push the journal's history when a remote is configured and reachable
+#[tauri::command]
+async fn push_journal_history(state: State<'_, AppState>) -> Result<PushResult, String> {
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+    let git = database
+        .git()
+        .ok_or("This journal does not keep a git history")?;
+
+    git.push().await.map_err(|e| e.to_string())
+}
+
 // AI commands
 #[tauri::command]
 async fn generate_embedding(
@@ -314,18 +2632,112 @@ async fn generate_embedding(
     text: String,
     model: Option<String>,
 ) -> Result<Vec<f32>, String> {
-    let ai_guard = state.ai_service.lock().await;
-    let ai_service = ai_guard.as_ref().ok_or("AI service not initialized")?;
+    let ai_service = current_ai_service(&state).await?;
 
-    let request = EmbeddingRequest { text, model };
-    let response = ai_service
-        .generate_embedding(request)
-        .await
-        .map_err(|e| e.to_string())?;
+    let request = EmbeddingRequest {
+        text,
+        model: model.clone(),
+    };
+    let started = Instant::now();
+    let result = ai_service.generate_embedding(request).await;
+
+    track_ai_call(&state, "embedding", model, started, result.is_ok()).await;
+    let response = result.map_err(|e| e.to_string())?;
 
     Ok(response.embedding)
 }
 
+#[tauri::command]
+async fn generate_embeddings(
+    state: State<'_, AppState>,
+    texts: Vec<String>,
+    model: Option<String>,
+) -> Result<EmbeddingBatchResponse, String> {
+    let ai_service = current_ai_service(&state).await?;
+
+    let request = EmbeddingBatchRequest {
+        texts,
+        model: model.clone(),
+    };
+    let started = Instant::now();
+    let result = ai_service.generate_embeddings(request).await;
+
+    track_ai_call(&state, "embedding", model, started, result.is_ok()).await;
+    result.map_err(|e| e.to_string())
+}
+
+/// Embed every entry again, switching the embedding model first if `model` is given
+#[tauri::command]
+async fn reindex_embeddings(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    model: Option<String>,
+) -> Result<EmbeddingIndexStatus, String> {
+    if let Some(model) = model {
+        let settings = {
+            let db_guard = state.database.lock().await;
+            let database = db_guard.as_ref().ok_or("Database not initialized")?;
+            let store = SettingsStore::new(database);
+            let current = store.get().await.map_err(|e| e.to_string())?;
+
+            if current.embedding_model == model {
+                None
+            } else {
+                let mut patch = serde_json::Map::new();
+                patch.insert("embedding_model".to_string(), Value::String(model));
+                Some(store.update(patch).await.map_err(|e| e.to_string())?)
+            }
+        };
+
+        if let Some(settings) = settings {
+            apply_settings(&state, &settings).await?;
+            let _ = app_handle.emit("settings://changed", &settings);
+        }
+    }
+
+    let model = embedding_model_name(&state).await?;
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+    let index = EmbeddingIndex::new(database);
+
+    index.enqueue_all(true).await.map_err(|e| e.to_string())?;
+    state.embedding_index.notify_one();
+
+    index.status(&model).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn embedding_index_status(
+    state: State<'_, AppState>,
+) -> Result<EmbeddingIndexStatus, String> {
+    let model = embedding_model_name(&state).await?;
+    let db_guard = state.database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    EmbeddingIndex::new(database)
+        .status(&model)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn embedding_model_name(state: &AppState) -> Result<String, String> {
+    current_ai_service(state)
+        .await?
+        .embedding_model()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// A copy of the AI service, so its lock isn't held while a model works
+async fn current_ai_service(state: &AppState) -> Result<AIService, String> {
+    state
+        .ai_service
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| "AI service not initialized".to_string())
+}
+
 #[tauri::command]
 async fn generate_chat_response(
     state: State<'_, AppState>,
@@ -333,18 +2745,18 @@ async fn generate_chat_response(
     context: Option<String>,
     model: Option<String>,
 ) -> Result<String, String> {
-    let ai_guard = state.ai_service.lock().await;
-    let ai_service = ai_guard.as_ref().ok_or("AI service not initialized")?;
+    let ai_service = current_ai_service(&state).await?;
 
     let request = ChatRequest {
         message,
         context,
-        model,
+        model: model.clone(),
     };
-    let response = ai_service
-        .generate_chat(request)
-        .await
-        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let result = ai_service.generate_chat(request).await;
+
+    track_ai_call(&state, "chat", model, started, result.is_ok()).await;
+    let response = result.map_err(|e| e.to_string())?;
 
     Ok(response.response)
 }
@@ -354,8 +2766,7 @@ async fn analyze_echo_patterns(
     state: State<'_, AppState>,
     entry_ids: Vec<String>,
 ) -> Result<Value, String> {
-    let ai_guard = state.ai_service.lock().await;
-    let ai_service = ai_guard.as_ref().ok_or("AI service not initialized")?;
+    let ai_service = current_ai_service(&state).await?;
 
     // Fetch journal entries from database
     let db_guard = state.database.lock().await;
@@ -370,26 +2781,25 @@ async fn analyze_echo_patterns(
 
     drop(db_guard);
 
-    let analysis = ai_service
-        .analyze_echo_patterns(entry_contents)
-        .await
-        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let result = ai_service.analyze_echo_patterns(entry_contents).await;
+
+    track_ai_call(&state, "echo_analysis", None, started, result.is_ok()).await;
+    let analysis = result.map_err(|e| e.to_string())?;
 
     Ok(serde_json::to_value(analysis).map_err(|e| e.to_string())?)
 }
 
 #[tauri::command]
 async fn get_ai_models(state: State<'_, AppState>) -> Result<Vec<String>, String> {
-    let ai_guard = state.ai_service.lock().await;
-    let ai_service = ai_guard.as_ref().ok_or("AI service not initialized")?;
+    let ai_service = current_ai_service(&state).await?;
 
     ai_service.list_models().await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn check_ai_availability(state: State<'_, AppState>) -> Result<bool, String> {
-    let ai_guard = state.ai_service.lock().await;
-    let ai_service = ai_guard.as_ref().ok_or("AI service not initialized")?;
+    let ai_service = current_ai_service(&state).await?;
 
     ai_service
         .check_availability()
@@ -0,0 +1,238 @@
+/**
+ * Database maintenance for MyFace SnapJournal
+ *
+ * This module handles:
+ * - SQLite connection settings (WAL, busy timeout, foreign keys)
+ * - Integrity checks, including the full-text search index
+ * - Optimize, analyze, vacuum and search index rebuilds
+ * - Light maintenance while the app is idle, including rebuilding
+ *   statistics that fell out of step with the entries and pruning old
+ *   analytics events
+ */
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use sqlx::Row;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::analytics::AnalyticsRecorder;
+use crate::database::{format_timestamp, parse_timestamp, Database};
+
+const LAST_MAINTENANCE_SETTING: &str = "last_maintenance_at";
+
+/// How often idle maintenance is allowed to run
+const IDLE_MAINTENANCE_EVERY_HOURS: i64 = 6;
+
+/// How often the app checks whether idle maintenance is due
+pub const IDLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceResult {
+    pub operation: String,
+    pub ok: bool,
+    pub duration_ms: u64,
+    pub messages: Vec<String>,
+    pub size_before: Option<i64>,
+    pub size_after: Option<i64>,
+}
+
+/// Connection settings for a journal database
+pub fn connect_options(database_path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(database_path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(std::time::Duration::from_secs(10))
+        .foreign_keys(true)
+}
+
+pub struct Maintenance<'a> {
+    database: &'a Database,
+}
+
+impl<'a> Maintenance<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        Maintenance { database }
+    }
+
+    /// Run SQLite's integrity and foreign key checks and verify the search index
+    pub async fn check_integrity(&self) -> Result<MaintenanceResult> {
+        let started = Instant::now();
+        let mut messages = Vec::new();
+
+        let integrity: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to run integrity check")?;
+        let mut ok = integrity.len() == 1 && integrity[0] == "ok";
+        if !ok {
+            messages.extend(integrity);
+        }
+
+        let violations = sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to run foreign key check")?;
+        for row in &violations {
+            ok = false;
+            messages.push(format!(
+                "Foreign key violation in {} (row {})",
+                row.get::<String, _>(0),
+                row.get::<Option<i64>, _>(1).unwrap_or_default()
+            ));
+        }
+
+        // For external content tables, rank 1 compares the index with journal_entries
+        if let Err(e) = sqlx::query(
+            "INSERT INTO journal_entries_fts(journal_entries_fts, rank) VALUES ('integrity-check', 1)",
+        )
+        .execute(self.database.pool())
+        .await
+        {
+            ok = false;
+            messages.push(format!("Search index is out of sync: {}", e));
+        }
+
+        if ok {
+            messages.push("ok".to_string());
+        }
+
+        Ok(self.result("check_integrity", ok, started, messages, None, None))
+    }
+
+    /// Let SQLite refresh statistics it considers stale and merge search index segments
+    pub async fn optimize(&self) -> Result<MaintenanceResult> {
+        let started = Instant::now();
+
+        sqlx::query("PRAGMA optimize")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to optimize database")?;
+
+        sqlx::query("INSERT INTO journal_entries_fts(journal_entries_fts) VALUES ('optimize')")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to optimize search index")?;
+
+        Ok(self.result("optimize", true, started, vec![], None, None))
+    }
+
+    pub async fn analyze(&self) -> Result<MaintenanceResult> {
+        let started = Instant::now();
+
+        sqlx::query("ANALYZE")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to analyze database")?;
+
+        Ok(self.result("analyze", true, started, vec![], None, None))
+    }
+
+    /// Rewrite the database file to reclaim free pages
+    pub async fn vacuum(&self) -> Result<MaintenanceResult> {
+        let started = Instant::now();
+        let size_before = self.database_size().await?;
+
+        self.checkpoint().await?;
+        sqlx::query("VACUUM")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to vacuum database")?;
+
+        let size_after = self.database_size().await?;
+        let messages = vec![format!(
+            "Reclaimed {} bytes",
+            (size_before - size_after).max(0)
+        )];
+
+        Ok(self.result(
+            "vacuum",
+            true,
+            started,
+            messages,
+            Some(size_before),
+            Some(size_after),
+        ))
+    }
+
+    /// Rebuild the full-text search index from journal_entries
+    pub async fn rebuild_search_index(&self) -> Result<MaintenanceResult> {
+        let started = Instant::now();
+
+        sqlx::query("INSERT INTO journal_entries_fts(journal_entries_fts) VALUES ('rebuild')")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to rebuild search index")?;
+
+        Ok(self.result("rebuild_search_index", true, started, vec![], None, None))
+    }
+
+    /// Copy the WAL back into the main database file and truncate it
+    pub async fn checkpoint(&self) -> Result<()> {
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to checkpoint WAL")?;
+
+        Ok(())
+    }
+
+    /// Optimize, checkpoint, repair stale statistics and drop analytics events
+    /// past their retention if it hasn't been done recently
+    pub async fn run_idle_maintenance(&self) -> Result<Option<MaintenanceResult>> {
+        if let Some(last) = self.database.get_setting(LAST_MAINTENANCE_SETTING).await? {
+            if let Ok(last) = parse_timestamp(&last) {
+                if Utc::now() - last < Duration::hours(IDLE_MAINTENANCE_EVERY_HOURS) {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let result = self.optimize().await?;
+        self.database.rebuild_stale_statistics().await?;
+        AnalyticsRecorder::new(self.database).prune().await?;
+        sqlx::query("PRAGMA wal_checkpoint(PASSIVE)")
+            .execute(self.database.pool())
+            .await
+            .context("Failed to checkpoint WAL")?;
+
+        self.database
+            .set_setting(LAST_MAINTENANCE_SETTING, &format_timestamp(&Utc::now()))
+            .await?;
+
+        Ok(Some(result))
+    }
+
+    async fn database_size(&self) -> Result<i64> {
+        let row = sqlx::query(
+            "SELECT page_count * page_size AS size FROM pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(self.database.pool())
+        .await
+        .context("Failed to read database size")?;
+
+        Ok(row.get("size"))
+    }
+
+    fn result(
+        &self,
+        operation: &str,
+        ok: bool,
+        started: Instant,
+        messages: Vec<String>,
+        size_before: Option<i64>,
+        size_after: Option<i64>,
+    ) -> MaintenanceResult {
+        MaintenanceResult {
+            operation: operation.to_string(),
+            ok,
+            duration_ms: started.elapsed().as_millis() as u64,
+            messages,
+            size_before,
+            size_after,
+        }
+    }
+}
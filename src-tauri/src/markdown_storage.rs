@@ -0,0 +1,849 @@
+/**
+ * Markdown folder storage for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Keeping each entry as a Markdown file with front matter
+ * - Indexing the folder into SQLite for listing and search
+ * - Picking up files added, edited, moved or removed outside the app
+ * - Committing every change when the folder keeps its history in git
+ *
+ * The files are the source of truth, so the folder can be managed with git
+ * or Syncthing. The index is rebuilt from the folder whenever it is stale.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::UNIX_EPOCH;
+use uuid::Uuid;
+
+use crate::database::{format_timestamp, is_imported_source, parse_timestamp, JournalEntry};
+use crate::entry_events::EntryChanges;
+use crate::git_history::{GitCommit, GitRepository};
+use crate::locations::Bounds;
+use crate::moods::slugify;
+use crate::settings::JournalTimezone;
+use crate::storage::{EntryFilter, EntryStorage, SqliteStorage};
+
+const FRONT_MATTER_DELIMITER: &str = "---";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReindexReport {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    /// Files that could not be indexed, with the reason
+    pub failed: Vec<String>,
+    /// The entries behind the counts above
+    #[serde(skip)]
+    pub changes: EntryChanges,
+}
+
+impl ReindexReport {
+    pub fn changed(&self) -> bool {
+        self.added + self.updated + self.removed > 0
+    }
+}
+
+/// A Markdown file found in the journal folder
+struct FolderFile {
+    relative: String,
+    path: PathBuf,
+    modified_ms: i64,
+    size: i64,
+}
+
+/// What the index knows about a file
+struct IndexedFile {
+    entry_id: String,
+    modified_ms: i64,
+    size: i64,
+    content_hash: String,
+}
+
+pub struct MarkdownStorage {
+    folder: PathBuf,
+    pool: SqlitePool,
+    index: SqliteStorage,
+    git: Option<GitRepository>,
+    /// Dates files without an offset
+    timezone: Arc<RwLock<JournalTimezone>>,
+}
+
+impl MarkdownStorage {
+    pub fn new(
+        pool: SqlitePool,
+        folder: PathBuf,
+        git: Option<GitRepository>,
+        timezone: Arc<RwLock<JournalTimezone>>,
+    ) -> Self {
+        MarkdownStorage {
+            folder,
+            index: SqliteStorage::new(pool.clone(), timezone.clone()),
+            pool,
+            git,
+            timezone,
+        }
+    }
+
+    fn timezone(&self) -> JournalTimezone {
+        *self.timezone.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The repository holding the folder's history, if it keeps one
+    pub fn git(&self) -> Option<&GitRepository> {
+        self.git.as_ref()
+    }
+
+    /// Bring the index in line with the files in the folder
+    pub async fn reindex(&self) -> Result<ReindexReport> {
+        tokio::fs::create_dir_all(&self.folder)
+            .await
+            .with_context(|| format!("Failed to create journal folder {:?}", self.folder))?;
+
+        let files = scan_folder(&self.folder).await?;
+        let indexed = self.indexed_files().await?;
+
+        let mut report = ReindexReport::default();
+        let mut seen: HashSet<String> = HashSet::new();
+
+        for file in &files {
+            let known = indexed.get(&file.relative);
+            if let Some(known) = known {
+                if known.modified_ms == file.modified_ms && known.size == file.size {
+                    seen.insert(known.entry_id.clone());
+                    continue;
+                }
+            }
+
+            match self.index_file(file, known, &seen).await {
+                Ok(Some((id, added))) => {
+                    if added {
+                        report.added += 1;
+                        report.changes.record_created(&id);
+                    } else {
+                        report.updated += 1;
+                        report.changes.record_updated(&id);
+                    }
+                    seen.insert(id);
+                }
+                Ok(None) => {
+                    if let Some(known) = known {
+                        seen.insert(known.entry_id.clone());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to index {}: {}", file.relative, e);
+                    report.failed.push(format!("{}: {}", file.relative, e));
+                    // Keep the last good version rather than dropping the entry
+                    if let Some(known) = known {
+                        seen.insert(known.entry_id.clone());
+                    }
+                }
+            }
+        }
+
+        // Anything indexed that no file accounts for was removed outside the app
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM journal_entries")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list indexed entries")?;
+        for id in ids.iter().filter(|id| !seen.contains(*id)) {
+            self.index.delete_entry(id).await?;
+            report.removed += 1;
+            report.changes.record_deleted(id);
+        }
+
+        if report.changed() {
+            println!(
+                "Indexed {:?}: {} added, {} updated, {} removed",
+                self.folder, report.added, report.updated, report.removed
+            );
+        }
+
+        // Edits made with other tools, or saves whose commit failed, are
+        // recorded as soon as the app sees them
+        if let Some(git) = &self.git {
+            if let Err(e) = git.commit_all("Record changes made outside the app").await {
+                eprintln!("Failed to commit changes in {:?}: {:#}", self.folder, e);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Commits that touched an entry's file, newest first
+    pub async fn history(&self, id: &str) -> Result<Vec<GitCommit>> {
+        let git = self.require_git()?;
+        let path = match self.indexed_path(id).await? {
+            Some(path) => path,
+            // Deleted entries are found through the commit that removed them
+            None => git
+                .find_entry_path(id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Entry has no history: {}", id))?,
+        };
+
+        git.log(&path).await
+    }
+
+    /// The entry as it was saved in `commit`
+    pub async fn version_at(&self, id: &str, commit: &str) -> Result<JournalEntry> {
+        let git = self.require_git()?;
+        if commit.len() < 4 {
+            return Err(anyhow::anyhow!(
+                "Commit {:?} is too short to look up",
+                commit
+            ));
+        }
+        let version = self
+            .history(id)
+            .await?
+            .into_iter()
+            .find(|version| version.hash.starts_with(commit))
+            .ok_or_else(|| anyhow::anyhow!("Commit {} did not change this entry", commit))?;
+        if version.deleted {
+            return Err(anyhow::anyhow!(
+                "The entry was deleted in {}; pick an earlier commit",
+                short_hash(&version.hash)
+            ));
+        }
+
+        let text = git.show(&version.hash, &version.path).await?;
+        let stem = Path::new(&version.path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (mut entry, _) = from_markdown(&text, &stem, version.date, &self.timezone());
+        entry.id = id.to_string();
+
+        Ok(entry)
+    }
+
+    /// Save an older version of an entry as its current one
+    pub async fn restore_entry(&self, entry: &JournalEntry, commit: &str) -> Result<()> {
+        let relative = self.write_entry(entry).await?;
+        self.commit_change(
+            &[&relative],
+            &format!(
+                "Restore entry: {} (from {})",
+                describe(entry),
+                short_hash(commit)
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    fn require_git(&self) -> Result<&GitRepository> {
+        self.git
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("This journal does not keep a git history"))
+    }
+
+    /// Write an entry to its file, or a new one, and index it
+    async fn write_entry(&self, entry: &JournalEntry) -> Result<String> {
+        let relative = match self.indexed_path(&entry.id).await? {
+            Some(relative) => relative,
+            None => self.new_file_path(entry).await?,
+        };
+
+        write_atomic(&self.folder.join(&relative), &to_markdown(entry)).await?;
+        self.index.put_entry(entry).await?;
+        self.record_file(&entry.id, &relative).await?;
+
+        Ok(relative)
+    }
+
+    /// Commit a saved change; the file is already written, so a failed
+    /// commit is left for the next reindex to pick up
+    async fn commit_change(&self, paths: &[&str], message: &str) {
+        if let Some(git) = &self.git {
+            if let Err(e) = git.commit(paths, message).await {
+                eprintln!("Failed to commit \"{}\": {:#}", message, e);
+            }
+        }
+    }
+
+    /// Index one new or changed file, returning its entry id and whether it is new
+    async fn index_file(
+        &self,
+        file: &FolderFile,
+        known: Option<&IndexedFile>,
+        seen: &HashSet<String>,
+    ) -> Result<Option<(String, bool)>> {
+        let bytes = tokio::fs::read(&file.path)
+            .await
+            .context("Failed to read file")?;
+        let hash = content_hash(&bytes);
+
+        // Touched but not edited
+        if let Some(known) = known.filter(|known| known.content_hash == hash) {
+            self.record_file(&known.entry_id, &file.relative).await?;
+            return Ok(None);
+        }
+
+        let text = String::from_utf8(bytes).context("File is not valid UTF-8")?;
+        let stem = file
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let modified =
+            DateTime::<Utc>::from_timestamp_millis(file.modified_ms).unwrap_or_else(Utc::now);
+        let (mut entry, mut rewrite) = from_markdown(&text, &stem, modified, &self.timezone());
+
+        // A copied file carries the id of the original
+        if seen.contains(&entry.id) {
+            entry.id = Uuid::new_v4().to_string();
+            rewrite = true;
+        }
+
+        // Give hand-written files the front matter that identifies them
+        if rewrite {
+            write_atomic(&file.path, &to_markdown(&entry)).await?;
+        }
+
+        let added = self.index.get_entry(&entry.id).await?.is_none();
+        self.index.put_entry(&entry).await?;
+        self.record_file(&entry.id, &file.relative).await?;
+
+        Ok(Some((entry.id, added)))
+    }
+
+    async fn indexed_files(&self) -> Result<HashMap<String, IndexedFile>> {
+        let rows = sqlx::query(
+            "SELECT entry_id, path, modified_ms, size, content_hash FROM markdown_files",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to read indexed files")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.get("path"),
+                    IndexedFile {
+                        entry_id: row.get("entry_id"),
+                        modified_ms: row.get("modified_ms"),
+                        size: row.get("size"),
+                        content_hash: row.get("content_hash"),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn indexed_path(&self, id: &str) -> Result<Option<String>> {
+        sqlx::query_scalar("SELECT path FROM markdown_files WHERE entry_id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to look up entry file")
+    }
+
+    /// Remember the current state of an entry's file
+    async fn record_file(&self, entry_id: &str, relative: &str) -> Result<()> {
+        let path = self.folder.join(relative);
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .with_context(|| format!("Failed to read metadata of {:?}", path))?;
+        let hash = content_hash(&tokio::fs::read(&path).await?);
+
+        // The path may still be recorded for an entry that used to live there
+        sqlx::query("DELETE FROM markdown_files WHERE path = ? AND entry_id != ?")
+            .bind(relative)
+            .bind(entry_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update indexed files")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO markdown_files (entry_id, path, modified_ms, size, content_hash)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(entry_id) DO UPDATE SET
+                path = excluded.path, modified_ms = excluded.modified_ms,
+                size = excluded.size, content_hash = excluded.content_hash
+            "#,
+        )
+        .bind(entry_id)
+        .bind(relative)
+        .bind(modified_millis(&metadata))
+        .bind(metadata.len() as i64)
+        .bind(hash)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update indexed files")?;
+
+        Ok(())
+    }
+
+    /// A free `YYYY/YYYY-MM-DD-title.md` path for a new entry
+    async fn new_file_path(&self, entry: &JournalEntry) -> Result<String> {
+        let slug = match slugify(&entry.title) {
+            slug if slug.is_empty() => "entry".to_string(),
+            slug => slug
+                .chars()
+                .take(60)
+                .collect::<String>()
+                .trim_end_matches('-')
+                .to_string(),
+        };
+        let base = format!(
+            "{}/{}-{}",
+            entry.entry_date.format("%Y"),
+            entry.entry_date.format("%Y-%m-%d"),
+            slug
+        );
+
+        let mut candidate = format!("{}.md", base);
+        let mut counter = 2;
+        while tokio::fs::try_exists(self.folder.join(&candidate)).await? {
+            candidate = format!("{}-{}.md", base, counter);
+            counter += 1;
+        }
+
+        Ok(candidate)
+    }
+}
+
+impl EntryStorage for MarkdownStorage {
+    async fn create_entry(&self, entry: &JournalEntry) -> Result<()> {
+        // Index first so invalid entries never reach the folder
+        self.index.create_entry(entry).await?;
+
+        let relative = self.new_file_path(entry).await?;
+        let written = write_atomic(&self.folder.join(&relative), &to_markdown(entry)).await;
+        if let Err(e) = written {
+            self.index.delete_entry(&entry.id).await?;
+            return Err(e);
+        }
+
+        self.record_file(&entry.id, &relative).await?;
+        self.commit_change(&[&relative], &format!("Add entry: {}", describe(entry)))
+            .await;
+
+        Ok(())
+    }
+
+    async fn get_entry(&self, id: &str) -> Result<Option<JournalEntry>> {
+        self.index.get_entry(id).await
+    }
+
+    async fn update_entry(&self, entry: &JournalEntry) -> Result<()> {
+        let relative = self.write_entry(entry).await?;
+        self.commit_change(&[&relative], &format!("Edit entry: {}", describe(entry)))
+            .await;
+
+        Ok(())
+    }
+
+    async fn delete_entry(&self, id: &str) -> Result<()> {
+        let previous = self.index.get_entry(id).await?;
+        let relative = self.indexed_path(id).await?;
+        if let Some(relative) = &relative {
+            let path = self.folder.join(relative);
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("Failed to delete {:?}", path)),
+            }
+        }
+
+        self.index.delete_entry(id).await?;
+
+        if let (Some(relative), Some(previous)) = (relative, previous) {
+            self.commit_change(
+                &[&relative],
+                &format!("Delete entry: {}", describe(&previous)),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+
+    async fn list_entries(
+        &self,
+        limit: i64,
+        offset: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        self.index.list_entries(limit, offset, filter).await
+    }
+
+    async fn search_entries(&self, query: &str, filter: &EntryFilter) -> Result<Vec<JournalEntry>> {
+        self.index.search_entries(query, filter).await
+    }
+
+    async fn list_entries_in_bounds(
+        &self,
+        bounds: &Bounds,
+        limit: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        self.index
+            .list_entries_in_bounds(bounds, limit, filter)
+            .await
+    }
+}
+
+/// Render an entry as front matter followed by its content
+///
+/// Values are written as JSON, which is also valid YAML, so other Markdown
+/// tools can read the front matter.
+pub fn to_markdown(entry: &JournalEntry) -> String {
+    let fields = [
+        ("id", json!(entry.id)),
+        ("title", json!(entry.title)),
+        ("entry_date", json!(entry.entry_date.to_rfc3339())),
+        ("created_at", json!(format_timestamp(&entry.created_at))),
+        ("updated_at", json!(format_timestamp(&entry.updated_at))),
+        ("tags", json!(entry.tags)),
+        ("mood", json!(entry.mood)),
+        ("mood_intensity", json!(entry.mood_intensity)),
+        ("privacy", json!(entry.privacy)),
+        ("source", json!(entry.source)),
+        ("source_id", json!(entry.source_id)),
+        ("source_url", json!(entry.source_url)),
+        ("location", json!(entry.location)),
+        ("pinned", flag(entry.pinned)),
+        ("favorite", flag(entry.favorite)),
+        ("archived", flag(entry.archived)),
+        // Imported entries are locked unless the file says otherwise
+        (
+            "locked",
+            if entry.locked == entry.is_imported() {
+                Value::Null
+            } else {
+                json!(entry.locked)
+            },
+        ),
+        ("metadata", json!(entry.metadata)),
+    ];
+
+    let mut text = format!("{}\n", FRONT_MATTER_DELIMITER);
+    for (key, value) in fields.iter().filter(|(_, value)| !value.is_null()) {
+        text.push_str(&format!("{}: {}\n", key, value));
+    }
+    text.push_str(&format!(
+        "{}\n\n{}\n",
+        FRONT_MATTER_DELIMITER, entry.content
+    ));
+
+    text
+}
+
+/// Read an entry from a Markdown file, filling gaps from the file name and
+/// modification time. Dates without an offset are read in `timezone`. Also
+/// returns whether the file lacks an id and should be rewritten with one.
+pub fn from_markdown(
+    text: &str,
+    stem: &str,
+    modified: DateTime<Utc>,
+    timezone: &JournalTimezone,
+) -> (JournalEntry, bool) {
+    let (front_matter, body) = split_front_matter(text);
+    let fields = front_matter.map(parse_front_matter).unwrap_or_default();
+    let string = |key: &str| fields.get(key).and_then(value_to_string);
+
+    let id = string("id");
+    let needs_id = id.is_none();
+
+    let body = body
+        .strip_prefix("\r\n")
+        .or_else(|| body.strip_prefix('\n'))
+        .unwrap_or(body);
+    let body = body.strip_suffix('\n').unwrap_or(body);
+    let body = body.strip_suffix('\r').unwrap_or(body);
+
+    let tags = match fields.get("tags") {
+        Some(Value::Array(items)) => items.iter().filter_map(value_to_string).collect(),
+        Some(Value::String(tags)) => split_list(tags),
+        _ => vec![],
+    };
+
+    let source = string("source");
+    let flag = |key: &str| fields.get(key).and_then(Value::as_bool);
+
+    let entry = JournalEntry {
+        id: id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        title: string("title").unwrap_or_else(|| stem.to_string()),
+        content: body.to_string(),
+        tags,
+        mood: string("mood"),
+        mood_intensity: fields.get("mood_intensity").and_then(Value::as_f64),
+        privacy: string("privacy").unwrap_or_else(|| "private".to_string()),
+        locked: flag("locked").unwrap_or(is_imported_source(source.as_deref())),
+        source,
+        source_id: string("source_id"),
+        source_url: string("source_url"),
+        metadata: fields.get("metadata").filter(|v| !v.is_null()).cloned(),
+        location: fields
+            .get("location")
+            .and_then(|v| serde_json::from_value(v.clone()).ok()),
+        pinned: flag("pinned").unwrap_or(false),
+        favorite: flag("favorite").unwrap_or(false),
+        archived: flag("archived").unwrap_or(false),
+        entry_date: string("entry_date")
+            .or_else(|| string("date"))
+            .and_then(|date| parse_date(&date, timezone))
+            .unwrap_or_else(|| timezone.at(modified)),
+        created_at: string("created_at")
+            .and_then(|v| parse_timestamp(&v).ok())
+            .unwrap_or(modified),
+        updated_at: string("updated_at")
+            .and_then(|v| parse_timestamp(&v).ok())
+            .unwrap_or(modified),
+    };
+
+    (entry, needs_id)
+}
+
+/// A state written to the front matter only when it is set
+fn flag(value: bool) -> Value {
+    if value {
+        json!(true)
+    } else {
+        Value::Null
+    }
+}
+
+/// Split `---` delimited front matter from the rest of the file
+fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (None, text);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FRONT_MATTER_DELIMITER {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+
+    (None, text)
+}
+
+/// Parse the `key: value` lines and `- item` lists of simple YAML front matter
+fn parse_front_matter(front_matter: &str) -> Map<String, Value> {
+    let mut fields = Map::new();
+    let mut list: Option<(String, Vec<Value>)> = None;
+
+    for line in front_matter.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if let Some((_, items)) = list.as_mut() {
+                items.push(parse_value(item.trim()));
+            }
+            continue;
+        }
+
+        if let Some((key, items)) = list.take() {
+            fields.insert(key, list_value(items));
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim().to_string(), value.trim());
+        if value.is_empty() {
+            list = Some((key, vec![]));
+        } else {
+            fields.insert(key, parse_value(value));
+        }
+    }
+
+    if let Some((key, items)) = list {
+        fields.insert(key, list_value(items));
+    }
+
+    fields
+}
+
+fn list_value(items: Vec<Value>) -> Value {
+    if items.is_empty() {
+        Value::Null
+    } else {
+        Value::Array(items)
+    }
+}
+
+/// JSON values as written by `to_markdown`, otherwise plain YAML scalars
+fn parse_value(value: &str) -> Value {
+    if let Ok(value) = serde_json::from_str(value) {
+        return value;
+    }
+
+    if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return Value::Array(split_list(items).into_iter().map(Value::String).collect());
+    }
+
+    Value::String(unquote(value))
+}
+
+fn split_list(items: &str) -> Vec<String> {
+    items
+        .split(',')
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unquote(value: &str) -> String {
+    for quote in ['\'', '"'] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Entry dates may carry an offset, be a UTC timestamp, or be a plain day
+/// in `timezone`
+fn parse_date(value: &str, timezone: &JournalTimezone) -> Option<DateTime<FixedOffset>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date);
+    }
+    if let Ok(timestamp) = parse_timestamp(value) {
+        return Some(timezone.at(timestamp));
+    }
+
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    timezone.from_local(day.and_hms_opt(0, 0, 0)?)
+}
+
+/// Every `.md` file below `folder`, skipping hidden files and directories
+async fn scan_folder(folder: &Path) -> Result<Vec<FolderFile>> {
+    let folder = folder.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        let mut pending = vec![folder.clone()];
+
+        while let Some(dir) = pending.pop() {
+            for item in
+                std::fs::read_dir(&dir).with_context(|| format!("Failed to read {:?}", dir))?
+            {
+                let item = item?;
+                if item.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+
+                let path = item.path();
+                let file_type = item.file_type()?;
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                let is_markdown = path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+                if !file_type.is_file() || !is_markdown {
+                    continue;
+                }
+
+                let metadata = item.metadata()?;
+                let relative = path
+                    .strip_prefix(&folder)?
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                files.push(FolderFile {
+                    relative,
+                    path,
+                    modified_ms: modified_millis(&metadata),
+                    size: metadata.len() as i64,
+                });
+            }
+        }
+
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+        Ok(files)
+    })
+    .await?
+}
+
+/// Write through a hidden temporary file so readers never see half a file
+async fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create {:?}", parent))?;
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    tokio::fs::write(&temp_path, contents)
+        .await
+        .with_context(|| format!("Failed to write {:?}", temp_path))?;
+    tokio::fs::rename(&temp_path, path)
+        .await
+        .with_context(|| format!("Failed to replace {:?}", path))?;
+
+    Ok(())
+}
+
+/// How an entry is named in commit messages
+fn describe(entry: &JournalEntry) -> String {
+    let title = entry.title.trim();
+    if title.is_empty() {
+        format!("Untitled ({})", entry.entry_date.format("%Y-%m-%d"))
+    } else {
+        title.to_string()
+    }
+}
+
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn modified_millis(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,516 @@
+/**
+ * Mood registry for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Built-in and user-defined moods with valence/energy coordinates
+ * - Aliases so "Happy", "happy" and "😊" resolve to the same mood
+ * - Migrating legacy free-form mood strings into the registry
+ * - Mood timelines averaged per day, week, month or year
+ */
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::collections::BTreeMap;
+
+use crate::database::{format_timestamp, parse_entry_date, Database};
+use crate::statistics::{Granularity, StatisticsRange};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mood {
+    pub id: String,
+    pub label: String,
+    pub emoji: Option<String>,
+    /// Unpleasant (-1.0) to pleasant (1.0)
+    pub valence: f64,
+    /// Calm (-1.0) to activated (1.0)
+    pub energy: f64,
+    pub is_custom: bool,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoodInput {
+    pub label: String,
+    pub emoji: Option<String>,
+    pub valence: f64,
+    pub energy: f64,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoodTimelinePoint {
+    pub period_start: chrono::NaiveDate,
+    pub entries: i64,
+    /// Average valence, each entry scaled by its intensity (1.0 when unset)
+    pub valence: f64,
+    /// Average energy, each entry scaled by its intensity (1.0 when unset)
+    pub energy: f64,
+    pub average_intensity: f64,
+    pub moods: BTreeMap<String, i64>,
+}
+
+pub struct MoodRegistry<'a> {
+    database: &'a Database,
+}
+
+impl<'a> MoodRegistry<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        MoodRegistry { database }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Mood>> {
+        let rows = sqlx::query("SELECT * FROM moods ORDER BY is_custom, valence DESC, label")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list moods")?;
+
+        let alias_rows = sqlx::query("SELECT alias, mood_id FROM mood_aliases ORDER BY alias")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list mood aliases")?;
+
+        let mut aliases: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for row in alias_rows {
+            aliases
+                .entry(row.get("mood_id"))
+                .or_default()
+                .push(row.get("alias"));
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let id: String = row.get("id");
+                Mood {
+                    aliases: aliases.remove(&id).unwrap_or_default(),
+                    label: row.get("label"),
+                    emoji: row.get("emoji"),
+                    valence: row.get("valence"),
+                    energy: row.get("energy"),
+                    is_custom: row.get::<i64, _>("is_custom") != 0,
+                    id,
+                }
+            })
+            .collect())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Mood>> {
+        Ok(self.list().await?.into_iter().find(|m| m.id == id))
+    }
+
+    /// Canonical mood id for a label, id, emoji or alias
+    pub async fn resolve(&self, raw: &str) -> Result<Option<String>> {
+        let key = normalize(raw);
+        if key.is_empty() {
+            return Ok(None);
+        }
+
+        sqlx::query_scalar(
+            r#"
+            SELECT id FROM moods WHERE id = ?1 OR lower(label) = ?1 OR emoji = ?1
+            UNION
+            SELECT mood_id FROM mood_aliases WHERE alias = ?1
+            LIMIT 1
+            "#,
+        )
+        .bind(&key)
+        .fetch_optional(self.database.pool())
+        .await
+        .context("Failed to resolve mood")
+    }
+
+    /// Canonical mood id for `raw`, registering it as a custom mood if unknown
+    pub async fn resolve_or_create(&self, raw: Option<&str>) -> Result<Option<String>> {
+        let Some(raw) = raw.map(str::trim).filter(|r| !r.is_empty()) else {
+            return Ok(None);
+        };
+
+        if let Some(id) = self.resolve(raw).await? {
+            return Ok(Some(id));
+        }
+
+        // Unknown moods start out neutral until the user places them
+        let mood = self
+            .create(&MoodInput {
+                label: raw.to_string(),
+                emoji: None,
+                valence: 0.0,
+                energy: 0.0,
+                aliases: vec![],
+            })
+            .await?;
+
+        Ok(Some(mood.id))
+    }
+
+    pub async fn create(&self, input: &MoodInput) -> Result<Mood> {
+        validate(input)?;
+
+        let mut id = slugify(&input.label);
+        if id.is_empty() {
+            id = format!("custom-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        }
+        if self.resolve(&id).await?.is_some() || self.resolve(&input.label).await?.is_some() {
+            return Err(anyhow::anyhow!("Mood already exists: {}", input.label));
+        }
+
+        sqlx::query(
+            "INSERT INTO moods (id, label, emoji, valence, energy, is_custom, created_at) VALUES (?, ?, ?, ?, ?, 1, ?)",
+        )
+        .bind(&id)
+        .bind(input.label.trim())
+        .bind(&input.emoji)
+        .bind(input.valence)
+        .bind(input.energy)
+        .bind(format_timestamp(&Utc::now()))
+        .execute(self.database.pool())
+        .await
+        .context("Failed to create mood")?;
+
+        for alias in &input.aliases {
+            self.add_alias(alias, &id).await?;
+        }
+
+        self.get(&id)
+            .await?
+            .context("Mood not found after creation")
+    }
+
+    pub async fn update(&self, id: &str, input: &MoodInput) -> Result<Mood> {
+        validate(input)?;
+
+        let result = sqlx::query(
+            "UPDATE moods SET label = ?, emoji = ?, valence = ?, energy = ? WHERE id = ?",
+        )
+        .bind(input.label.trim())
+        .bind(&input.emoji)
+        .bind(input.valence)
+        .bind(input.energy)
+        .bind(id)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to update mood")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Mood not found: {}", id));
+        }
+
+        sqlx::query("DELETE FROM mood_aliases WHERE mood_id = ?")
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to replace mood aliases")?;
+
+        for alias in &input.aliases {
+            self.add_alias(alias, id).await?;
+        }
+
+        self.get(id).await?.context("Mood not found after update")
+    }
+
+    /// Delete a custom mood; entries that used it are left without a mood
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let mood = self
+            .get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Mood not found: {}", id))?;
+        if !mood.is_custom {
+            return Err(anyhow::anyhow!("Built-in moods cannot be deleted"));
+        }
+
+        // Entries go through the journal's storage so files, sync and statistics follow
+        let ids = self.entries_with_mood(id).await?;
+        self.database
+            .update_entries(&ids, |entry| {
+                entry.mood = None;
+                entry.mood_intensity = None;
+            })
+            .await
+            .context("Failed to clear mood from entries")?;
+
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        sqlx::query("DELETE FROM mood_aliases WHERE mood_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete mood aliases")?;
+
+        sqlx::query("DELETE FROM moods WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete mood")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit mood deletion")?;
+
+        Ok(())
+    }
+
+    pub async fn add_alias(&self, alias: &str, mood_id: &str) -> Result<()> {
+        let alias = normalize(alias);
+        if alias.is_empty() {
+            return Err(anyhow::anyhow!("Alias cannot be empty"));
+        }
+        if let Some(existing) = self.resolve(&alias).await? {
+            if existing != mood_id {
+                return Err(anyhow::anyhow!(
+                    "\"{}\" already refers to {}",
+                    alias,
+                    existing
+                ));
+            }
+        }
+
+        sqlx::query("INSERT OR IGNORE INTO mood_aliases (alias, mood_id) VALUES (?, ?)")
+            .bind(&alias)
+            .bind(mood_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to add mood alias")?;
+
+        Ok(())
+    }
+
+    pub async fn remove_alias(&self, alias: &str) -> Result<()> {
+        sqlx::query("DELETE FROM mood_aliases WHERE alias = ?")
+            .bind(normalize(alias))
+            .execute(self.database.pool())
+            .await
+            .context("Failed to remove mood alias")?;
+
+        Ok(())
+    }
+
+    /// Rewrite free-form moods on existing entries to registry ids.
+    /// Returns the number of entries changed.
+    pub async fn migrate_legacy_moods(&self) -> Result<usize> {
+        let legacy: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT mood FROM journal_entries
+             WHERE mood IS NOT NULL AND mood NOT IN (SELECT id FROM moods)",
+        )
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to find legacy moods")?;
+
+        let mut changed = 0;
+        for raw in legacy {
+            let id = self.resolve_or_create(Some(&raw)).await?;
+            let ids = self.entries_with_mood(&raw).await?;
+            changed += self
+                .database
+                .update_entries(&ids, |entry| entry.mood = id.clone())
+                .await
+                .context("Failed to migrate legacy mood")?
+                .len();
+        }
+
+        if changed > 0 {
+            println!("Migrated {} entries to the mood registry", changed);
+        }
+
+        Ok(changed)
+    }
+
+    async fn entries_with_mood(&self, mood: &str) -> Result<Vec<String>> {
+        sqlx::query_scalar("SELECT id FROM journal_entries WHERE mood = ?")
+            .bind(mood)
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to find entries with mood")
+    }
+
+    /// Average valence/energy of entries with a mood, bucketed by entry date
+    pub async fn timeline(
+        &self,
+        range: &StatisticsRange,
+        granularity: Granularity,
+    ) -> Result<Vec<MoodTimelinePoint>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT COALESCE(e.entry_date, e.created_at) AS entry_date, e.entry_utc_offset,
+                   e.mood_intensity, m.id, m.valence, m.energy
+            FROM journal_entries e
+            JOIN moods m ON m.id = e.mood
+            "#,
+        )
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to load mood timeline")?;
+
+        struct Accumulator {
+            entries: i64,
+            valence: f64,
+            energy: f64,
+            intensity: f64,
+            moods: BTreeMap<String, i64>,
+        }
+
+        let timezone = self.database.timezone();
+        let mut buckets: BTreeMap<chrono::NaiveDate, Accumulator> = BTreeMap::new();
+        for row in rows {
+            let date = parse_entry_date(
+                &row.get::<String, _>("entry_date"),
+                row.get("entry_utc_offset"),
+                &timezone,
+            )?
+            .date_naive();
+            if range.start.is_some_and(|start| date < start)
+                || range.end.is_some_and(|end| date > end)
+            {
+                continue;
+            }
+
+            let intensity = row.get::<Option<f64>, _>("mood_intensity").unwrap_or(1.0);
+            let bucket = buckets
+                .entry(granularity.period_start(date))
+                .or_insert_with(|| Accumulator {
+                    entries: 0,
+                    valence: 0.0,
+                    energy: 0.0,
+                    intensity: 0.0,
+                    moods: BTreeMap::new(),
+                });
+
+            bucket.entries += 1;
+            bucket.valence += row.get::<f64, _>("valence") * intensity;
+            bucket.energy += row.get::<f64, _>("energy") * intensity;
+            bucket.intensity += intensity;
+            *bucket.moods.entry(row.get("id")).or_insert(0) += 1;
+        }
+
+        Ok(buckets
+            .into_iter()
+            .map(|(period_start, bucket)| {
+                let n = bucket.entries as f64;
+                MoodTimelinePoint {
+                    period_start,
+                    entries: bucket.entries,
+                    valence: bucket.valence / n,
+                    energy: bucket.energy / n,
+                    average_intensity: bucket.intensity / n,
+                    moods: bucket.moods,
+                }
+            })
+            .collect())
+    }
+}
+
+pub fn validate_intensity(intensity: Option<f64>) -> Result<()> {
+    match intensity {
+        Some(i) if !(0.0..=1.0).contains(&i) => {
+            Err(anyhow::anyhow!("Mood intensity must be between 0 and 1"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate(input: &MoodInput) -> Result<()> {
+    if input.label.trim().is_empty() {
+        return Err(anyhow::anyhow!("Mood label cannot be empty"));
+    }
+    if !(-1.0..=1.0).contains(&input.valence) || !(-1.0..=1.0).contains(&input.energy) {
+        return Err(anyhow::anyhow!(
+            "Valence and energy must be between -1 and 1"
+        ));
+    }
+    Ok(())
+}
+
+fn normalize(raw: &str) -> String {
+    raw.trim().to_lowercase()
+}
+
+pub fn slugify(label: &str) -> String {
+    let slug: String = label
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, TempJournal};
+
+    async fn entry_file(journal: &TempJournal, id: &str) -> String {
+        let path: String = sqlx::query_scalar("SELECT path FROM markdown_files WHERE entry_id = ?")
+            .bind(id)
+            .fetch_one(journal.pool())
+            .await
+            .unwrap();
+        std::fs::read_to_string(journal.dir().join("entries").join(path)).unwrap()
+    }
+
+    async fn mood_count(journal: &TempJournal, mood: &str) -> f64 {
+        sqlx::query_scalar(
+            "SELECT COALESCE(SUM(stat_value), 0.0) FROM journal_statistics WHERE stat_type LIKE ?",
+        )
+        .bind(format!("mood:{}:%", mood))
+        .fetch_one(journal.pool())
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn deleting_a_mood_clears_it_from_entry_files() {
+        let journal = TempJournal::markdown().await;
+        let moods = MoodRegistry::new(&journal);
+        let wistful = moods.resolve_or_create(Some("Wistful")).await.unwrap();
+
+        let mut written = entry("rainy", "Rain all afternoon");
+        written.mood = wistful.clone();
+        written.mood_intensity = Some(0.5);
+        journal.create_entry(&written).await.unwrap();
+        journal.take_entry_changes();
+        assert_eq!(mood_count(&journal, "wistful").await, 1.0);
+
+        moods.delete(wistful.as_deref().unwrap()).await.unwrap();
+
+        let cleared = journal.get_entry("rainy").await.unwrap().unwrap();
+        assert_eq!(cleared.mood, None);
+        assert_eq!(cleared.mood_intensity, None);
+        assert!(!entry_file(&journal, "rainy").await.contains("wistful"));
+        assert_eq!(mood_count(&journal, "wistful").await, 0.0);
+        assert_eq!(journal.take_entry_changes().updated, ["rainy"]);
+        assert!(moods.resolve("wistful").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn legacy_moods_are_rewritten_in_entry_files() {
+        let journal = TempJournal::markdown().await;
+        let mut legacy = entry("old", "Written before the registry");
+        legacy.mood = Some("Over the Moon".to_string());
+        journal.create_entry(&legacy).await.unwrap();
+
+        let moods = MoodRegistry::new(&journal);
+        assert_eq!(moods.migrate_legacy_moods().await.unwrap(), 1);
+
+        let migrated = journal.get_entry("old").await.unwrap().unwrap();
+        assert_eq!(migrated.mood.as_deref(), Some("over-the-moon"));
+        assert!(entry_file(&journal, "old")
+            .await
+            .contains(r#"mood: "over-the-moon""#));
+        assert_eq!(mood_count(&journal, "over-the-moon").await, 1.0);
+        assert_eq!(mood_count(&journal, "over the moon").await, 0.0);
+
+        // Nothing is left to migrate the second time
+        assert_eq!(moods.migrate_legacy_moods().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,172 @@
+/**
+ * Database relocation for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Copying a journal's database to a new location from a consistent snapshot
+ * - Verifying the copy before anything is switched over or deleted
+ *
+ * Files are written under a temporary name next to the destination and
+ * renamed into place only once they check out, so an interrupted move never
+ * leaves a half-written journal behind.
+ */
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{ConnectOptions, Connection};
+use std::path::{Path, PathBuf};
+
+use crate::database::Database;
+use crate::journals;
+use crate::maintenance::Maintenance;
+
+const PARTIAL_SUFFIX: &str = ".partial";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelocationReport {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub tables: Vec<TableCount>,
+    pub original_deleted: bool,
+}
+
+/// Copy the open database to `destination` and verify the copy
+pub async fn copy_journal(
+    database: &Database,
+    source: &Path,
+    destination: &Path,
+) -> Result<RelocationReport> {
+    if destination == source {
+        return Err(anyhow::anyhow!(
+            "The journal is already at {:?}",
+            destination
+        ));
+    }
+    if tokio::fs::try_exists(destination).await? {
+        return Err(anyhow::anyhow!("{:?} already exists", destination));
+    }
+
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .context("Failed to create destination directory")?;
+    }
+
+    // Snapshot the database; VACUUM INTO reads a single consistent transaction
+    Maintenance::new(database).checkpoint().await?;
+    let tables = {
+        let mut connection = database.pool().acquire().await?;
+        table_counts(&mut connection).await?
+    };
+
+    let partial = partial_path(destination);
+    journals::delete_journal_files(&partial).await?;
+    if let Err(e) = snapshot_and_verify(database, &partial, &tables).await {
+        let _ = journals::delete_journal_files(&partial).await;
+        return Err(e);
+    }
+
+    tokio::fs::rename(&partial, destination)
+        .await
+        .context("Failed to move database into place")?;
+
+    Ok(RelocationReport {
+        old_path: source.to_path_buf(),
+        new_path: destination.to_path_buf(),
+        tables,
+        original_deleted: false,
+    })
+}
+
+/// Remove the original database files after a successful move
+pub async fn delete_original(source: &Path) -> Result<()> {
+    journals::delete_journal_files(source).await
+}
+
+async fn snapshot_and_verify(
+    database: &Database,
+    partial: &Path,
+    expected: &[TableCount],
+) -> Result<()> {
+    sqlx::query("VACUUM INTO ?")
+        .bind(partial.to_string_lossy().to_string())
+        .execute(database.pool())
+        .await
+        .context("Failed to copy database")?;
+
+    let mut connection = SqliteConnectOptions::new()
+        .filename(partial)
+        .connect()
+        .await
+        .context("Failed to open copied database")?;
+
+    let result = verify_copy(&mut connection, expected).await;
+    connection.close().await?;
+    result
+}
+
+async fn verify_copy(connection: &mut SqliteConnection, expected: &[TableCount]) -> Result<()> {
+    let integrity: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut *connection)
+        .await
+        .context("Failed to check copied database")?;
+    if integrity.len() != 1 || integrity[0] != "ok" {
+        return Err(anyhow::anyhow!(
+            "Copied database failed integrity check: {}",
+            integrity.join("; ")
+        ));
+    }
+
+    let actual = table_counts(&mut *connection).await?;
+    for table in expected {
+        let rows = actual
+            .iter()
+            .find(|t| t.table == table.table)
+            .map(|t| t.rows);
+        if rows != Some(table.rows) {
+            return Err(anyhow::anyhow!(
+                "Copied database has {} rows in {} instead of {}",
+                rows.unwrap_or(0),
+                table.table,
+                table.rows
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Row counts for every ordinary table in the database
+async fn table_counts(connection: &mut SqliteConnection) -> Result<Vec<TableCount>> {
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND sql NOT LIKE 'CREATE VIRTUAL%'
+         ORDER BY name",
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .context("Failed to list tables")?;
+
+    let mut counts = Vec::with_capacity(names.len());
+    for name in names {
+        let sql = format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\""));
+        let rows: i64 = sqlx::query_scalar(&sql)
+            .fetch_one(&mut *connection)
+            .await
+            .with_context(|| format!("Failed to count rows in {}", name))?;
+        counts.push(TableCount { table: name, rows });
+    }
+
+    Ok(counts)
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(PARTIAL_SUFFIX);
+    PathBuf::from(partial)
+}
@@ -0,0 +1,962 @@
+/**
+ * Journaling reminders for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Daily, weekday and "you haven't written in N days" reminders
+ * - Quiet hours, snoozing and reminders missed while the app was closed
+ * - A history of reminders fired and what the user did about them
+ *
+ * Schedules use the journal's timezone setting, or the computer's local time
+ * when none is set. The app checks for due reminders every minute and sends
+ * each one to the windows as a reminders://due event.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::database::{format_timestamp, is_imported_source, parse_timestamp, Database};
+use crate::settings::{JournalTimezone, Settings, SettingsStore};
+
+pub const REMINDERS_DUE: &str = "reminders://due";
+
+/// How often the app checks for due reminders
+pub const REMINDER_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Reminders missed by more than this while the app was closed are skipped
+const MISSED_GRACE_HOURS: i64 = 3;
+
+/// A reminder counts as acted on when an entry is written within this many hours
+const WROTE_WITHIN_HOURS: i64 = 12;
+
+const TIME_FORMAT: &str = "%H:%M";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReminderSchedule {
+    /// Every day at `time` ("HH:MM")
+    Daily { time: String },
+    /// On the given days at `time`
+    Weekdays { time: String, days: Vec<Weekday> },
+    /// At `time` on any day when nothing has been written for `days` days
+    Inactivity { time: String, days: u32 },
+}
+
+impl ReminderSchedule {
+    fn time(&self) -> Result<NaiveTime> {
+        let time = match self {
+            ReminderSchedule::Daily { time }
+            | ReminderSchedule::Weekdays { time, .. }
+            | ReminderSchedule::Inactivity { time, .. } => time,
+        };
+
+        parse_time(time)
+    }
+
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        match self {
+            ReminderSchedule::Weekdays { days, .. } => days.contains(&date.weekday()),
+            ReminderSchedule::Daily { .. } | ReminderSchedule::Inactivity { .. } => true,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.time()?;
+        match self {
+            ReminderSchedule::Weekdays { days, .. } if days.is_empty() => {
+                Err(anyhow::anyhow!("Pick at least one day for the reminder"))
+            }
+            ReminderSchedule::Inactivity { days: 0, .. } => Err(anyhow::anyhow!(
+                "Inactivity reminders need at least one day"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The latest scheduled time at or before `now`, which is given in `tz`
+    fn occurrence_before(
+        &self,
+        now: DateTime<FixedOffset>,
+        tz: JournalTimezone,
+    ) -> Result<Option<DateTime<FixedOffset>>> {
+        let time = self.time()?;
+        for days_back in 0..=7 {
+            let date = now.date_naive() - Duration::days(days_back);
+            if !self.runs_on(date) {
+                continue;
+            }
+            // A time skipped by a DST change falls on no day at all
+            let Some(at) = tz.from_local(date.and_time(time)) else {
+                continue;
+            };
+            if at <= now {
+                return Ok(Some(at));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The next scheduled time after `now`, which is given in `tz`
+    fn occurrence_after(
+        &self,
+        now: DateTime<FixedOffset>,
+        tz: JournalTimezone,
+    ) -> Result<Option<DateTime<FixedOffset>>> {
+        let time = self.time()?;
+        for days_ahead in 0..=8 {
+            let date = now.date_naive() + Duration::days(days_ahead);
+            if !self.runs_on(date) {
+                continue;
+            }
+            let Some(at) = tz.from_local(date.and_time(time)) else {
+                continue;
+            };
+            if at > now {
+                return Ok(Some(at));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: String,
+    pub title: String,
+    pub message: String,
+    pub schedule: ReminderSchedule,
+    pub enabled: bool,
+    /// Stay quiet on days something was already written
+    pub skip_if_written: bool,
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// The latest scheduled time that was fired or skipped
+    pub last_occurrence_at: Option<DateTime<Utc>>,
+    /// When the reminder is next expected, ignoring quiet hours and conditions
+    pub next_occurrence_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderInput {
+    pub title: String,
+    #[serde(default)]
+    pub message: String,
+    pub schedule: ReminderSchedule,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub skip_if_written: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderAction {
+    Opened,
+    Wrote,
+    Snoozed,
+    Dismissed,
+}
+
+impl ReminderAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReminderAction::Opened => "opened",
+            ReminderAction::Wrote => "wrote",
+            ReminderAction::Snoozed => "snoozed",
+            ReminderAction::Dismissed => "dismissed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "opened" => Some(ReminderAction::Opened),
+            "wrote" => Some(ReminderAction::Wrote),
+            "snoozed" => Some(ReminderAction::Snoozed),
+            "dismissed" => Some(ReminderAction::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+/// Payload of the reminders://due event, ready for the notification store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderNotification {
+    /// Pass back to record_reminder_action
+    pub history_id: i64,
+    pub reminder_id: String,
+    pub title: String,
+    pub message: String,
+    pub scheduled_for: DateTime<Utc>,
+    pub snoozed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderHistoryItem {
+    pub id: i64,
+    pub reminder_id: String,
+    pub title: String,
+    pub scheduled_for: DateTime<Utc>,
+    pub fired_at: DateTime<Utc>,
+    pub action: Option<ReminderAction>,
+    pub acted_at: Option<DateTime<Utc>>,
+}
+
+/// Hours during which no reminder is shown; they may span midnight
+#[derive(Debug, Clone, Copy)]
+struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    fn contains(&self, at: DateTime<FixedOffset>) -> bool {
+        let time = at.time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// When the quiet hours that include `at` are over
+    fn end_after(&self, at: DateTime<FixedOffset>, tz: JournalTimezone) -> DateTime<FixedOffset> {
+        let date = if at.time() < self.end {
+            at.date_naive()
+        } else {
+            at.date_naive() + Duration::days(1)
+        };
+
+        tz.from_local(date.and_time(self.end)).unwrap_or(at)
+    }
+}
+
+pub struct ReminderScheduler<'a> {
+    database: &'a Database,
+}
+
+impl<'a> ReminderScheduler<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        ReminderScheduler { database }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Reminder>> {
+        let rows = sqlx::query("SELECT * FROM reminders ORDER BY created_at")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list reminders")?;
+
+        let tz = SettingsStore::new(self.database).timezone().await?;
+        rows.iter().map(|row| reminder_from_row(row, tz)).collect()
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Reminder>> {
+        let row = sqlx::query("SELECT * FROM reminders WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await
+            .context("Failed to fetch reminder")?;
+
+        let tz = SettingsStore::new(self.database).timezone().await?;
+        row.as_ref()
+            .map(|row| reminder_from_row(row, tz))
+            .transpose()
+    }
+
+    pub async fn create(&self, input: &ReminderInput) -> Result<Reminder> {
+        validate(input)?;
+        let id = Uuid::new_v4().to_string();
+        let now = format_timestamp(&Utc::now());
+
+        sqlx::query(
+            r#"
+            INSERT INTO reminders (id, title, message, schedule, enabled, skip_if_written, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(input.title.trim())
+        .bind(input.message.trim())
+        .bind(serde_json::to_string(&input.schedule)?)
+        .bind(input.enabled)
+        .bind(input.skip_if_written)
+        .bind(&now)
+        .bind(&now)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to create reminder")?;
+
+        self.get(&id)
+            .await?
+            .context("Reminder not found after creation")
+    }
+
+    pub async fn update(&self, id: &str, input: &ReminderInput) -> Result<Reminder> {
+        validate(input)?;
+
+        // A new schedule starts counting from now rather than firing what it would have missed
+        let result = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET title = ?, message = ?, enabled = ?, skip_if_written = ?, updated_at = ?,
+                last_occurrence_at = CASE WHEN schedule = ? THEN last_occurrence_at ELSE ? END,
+                schedule = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(input.title.trim())
+        .bind(input.message.trim())
+        .bind(input.enabled)
+        .bind(input.skip_if_written)
+        .bind(format_timestamp(&Utc::now()))
+        .bind(serde_json::to_string(&input.schedule)?)
+        .bind(format_timestamp(&Utc::now()))
+        .bind(serde_json::to_string(&input.schedule)?)
+        .bind(id)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to update reminder")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Reminder not found: {}", id));
+        }
+
+        self.get(id)
+            .await?
+            .context("Reminder not found after update")
+    }
+
+    /// Delete a reminder; its history is kept
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM reminders WHERE id = ?")
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to delete reminder")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Reminder not found: {}", id));
+        }
+
+        Ok(())
+    }
+
+    /// Show the reminder again in `minutes`, recording the snooze against the last time it fired
+    pub async fn snooze(&self, id: &str, minutes: u32) -> Result<Reminder> {
+        if !(1..=24 * 60).contains(&minutes) {
+            return Err(anyhow::anyhow!("Snooze for between 1 minute and 24 hours"));
+        }
+        let until = Utc::now() + Duration::minutes(minutes as i64);
+
+        let result = sqlx::query("UPDATE reminders SET snoozed_until = ? WHERE id = ?")
+            .bind(format_timestamp(&until))
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to snooze reminder")?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Reminder not found: {}", id));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE reminder_history SET action = 'snoozed', acted_at = ?
+            WHERE id = (SELECT MAX(id) FROM reminder_history WHERE reminder_id = ?) AND action IS NULL
+            "#,
+        )
+        .bind(format_timestamp(&Utc::now()))
+        .bind(id)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to record snooze")?;
+
+        self.get(id)
+            .await?
+            .context("Reminder not found after snooze")
+    }
+
+    pub async fn record_action(&self, history_id: i64, action: ReminderAction) -> Result<()> {
+        let result =
+            sqlx::query("UPDATE reminder_history SET action = ?, acted_at = ? WHERE id = ?")
+                .bind(action.as_str())
+                .bind(format_timestamp(&Utc::now()))
+                .bind(history_id)
+                .execute(self.database.pool())
+                .await
+                .context("Failed to record reminder action")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!(
+                "Reminder history not found: {}",
+                history_id
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Reminders fired, newest first
+    pub async fn history(&self, limit: i64) -> Result<Vec<ReminderHistoryItem>> {
+        let rows =
+            sqlx::query("SELECT * FROM reminder_history ORDER BY fired_at DESC, id DESC LIMIT ?")
+                .bind(limit)
+                .fetch_all(self.database.pool())
+                .await
+                .context("Failed to list reminder history")?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ReminderHistoryItem {
+                    id: row.get("id"),
+                    reminder_id: row.get("reminder_id"),
+                    title: row.get("title"),
+                    scheduled_for: parse_timestamp(&row.get::<String, _>("scheduled_for"))?,
+                    fired_at: parse_timestamp(&row.get::<String, _>("fired_at"))?,
+                    action: row
+                        .get::<Option<String>, _>("action")
+                        .as_deref()
+                        .and_then(ReminderAction::parse),
+                    acted_at: row
+                        .get::<Option<String>, _>("acted_at")
+                        .as_deref()
+                        .map(parse_timestamp)
+                        .transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Fire every reminder due at `now`, returning what to show
+    pub async fn fire_due(&self, now: DateTime<Utc>) -> Result<Vec<ReminderNotification>> {
+        let settings = SettingsStore::new(self.database).get().await?;
+        let tz = settings.journal_timezone();
+        let now = tz.at(now);
+        let quiet_hours = quiet_hours(&settings)?;
+        let last_written = self.last_written().await?;
+        self.record_writing(last_written).await?;
+
+        let mut notifications = Vec::new();
+        for reminder in self.list().await? {
+            if !reminder.enabled {
+                continue;
+            }
+
+            match self
+                .check(&reminder, now, tz, quiet_hours, last_written)
+                .await
+            {
+                Ok(Some(notification)) => notifications.push(notification),
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to check reminder {}: {}", reminder.id, e),
+            }
+        }
+
+        Ok(notifications)
+    }
+
+    async fn check(
+        &self,
+        reminder: &Reminder,
+        now: DateTime<FixedOffset>,
+        tz: JournalTimezone,
+        quiet_hours: Option<QuietHours>,
+        last_written: Option<DateTime<Utc>>,
+    ) -> Result<Option<ReminderNotification>> {
+        let quiet_now = quiet_hours.is_some_and(|quiet| quiet.contains(now));
+
+        // A snoozed reminder comes back once, and stands in for anything scheduled meanwhile
+        if let Some(snoozed_until) = reminder.snoozed_until {
+            if now < snoozed_until || quiet_now {
+                return Ok(None);
+            }
+            sqlx::query(
+                "UPDATE reminders SET snoozed_until = NULL, last_occurrence_at = ? WHERE id = ?",
+            )
+            .bind(format_timestamp(&now.with_timezone(&Utc)))
+            .bind(&reminder.id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to clear snooze")?;
+
+            return self
+                .fire(reminder, snoozed_until, now, last_written, true)
+                .await
+                .map(Some);
+        }
+
+        let Some(occurrence) = reminder.schedule.occurrence_before(now, tz)? else {
+            return Ok(None);
+        };
+        let handled_until = reminder.last_occurrence_at.unwrap_or(reminder.created_at);
+        if occurrence.with_timezone(&Utc) <= handled_until {
+            return Ok(None);
+        }
+
+        // Held back by quiet hours until they end
+        let due_at = match quiet_hours {
+            Some(quiet) if quiet.contains(occurrence) => quiet.end_after(occurrence, tz),
+            _ => occurrence,
+        };
+        if now < due_at || quiet_now {
+            return Ok(None);
+        }
+
+        self.mark_handled(&reminder.id, occurrence.with_timezone(&Utc))
+            .await?;
+
+        // Missed while the app was closed for too long
+        if now - due_at > Duration::hours(MISSED_GRACE_HOURS) {
+            return Ok(None);
+        }
+
+        let written_since = |since: DateTime<Utc>| last_written.is_some_and(|at| at >= since);
+        let skip = match &reminder.schedule {
+            ReminderSchedule::Inactivity { days, .. } => {
+                written_since(occurrence.with_timezone(&Utc) - Duration::days(*days as i64))
+            }
+            ReminderSchedule::Daily { .. } | ReminderSchedule::Weekdays { .. } => {
+                let start_of_day = tz
+                    .from_local(occurrence.date_naive().and_time(NaiveTime::MIN))
+                    .unwrap_or(occurrence);
+                reminder.skip_if_written && written_since(start_of_day.with_timezone(&Utc))
+            }
+        };
+        if skip {
+            return Ok(None);
+        }
+
+        self.fire(
+            reminder,
+            occurrence.with_timezone(&Utc),
+            now,
+            last_written,
+            false,
+        )
+        .await
+        .map(Some)
+    }
+
+    async fn fire(
+        &self,
+        reminder: &Reminder,
+        scheduled_for: DateTime<Utc>,
+        now: DateTime<FixedOffset>,
+        last_written: Option<DateTime<Utc>>,
+        snoozed: bool,
+    ) -> Result<ReminderNotification> {
+        let message = match (&reminder.schedule, reminder.message.is_empty()) {
+            (ReminderSchedule::Inactivity { .. }, true) => match last_written {
+                Some(at) => match (now.with_timezone(&Utc) - at).num_days() {
+                    1 => "You haven't written since yesterday.".to_string(),
+                    days => format!("You haven't written in {} days.", days),
+                },
+                None => "You haven't written anything yet.".to_string(),
+            },
+            (_, true) => "Time to write in your journal.".to_string(),
+            (_, false) => reminder.message.clone(),
+        };
+
+        let history_id = sqlx::query(
+            "INSERT INTO reminder_history (reminder_id, title, scheduled_for, fired_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&reminder.id)
+        .bind(&reminder.title)
+        .bind(format_timestamp(&scheduled_for))
+        .bind(format_timestamp(&Utc::now()))
+        .execute(self.database.pool())
+        .await
+        .context("Failed to record reminder")?
+        .last_insert_rowid();
+
+        Ok(ReminderNotification {
+            history_id,
+            reminder_id: reminder.id.clone(),
+            title: reminder.title.clone(),
+            message,
+            scheduled_for,
+            snoozed,
+        })
+    }
+
+    async fn mark_handled(&self, id: &str, occurrence: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE reminders SET last_occurrence_at = ? WHERE id = ?")
+            .bind(format_timestamp(&occurrence))
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to update reminder")?;
+
+        Ok(())
+    }
+
+    /// Count a recent reminder as acted on once something has been written after it
+    async fn record_writing(&self, last_written: Option<DateTime<Utc>>) -> Result<()> {
+        let Some(last_written) = last_written else {
+            return Ok(());
+        };
+
+        let rows = sqlx::query(
+            "SELECT id, fired_at FROM reminder_history WHERE action IS NULL AND fired_at >= ?",
+        )
+        .bind(format_timestamp(
+            &(Utc::now() - Duration::hours(WROTE_WITHIN_HOURS)),
+        ))
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to read reminder history")?;
+
+        for row in rows {
+            let fired_at = parse_timestamp(&row.get::<String, _>("fired_at"))?;
+            if last_written >= fired_at {
+                sqlx::query(
+                    "UPDATE reminder_history SET action = 'wrote', acted_at = ? WHERE id = ?",
+                )
+                .bind(format_timestamp(&last_written))
+                .bind(row.get::<i64, _>("id"))
+                .execute(self.database.pool())
+                .await
+                .context("Failed to record reminder action")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// When the most recent entry was written; imported posts weren't written in the journal
+    async fn last_written(&self) -> Result<Option<DateTime<Utc>>> {
+        let latest: Vec<(Option<String>, String)> =
+            sqlx::query_as("SELECT source, MAX(created_at) FROM journal_entries GROUP BY source")
+                .fetch_all(self.database.pool())
+                .await
+                .context("Failed to find the latest entry")?;
+
+        let mut last_written = None;
+        for (source, created_at) in latest {
+            if is_imported_source(source.as_deref()) {
+                continue;
+            }
+            let created_at = parse_timestamp(&created_at)?;
+            if last_written < Some(created_at) {
+                last_written = Some(created_at);
+            }
+        }
+
+        Ok(last_written)
+    }
+}
+
+fn quiet_hours(settings: &Settings) -> Result<Option<QuietHours>> {
+    match (&settings.quiet_hours_start, &settings.quiet_hours_end) {
+        (Some(start), Some(end)) => Ok(Some(QuietHours {
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Parse a reminder or quiet hours time written as "HH:MM"
+pub fn parse_time(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), TIME_FORMAT)
+        .with_context(|| format!("Invalid time {:?}, expected HH:MM", value))
+}
+
+fn validate(input: &ReminderInput) -> Result<()> {
+    if input.title.trim().is_empty() {
+        return Err(anyhow::anyhow!("Reminder title cannot be empty"));
+    }
+
+    input.schedule.validate()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn reminder_from_row(row: &SqliteRow, tz: JournalTimezone) -> Result<Reminder> {
+    let schedule: ReminderSchedule = serde_json::from_str(&row.get::<String, _>("schedule"))
+        .context("Invalid reminder schedule")?;
+    let next_occurrence_at = schedule
+        .occurrence_after(tz.now(), tz)?
+        .map(|at| at.with_timezone(&Utc));
+    let optional_timestamp = |column: &str| {
+        row.get::<Option<String>, _>(column)
+            .as_deref()
+            .map(parse_timestamp)
+            .transpose()
+    };
+
+    Ok(Reminder {
+        id: row.get("id"),
+        title: row.get("title"),
+        message: row.get("message"),
+        enabled: row.get::<i64, _>("enabled") != 0,
+        skip_if_written: row.get::<i64, _>("skip_if_written") != 0,
+        snoozed_until: optional_timestamp("snoozed_until")?,
+        last_occurrence_at: optional_timestamp("last_occurrence_at")?,
+        next_occurrence_at,
+        created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
+        updated_at: parse_timestamp(&row.get::<String, _>("updated_at"))?,
+        schedule,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry_at, TempJournal};
+
+    fn local(value: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(value).unwrap()
+    }
+
+    fn daily(time: &str) -> ReminderSchedule {
+        ReminderSchedule::Daily {
+            time: time.to_string(),
+        }
+    }
+
+    fn reminder(schedule: ReminderSchedule, skip_if_written: bool) -> ReminderInput {
+        ReminderInput {
+            title: "Write".to_string(),
+            message: String::new(),
+            schedule,
+            enabled: true,
+            skip_if_written,
+        }
+    }
+
+    /// A journal in UTC with the given settings
+    async fn journal(settings: &[(&str, &str)]) -> TempJournal {
+        let journal = TempJournal::new().await;
+        let mut patch = serde_json::Map::new();
+        patch.insert("timezone".to_string(), "UTC".into());
+        for (key, value) in settings {
+            patch.insert(key.to_string(), (*value).into());
+        }
+        SettingsStore::new(&journal).update(patch).await.unwrap();
+        journal
+    }
+
+    /// `time` on a day `days` from today, after any reminder created now
+    fn at(days: i64, time: &str) -> DateTime<Utc> {
+        (Utc::now().date_naive() + Duration::days(days))
+            .and_time(parse_time(time).unwrap())
+            .and_utc()
+    }
+
+    async fn write_at(journal: &TempJournal, id: &str, written: DateTime<Utc>) {
+        journal
+            .create_entry(&entry_at(id, "Dear diary", &written.to_rfc3339()))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn quiet_hours_can_span_midnight() {
+        let quiet = QuietHours {
+            start: parse_time("22:00").unwrap(),
+            end: parse_time("07:00").unwrap(),
+        };
+        assert!(quiet.contains(local("2026-03-14T23:30:00Z")));
+        assert!(quiet.contains(local("2026-03-15T03:00:00Z")));
+        assert!(!quiet.contains(local("2026-03-15T07:00:00Z")));
+        assert!(!quiet.contains(local("2026-03-14T21:59:00Z")));
+
+        // Quiet hours entered either side of midnight end the same morning
+        let utc = JournalTimezone::parse("UTC").unwrap();
+        assert_eq!(
+            quiet.end_after(local("2026-03-14T23:30:00Z"), utc),
+            local("2026-03-15T07:00:00Z")
+        );
+        assert_eq!(
+            quiet.end_after(local("2026-03-15T03:00:00Z"), utc),
+            local("2026-03-15T07:00:00Z")
+        );
+
+        let lunch = QuietHours {
+            start: parse_time("12:00").unwrap(),
+            end: parse_time("14:00").unwrap(),
+        };
+        assert!(lunch.contains(local("2026-03-14T13:00:00Z")));
+        assert!(!lunch.contains(local("2026-03-14T14:00:00Z")));
+        assert!(!lunch.contains(local("2026-03-14T23:00:00Z")));
+    }
+
+    #[test]
+    fn times_skipped_by_daylight_saving_fall_on_no_day() {
+        // New York skips from 02:00 to 03:00 on 8 March 2026
+        let new_york = JournalTimezone::parse("America/New_York").unwrap();
+        let schedule = daily("02:30");
+
+        assert_eq!(
+            schedule
+                .occurrence_before(local("2026-03-08T10:00:00-04:00"), new_york)
+                .unwrap(),
+            Some(local("2026-03-07T02:30:00-05:00"))
+        );
+        assert_eq!(
+            schedule
+                .occurrence_after(local("2026-03-07T12:00:00-05:00"), new_york)
+                .unwrap(),
+            Some(local("2026-03-09T02:30:00-04:00"))
+        );
+
+        // 01:30 happens twice on 1 November 2026; the first one counts
+        assert_eq!(
+            daily("01:30")
+                .occurrence_after(local("2026-11-01T00:00:00-04:00"), new_york)
+                .unwrap(),
+            Some(local("2026-11-01T01:30:00-04:00"))
+        );
+    }
+
+    #[test]
+    fn weekday_schedules_skip_other_days() {
+        let schedule = ReminderSchedule::Weekdays {
+            time: "08:00".to_string(),
+            days: vec![Weekday::Mon, Weekday::Fri],
+        };
+        let utc = JournalTimezone::parse("UTC").unwrap();
+
+        // Wednesday 18 March 2026
+        let wednesday = local("2026-03-18T12:00:00Z");
+        assert_eq!(
+            schedule.occurrence_before(wednesday, utc).unwrap(),
+            Some(local("2026-03-16T08:00:00Z"))
+        );
+        assert_eq!(
+            schedule.occurrence_after(wednesday, utc).unwrap(),
+            Some(local("2026-03-20T08:00:00Z"))
+        );
+    }
+
+    #[tokio::test]
+    async fn reminders_wait_for_quiet_hours_to_end() {
+        let journal =
+            journal(&[("quiet_hours_start", "22:00"), ("quiet_hours_end", "07:00")]).await;
+        let scheduler = ReminderScheduler::new(&journal);
+        scheduler
+            .create(&reminder(daily("23:00"), false))
+            .await
+            .unwrap();
+
+        assert!(scheduler.fire_due(at(2, "23:30")).await.unwrap().is_empty());
+        assert!(scheduler.fire_due(at(3, "06:59")).await.unwrap().is_empty());
+
+        let fired = scheduler.fire_due(at(3, "07:05")).await.unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].scheduled_for, at(2, "23:00"));
+        assert!(scheduler.fire_due(at(3, "07:10")).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reminders_missed_for_hours_are_skipped() {
+        let journal = journal(&[]).await;
+        let scheduler = ReminderScheduler::new(&journal);
+        let created = scheduler
+            .create(&reminder(daily("09:00"), false))
+            .await
+            .unwrap();
+
+        assert!(scheduler.fire_due(at(2, "13:00")).await.unwrap().is_empty());
+        let skipped = scheduler.get(&created.id).await.unwrap().unwrap();
+        assert_eq!(skipped.last_occurrence_at, Some(at(2, "09:00")));
+        assert!(scheduler.history(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn snoozed_reminders_come_back_once() {
+        let journal = journal(&[]).await;
+        let scheduler = ReminderScheduler::new(&journal);
+        let created = scheduler
+            .create(&reminder(daily("09:00"), false))
+            .await
+            .unwrap();
+        assert_eq!(scheduler.fire_due(at(2, "09:01")).await.unwrap().len(), 1);
+
+        assert!(scheduler.snooze(&created.id, 0).await.is_err());
+        let snoozed = scheduler.snooze(&created.id, 30).await.unwrap();
+        let until = snoozed.snoozed_until.unwrap();
+        assert_eq!(
+            scheduler.history(1).await.unwrap()[0].action,
+            Some(ReminderAction::Snoozed)
+        );
+
+        assert!(scheduler
+            .fire_due(until - Duration::minutes(1))
+            .await
+            .unwrap()
+            .is_empty());
+        let fired = scheduler.fire_due(until).await.unwrap();
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].snoozed);
+        assert_eq!(fired[0].scheduled_for, until);
+
+        let fired_again = scheduler
+            .fire_due(until + Duration::minutes(1))
+            .await
+            .unwrap();
+        assert!(fired_again.is_empty());
+        assert!(scheduler
+            .get(&created.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .snoozed_until
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn inactivity_reminders_wait_for_days_without_writing() {
+        let journal = journal(&[]).await;
+        let scheduler = ReminderScheduler::new(&journal);
+        let schedule = ReminderSchedule::Inactivity {
+            time: "20:00".to_string(),
+            days: 3,
+        };
+        scheduler.create(&reminder(schedule, false)).await.unwrap();
+        write_at(&journal, "written", at(2, "12:00")).await;
+
+        assert!(scheduler.fire_due(at(4, "20:05")).await.unwrap().is_empty());
+
+        // Imported posts don't count as writing
+        let mut imported = entry_at("toot", "Posted", &at(5, "10:00").to_rfc3339());
+        imported.source = Some("mastodon".to_string());
+        journal.create_entry(&imported).await.unwrap();
+
+        let fired = scheduler.fire_due(at(5, "20:05")).await.unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].message, "You haven't written in 3 days.");
+    }
+
+    #[tokio::test]
+    async fn skip_if_written_only_looks_at_the_same_day() {
+        let journal = journal(&[]).await;
+        let scheduler = ReminderScheduler::new(&journal);
+        let skipping = scheduler
+            .create(&reminder(daily("21:00"), true))
+            .await
+            .unwrap();
+        let always = scheduler
+            .create(&reminder(daily("21:00"), false))
+            .await
+            .unwrap();
+        write_at(&journal, "morning", at(2, "08:00")).await;
+
+        let fired = scheduler.fire_due(at(2, "21:01")).await.unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].reminder_id, always.id);
+
+        let fired = scheduler.fire_due(at(3, "21:01")).await.unwrap();
+        let mut ids: Vec<_> = fired.iter().map(|n| n.reminder_id.as_str()).collect();
+        ids.sort();
+        let mut expected = [skipping.id.as_str(), always.id.as_str()];
+        expected.sort();
+        assert_eq!(ids, expected);
+    }
+}
@@ -0,0 +1,509 @@
+/**
+ * Resurfacing older entries for MyFace SnapJournal
+ *
+ * This module handles:
+ * - "On this day": entries from the same calendar day in earlier years
+ * - Picking a few older entries to revisit each day
+ * - Tracking when entries were last opened and last resurfaced
+ *
+ * Archived entries never come back up, and in public privacy mode only public
+ * entries do. Picks favour entries that haven't been opened for a long time.
+ * A day's picks stay the same until tomorrow, after which they rest for the
+ * cooldown set in the settings.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::database::{format_timestamp, parse_timestamp, Database, JournalEntry};
+use crate::settings::{PrivacyMode, SettingsStore};
+use crate::storage::EntryFilter;
+
+/// Most entries that can be resurfaced at once
+pub const MAX_RESURFACED: u32 = 20;
+
+/// Entries written on the same day in one earlier year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnThisDayYear {
+    pub year: i32,
+    pub years_ago: i32,
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResurfacedEntry {
+    pub entry: JournalEntry,
+    pub view_count: i64,
+    pub last_viewed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct EntryViews {
+    view_count: i64,
+    last_viewed_at: Option<DateTime<Utc>>,
+    last_resurfaced_at: Option<DateTime<Utc>>,
+}
+
+pub struct Resurfacer<'a> {
+    database: &'a Database,
+}
+
+impl<'a> Resurfacer<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        Resurfacer { database }
+    }
+
+    /// Entries from the same month and day as `date` in earlier years, most recent year first
+    pub async fn on_this_day(&self, date: NaiveDate) -> Result<Vec<OnThisDayYear>> {
+        // Leap day entries come back on the 28th in other years
+        let with_leap_day = date.month() == 2 && date.day() == 28 && !is_leap_year(date.year());
+
+        let mut days = vec![date];
+        if with_leap_day {
+            days.extend(NaiveDate::from_ymd_opt(2000, 2, 29));
+        }
+
+        let mut years: BTreeMap<i32, Vec<JournalEntry>> = BTreeMap::new();
+        for entry in self.entries_written_around(&days).await? {
+            let written = entry.entry_date.date_naive();
+            let same_day = written.day() == date.day() || (with_leap_day && written.day() == 29);
+            if written.year() < date.year() && written.month() == date.month() && same_day {
+                years.entry(written.year()).or_default().push(entry);
+            }
+        }
+
+        Ok(years
+            .into_iter()
+            .rev()
+            .map(|(year, entries)| OnThisDayYear {
+                year,
+                years_ago: date.year() - year,
+                entries,
+            })
+            .collect())
+    }
+
+    /// A few older entries to revisit on the day of `now`
+    pub async fn resurface(&self, now: DateTime<Utc>) -> Result<Vec<ResurfacedEntry>> {
+        let settings = SettingsStore::new(self.database).get().await?;
+        if !settings.resurfacing_enabled {
+            return Ok(Vec::new());
+        }
+        let count = settings.resurfacing_count as usize;
+        let tz = settings.journal_timezone();
+        let today = tz.at(now).date_naive();
+
+        let entries = self.visible_entries().await?;
+        let views = self.views().await?;
+        let view_of = |entry: &JournalEntry| views.get(&entry.id).cloned().unwrap_or_default();
+        let resurfaced_on = |entry: &JournalEntry| {
+            view_of(entry)
+                .last_resurfaced_at
+                .map(|at| tz.at(at).date_naive())
+        };
+
+        // Keep offering today's picks so reopening the app doesn't reshuffle them
+        let mut picks: Vec<JournalEntry> = entries
+            .iter()
+            .filter(|entry| resurfaced_on(entry) == Some(today))
+            .take(count)
+            .cloned()
+            .collect();
+
+        if picks.is_empty() {
+            let written_before =
+                today - Duration::days(i64::from(settings.resurfacing_min_age_days));
+            let rested_since =
+                today - Duration::days(i64::from(settings.resurfacing_cooldown_days));
+
+            let mut candidates: Vec<(f64, &JournalEntry)> = entries
+                .iter()
+                .filter(|entry| entry.entry_date.date_naive() <= written_before)
+                .filter(|entry| match resurfaced_on(entry) {
+                    Some(date) => date <= rested_since,
+                    None => true,
+                })
+                .map(|entry| {
+                    let last_seen = view_of(entry)
+                        .last_viewed_at
+                        .map(|at| tz.at(at).date_naive())
+                        .unwrap_or_else(|| entry.entry_date.date_naive());
+                    let idle_days = (today - last_seen).num_days().max(0) + 1;
+                    (pick_key(today, &entry.id, idle_days as f64), entry)
+                })
+                .collect();
+
+            candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+            picks = candidates
+                .into_iter()
+                .take(count)
+                .map(|(_, entry)| entry.clone())
+                .collect();
+
+            self.mark_resurfaced(&picks, now).await?;
+        }
+
+        Ok(picks
+            .into_iter()
+            .map(|entry| {
+                let views = view_of(&entry);
+                ResurfacedEntry {
+                    entry,
+                    view_count: views.view_count,
+                    last_viewed_at: views.last_viewed_at,
+                }
+            })
+            .collect())
+    }
+
+    /// Note that an entry was opened
+    pub async fn record_view(&self, entry_id: &str) -> Result<()> {
+        if self.database.get_entry(entry_id).await?.is_none() {
+            return Err(anyhow::anyhow!("Entry not found: {}", entry_id));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO entry_views (entry_id, view_count, last_viewed_at) VALUES (?, 1, ?)
+            ON CONFLICT(entry_id) DO UPDATE SET
+                view_count = view_count + 1, last_viewed_at = excluded.last_viewed_at
+            "#,
+        )
+        .bind(entry_id)
+        .bind(format_timestamp(&Utc::now()))
+        .execute(self.database.pool())
+        .await
+        .context("Failed to record entry view")?;
+
+        Ok(())
+    }
+
+    /// Drop the view history of a deleted entry
+    pub async fn forget_entry(&self, entry_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM entry_views WHERE entry_id = ?")
+            .bind(entry_id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to remove entry views")?;
+
+        Ok(())
+    }
+
+    /// Unarchived entries the privacy mode lets us show unprompted
+    async fn visible_entries(&self) -> Result<Vec<JournalEntry>> {
+        let privacy_mode = SettingsStore::new(self.database).get().await?.privacy_mode;
+        let filter = EntryFilter {
+            archived: Some(false),
+            ..Default::default()
+        };
+
+        Ok(self
+            .database
+            .list_entries(None, None, &filter)
+            .await?
+            .into_iter()
+            .filter(|entry| privacy_mode != PrivacyMode::Public || entry.privacy == "public")
+            .collect())
+    }
+
+    /// Visible entries that may have been written on the month and day of one
+    /// of `days`, newest first. Entries without an offset are matched on their
+    /// UTC date and the days either side, since the journal timezone can't be
+    /// applied in SQL; callers check the day they were written.
+    async fn entries_written_around(&self, days: &[NaiveDate]) -> Result<Vec<JournalEntry>> {
+        let privacy_mode = SettingsStore::new(self.database).get().await?.privacy_mode;
+        let filter = EntryFilter {
+            archived: Some(false),
+            ..Default::default()
+        };
+
+        let month_day = |date: NaiveDate| date.format("%m-%d").to_string();
+        let exact: Vec<String> = days.iter().copied().map(month_day).collect();
+        // A leap year, so the neighbours of 29 February exist
+        let near: Vec<String> = days
+            .iter()
+            .filter_map(|day| NaiveDate::from_ymd_opt(2000, day.month(), day.day()))
+            .flat_map(|day| [day - Duration::days(1), day, day + Duration::days(1)])
+            .map(month_day)
+            .collect();
+        let placeholders = |values: &[String]| vec!["?"; values.len()].join(", ");
+
+        let sql = format!(
+            "SELECT id FROM journal_entries
+             WHERE CASE WHEN entry_utc_offset IS NULL THEN strftime('%m-%d', entry_date) IN ({})
+                   ELSE strftime('%m-%d', entry_date, printf('%+d minutes', entry_utc_offset)) IN ({}) END{}{}
+             ORDER BY entry_date DESC, created_at DESC",
+            placeholders(&near),
+            placeholders(&exact),
+            filter.conditions(),
+            if privacy_mode == PrivacyMode::Public {
+                " AND privacy = 'public'"
+            } else {
+                ""
+            }
+        );
+        let mut query = sqlx::query_scalar::<_, String>(&sql);
+        for value in near.iter().chain(&exact) {
+            query = query.bind(value);
+        }
+        let ids = query
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to find entries written on this day")?;
+
+        let mut entries = Vec::with_capacity(ids.len());
+        for id in ids {
+            entries.extend(self.database.get_entry(&id).await?);
+        }
+        Ok(entries)
+    }
+
+    async fn views(&self) -> Result<HashMap<String, EntryViews>> {
+        let rows = sqlx::query("SELECT * FROM entry_views")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to read entry views")?;
+
+        let timestamp = |value: Option<String>| value.as_deref().map(parse_timestamp).transpose();
+        let mut views = HashMap::new();
+        for row in rows {
+            views.insert(
+                row.get("entry_id"),
+                EntryViews {
+                    view_count: row.get("view_count"),
+                    last_viewed_at: timestamp(row.get("last_viewed_at"))?,
+                    last_resurfaced_at: timestamp(row.get("last_resurfaced_at"))?,
+                },
+            );
+        }
+
+        Ok(views)
+    }
+
+    async fn mark_resurfaced(&self, entries: &[JournalEntry], at: DateTime<Utc>) -> Result<()> {
+        let at = format_timestamp(&at);
+        for entry in entries {
+            sqlx::query(
+                r#"
+                INSERT INTO entry_views (entry_id, last_resurfaced_at) VALUES (?, ?)
+                ON CONFLICT(entry_id) DO UPDATE SET last_resurfaced_at = excluded.last_resurfaced_at
+                "#,
+            )
+            .bind(&entry.id)
+            .bind(&at)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to record resurfaced entry")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Weighted random key for picking without replacement: higher keys win, and
+/// entries idle for longer tend to get higher keys. The randomness comes from
+/// the day and entry id, so the same day always gives the same order.
+fn pick_key(today: NaiveDate, entry_id: &str, weight: f64) -> f64 {
+    let digest = Sha256::digest(format!("{}:{}", today, entry_id).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    let unit = (u64::from_le_bytes(bytes) as f64 + 1.0) / (u64::MAX as f64 + 2.0);
+    unit.ln() / weight
+}
+
+fn is_leap_year(year: i32) -> bool {
+    NaiveDate::from_ymd_opt(year, 2, 29).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry_at, TempJournal};
+    use std::collections::HashSet;
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    async fn journal(settings: &[(&str, serde_json::Value)]) -> TempJournal {
+        let journal = TempJournal::new().await;
+        let mut patch = serde_json::Map::new();
+        patch.insert("timezone".to_string(), "UTC".into());
+        for (key, value) in settings {
+            patch.insert(key.to_string(), value.clone());
+        }
+        SettingsStore::new(&journal).update(patch).await.unwrap();
+        journal
+    }
+
+    async fn write(journal: &TempJournal, id: &str, entry_date: &str) {
+        journal
+            .create_entry(&entry_at(id, "Dear diary", entry_date))
+            .await
+            .unwrap();
+    }
+
+    async fn on_this_day(journal: &TempJournal, date: &str) -> Vec<(i32, Vec<String>)> {
+        Resurfacer::new(journal)
+            .on_this_day(day(date))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|year| {
+                let ids = year.entries.into_iter().map(|entry| entry.id).collect();
+                (year.year, ids)
+            })
+            .collect()
+    }
+
+    fn ids(picks: &[ResurfacedEntry]) -> HashSet<String> {
+        picks.iter().map(|pick| pick.entry.id.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn leap_day_entries_come_back_on_the_28th() {
+        let journal = journal(&[]).await;
+        write(&journal, "leap", "2024-02-29T10:00:00+01:00").await;
+        write(&journal, "last-year", "2025-02-28T09:00:00Z").await;
+        write(&journal, "march", "2025-03-01T09:00:00Z").await;
+        write(&journal, "this-year", "2026-02-28T08:00:00Z").await;
+
+        assert_eq!(
+            on_this_day(&journal, "2026-02-28").await,
+            [
+                (2025, vec!["last-year".to_string()]),
+                (2024, vec!["leap".to_string()]),
+            ]
+        );
+        // Leap years have a 29th of their own
+        assert_eq!(
+            on_this_day(&journal, "2028-02-28").await,
+            [
+                (2026, vec!["this-year".to_string()]),
+                (2025, vec!["last-year".to_string()]),
+            ]
+        );
+        assert_eq!(
+            on_this_day(&journal, "2028-02-29").await,
+            [(2024, vec!["leap".to_string()])]
+        );
+    }
+
+    #[tokio::test]
+    async fn on_this_day_uses_the_day_entries_were_written() {
+        let journal = journal(&[]).await;
+        // The 15th in UTC, but written late on the 14th
+        write(&journal, "late", "2025-03-14T23:30:00-05:00").await;
+        // The 14th in UTC, but the 15th in the journal timezone
+        write(&journal, "unzoned", "2025-03-14T20:00:00Z").await;
+        sqlx::query("UPDATE journal_entries SET entry_utc_offset = NULL WHERE id = 'unzoned'")
+            .execute(journal.pool())
+            .await
+            .unwrap();
+        let mut patch = serde_json::Map::new();
+        patch.insert("timezone".to_string(), "+09:00".into());
+        SettingsStore::new(&journal).update(patch).await.unwrap();
+
+        assert_eq!(
+            on_this_day(&journal, "2026-03-14").await,
+            [(2025, vec!["late".to_string()])]
+        );
+        assert_eq!(
+            on_this_day(&journal, "2026-03-15").await,
+            [(2025, vec!["unzoned".to_string()])]
+        );
+    }
+
+    #[tokio::test]
+    async fn archived_and_hidden_entries_stay_down() {
+        let journal = journal(&[("privacy_mode", "public".into())]).await;
+        let mut shared = entry_at("shared", "Out loud", "2025-03-14T09:00:00Z");
+        shared.privacy = "public".to_string();
+        journal.create_entry(&shared).await.unwrap();
+        write(&journal, "private", "2025-03-14T10:00:00Z").await;
+        let mut archived = entry_at("archived", "Put away", "2025-03-14T11:00:00Z");
+        archived.privacy = "public".to_string();
+        archived.archived = true;
+        journal.create_entry(&archived).await.unwrap();
+
+        assert_eq!(
+            on_this_day(&journal, "2026-03-14").await,
+            [(2025, vec!["shared".to_string()])]
+        );
+    }
+
+    #[test]
+    fn pick_keys_are_fixed_for_the_day() {
+        let today = day("2026-03-14");
+        assert_eq!(pick_key(today, "a", 3.0), pick_key(today, "a", 3.0));
+        assert_ne!(pick_key(today, "a", 3.0), pick_key(today, "b", 3.0));
+        assert_ne!(
+            pick_key(today, "a", 3.0),
+            pick_key(day("2026-03-15"), "a", 3.0)
+        );
+    }
+
+    #[test]
+    fn entries_idle_for_longer_are_picked_more_often() {
+        let today = day("2026-03-14");
+        let mut keys: Vec<(f64, bool)> = (0..1000)
+            .map(|i| {
+                let idle = i % 2 == 0;
+                let weight = if idle { 10.0 } else { 1.0 };
+                (pick_key(today, &i.to_string(), weight), idle)
+            })
+            .collect();
+        keys.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let idle_picks = keys.iter().take(100).filter(|(_, idle)| *idle).count();
+        assert!(idle_picks > 80, "{} of 100 picks were idle", idle_picks);
+        // Recently seen entries still come up now and then
+        assert!(idle_picks < 100);
+    }
+
+    #[tokio::test]
+    async fn picks_hold_for_the_day_then_rest() {
+        let journal = journal(&[
+            ("resurfacing_count", 3.into()),
+            ("resurfacing_cooldown_days", 30.into()),
+        ])
+        .await;
+        for i in 0..6 {
+            write(&journal, &format!("old-{}", i), "2024-06-01T09:00:00Z").await;
+        }
+        write(&journal, "recent", "2026-03-10T09:00:00Z").await;
+        let resurfacer = Resurfacer::new(&journal);
+        let morning = DateTime::parse_from_rfc3339("2026-03-14T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let first = ids(&resurfacer.resurface(morning).await.unwrap());
+        assert_eq!(first.len(), 3);
+        assert!(!first.contains("recent"));
+        let evening = morning + Duration::hours(12);
+        assert_eq!(ids(&resurfacer.resurface(evening).await.unwrap()), first);
+
+        // Tomorrow's picks are the ones still rested
+        let second = ids(&resurfacer
+            .resurface(morning + Duration::days(1))
+            .await
+            .unwrap());
+        assert_eq!(second.len(), 3);
+        assert!(first.is_disjoint(&second));
+        assert!(resurfacer
+            .resurface(morning + Duration::days(2))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The first picks can come back once their cooldown is over
+        let later = ids(&resurfacer
+            .resurface(morning + Duration::days(30))
+            .await
+            .unwrap());
+        assert_eq!(later, first);
+    }
+}
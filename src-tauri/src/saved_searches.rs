@@ -0,0 +1,753 @@
+/**
+ * Saved searches for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Storing a name with a query and filter spec
+ * - Date ranges relative to today, such as the last 30 days
+ * - Running a saved search against the current entries
+ * - Caching entry counts for the sidebar
+ *
+ * A saved search stores what to look for, not which entries matched, so it
+ * behaves like a smart folder: running it always reflects the journal as it
+ * is now. Counts are refreshed in SQL shortly after entries stop changing
+ * and again on a new day, since relative date ranges move with the calendar.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::collections::ensure_same_members;
+use crate::database::{
+    format_timestamp, is_imported_source, parse_timestamp, Database, JournalEntry,
+};
+use crate::moods::MoodRegistry;
+use crate::settings::{JournalTimezone, SettingsStore};
+use crate::storage::EntryFilter;
+
+pub const SAVED_SEARCH_COUNTS: &str = "saved-searches://counts";
+
+/// How long entries must stay unchanged before saved searches are recounted
+pub const COUNT_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// The local source name for entries written in the app
+const LOCAL_SOURCE: &str = "local";
+
+/// Which entry dates a search covers, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DateRange {
+    /// Today and the `days - 1` days before it
+    LastDays {
+        days: u32,
+    },
+    /// Since Monday
+    ThisWeek,
+    ThisMonth,
+    ThisYear,
+    /// Fixed dates; a missing end is open
+    Between {
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    },
+}
+
+impl DateRange {
+    /// The first and last dates covered when today is `today`
+    pub fn resolve(&self, today: NaiveDate) -> (Option<NaiveDate>, Option<NaiveDate>) {
+        match *self {
+            DateRange::LastDays { days } => (
+                Some(today - Duration::days(i64::from(days.max(1)) - 1)),
+                Some(today),
+            ),
+            DateRange::ThisWeek => (
+                Some(today - Duration::days(i64::from(today.weekday().num_days_from_monday()))),
+                Some(today),
+            ),
+            DateRange::ThisMonth => (today.with_day(1), Some(today)),
+            DateRange::ThisYear => (today.with_ordinal(1), Some(today)),
+            DateRange::Between { from, to } => (from, to),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        match *self {
+            DateRange::LastDays { days: 0 } => {
+                Err(anyhow::anyhow!("A date range must cover at least one day"))
+            }
+            DateRange::Between {
+                from: Some(from),
+                to: Some(to),
+            } if from > to => Err(anyhow::anyhow!("Date range starts after it ends")),
+            _ => Ok(()),
+        }
+    }
+
+    fn contains(&self, date: NaiveDate, today: NaiveDate) -> bool {
+        let (from, to) = self.resolve(today);
+        match (from, to) {
+            (Some(from), _) if date < from => false,
+            (_, Some(to)) if date > to => false,
+            _ => true,
+        }
+    }
+}
+
+/// What a saved search looks for; empty fields don't restrict it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchSpec {
+    /// Text matched against titles, content and tags
+    pub query: String,
+    /// Entries from any of these sources; "local" means written in the app
+    pub sources: Vec<String>,
+    /// Entries with every one of these tags
+    pub tags: Vec<String>,
+    /// Entries with any of these moods
+    pub moods: Vec<String>,
+    pub date: Option<DateRange>,
+    pub filter: EntryFilter,
+}
+
+impl SearchSpec {
+    /// Whether `entry` matches everything but the query text
+    pub fn matches(&self, entry: &JournalEntry, today: NaiveDate) -> bool {
+        if !self.filter.matches(entry) {
+            return false;
+        }
+
+        if !self.sources.is_empty() {
+            let source = if entry.is_imported() {
+                entry.source.as_deref().unwrap_or_default().to_lowercase()
+            } else {
+                LOCAL_SOURCE.to_string()
+            };
+            if !self.sources.contains(&source) {
+                return false;
+            }
+        }
+
+        let tags: Vec<String> = entry.tags.iter().map(|tag| normalize_tag(tag)).collect();
+        if !self.tags.iter().all(|tag| tags.contains(tag)) {
+            return false;
+        }
+
+        if !self.moods.is_empty() && !entry.mood.as_ref().is_some_and(|m| self.moods.contains(m)) {
+            return false;
+        }
+
+        match &self.date {
+            Some(range) => range.contains(entry.entry_date.date_naive(), today),
+            None => true,
+        }
+    }
+
+    /// The same test as `matches`, plus the query text, as ` AND ...`
+    /// conditions on journal_entries with the values to bind in order.
+    /// Entries saved without an offset are dated in `timezone`.
+    fn conditions(&self, today: NaiveDate, timezone: &JournalTimezone) -> (String, Vec<String>) {
+        let mut sql = self.filter.conditions();
+        let mut values = Vec::new();
+
+        if !self.query.is_empty() {
+            sql.push_str(" AND (title LIKE ? OR content LIKE ? OR tags LIKE ?)");
+            let pattern = format!("%{}%", self.query);
+            values.extend([pattern.clone(), pattern.clone(), pattern]);
+        }
+
+        if !self.sources.is_empty() {
+            sql.push_str(&format!(
+                " AND (CASE WHEN source IS NULL OR source IN ('', '{0}') THEN '{0}' ELSE lower(source) END) IN ({1})",
+                LOCAL_SOURCE,
+                placeholders(self.sources.len())
+            ));
+            values.extend(self.sources.iter().cloned());
+        }
+
+        for tag in &self.tags {
+            sql.push_str(
+                " AND EXISTS (SELECT 1 FROM json_each(CASE WHEN json_valid(tags) THEN tags ELSE '[]' END)
+                              WHERE lower(ltrim(trim(value), '#')) = ?)",
+            );
+            values.push(tag.clone());
+        }
+
+        if !self.moods.is_empty() {
+            sql.push_str(&format!(
+                " AND mood IN ({})",
+                placeholders(self.moods.len())
+            ));
+            values.extend(self.moods.iter().cloned());
+        }
+
+        if let Some(range) = &self.date {
+            // The day an entry falls on in the offset it was written with;
+            // without one, when it was written against the journal's days
+            let on_or_after = "(CASE WHEN entry_utc_offset IS NULL THEN julianday(entry_date) >= julianday(?)
+                               ELSE date(entry_date, printf('%+d minutes', entry_utc_offset)) >= ? END)";
+            let on_or_before = "(CASE WHEN entry_utc_offset IS NULL THEN julianday(entry_date) < julianday(?)
+                                ELSE date(entry_date, printf('%+d minutes', entry_utc_offset)) <= ? END)";
+            let (from, to) = range.resolve(today);
+            if let Some(from) = from {
+                sql.push_str(&format!(" AND {}", on_or_after));
+                values.push(day_start(timezone, from));
+                values.push(from.format("%Y-%m-%d").to_string());
+            }
+            if let Some(to) = to {
+                sql.push_str(&format!(" AND {}", on_or_before));
+                values.push(day_start(timezone, to + Duration::days(1)));
+                values.push(to.format("%Y-%m-%d").to_string());
+            }
+        }
+
+        (sql, values)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub spec: SearchSpec,
+    pub position: i64,
+    /// Matching entries when last counted
+    pub entry_count: Option<i64>,
+    pub counted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchInput {
+    pub name: String,
+    #[serde(default)]
+    pub spec: SearchSpec,
+}
+
+/// Payload of the saved-searches://counts event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchCount {
+    pub id: String,
+    pub entry_count: i64,
+}
+
+pub struct SavedSearchStore<'a> {
+    database: &'a Database,
+}
+
+impl<'a> SavedSearchStore<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        SavedSearchStore { database }
+    }
+
+    /// Every saved search in sidebar order, recounting any counted before today
+    pub async fn list(&self) -> Result<Vec<SavedSearch>> {
+        let searches = self.fetch_all().await?;
+        let tz = SettingsStore::new(self.database).timezone().await?;
+        let today = tz.today();
+        let stale = searches.iter().any(|search| match search.counted_at {
+            Some(counted_at) => tz.at(counted_at).date_naive() < today,
+            None => true,
+        });
+
+        if stale {
+            self.refresh_counts().await?;
+            return self.fetch_all().await;
+        }
+
+        Ok(searches)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<SavedSearch>> {
+        let row = sqlx::query("SELECT * FROM saved_searches WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await
+            .context("Failed to fetch saved search")?;
+
+        row.as_ref().map(saved_search_from_row).transpose()
+    }
+
+    /// Save a search after the existing ones
+    pub async fn create(&self, input: &SavedSearchInput) -> Result<SavedSearch> {
+        let input = self.normalize(input).await?;
+        let id = Uuid::new_v4().to_string();
+        let now = format_timestamp(&Utc::now());
+
+        sqlx::query(
+            r#"
+            INSERT INTO saved_searches (id, name, spec, position, created_at, updated_at)
+            VALUES (?, ?, ?, (SELECT COALESCE(MAX(position) + 1, 0) FROM saved_searches), ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.name)
+        .bind(serde_json::to_string(&input.spec)?)
+        .bind(&now)
+        .bind(&now)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to create saved search")?;
+
+        self.run(&id).await?;
+        self.require(&id).await
+    }
+
+    pub async fn update(&self, id: &str, input: &SavedSearchInput) -> Result<SavedSearch> {
+        let input = self.normalize(input).await?;
+
+        let result = sqlx::query(
+            "UPDATE saved_searches SET name = ?, spec = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(&input.name)
+        .bind(serde_json::to_string(&input.spec)?)
+        .bind(format_timestamp(&Utc::now()))
+        .bind(id)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to update saved search")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Saved search not found: {}", id));
+        }
+
+        self.run(id).await?;
+        self.require(id).await
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM saved_searches WHERE id = ?")
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to delete saved search")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Saved search not found: {}", id));
+        }
+
+        Ok(())
+    }
+
+    /// Put the saved searches in the order of `ids`, which must list each one once
+    pub async fn reorder(&self, ids: &[String]) -> Result<Vec<SavedSearch>> {
+        let existing: Vec<String> = self.fetch_all().await?.into_iter().map(|s| s.id).collect();
+        ensure_same_members(&existing, ids, "saved search")?;
+
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        for (position, id) in ids.iter().enumerate() {
+            sqlx::query("UPDATE saved_searches SET position = ? WHERE id = ?")
+                .bind(position as i64)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to reorder saved searches")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit saved search order")?;
+
+        self.fetch_all().await
+    }
+
+    /// The entries matching a saved search now, newest first
+    pub async fn run(&self, id: &str) -> Result<Vec<JournalEntry>> {
+        let search = self.require(id).await?;
+        let entries = self.evaluate(&search.spec).await?;
+        self.store_count(id, entries.len() as i64).await?;
+
+        Ok(entries)
+    }
+
+    /// The entries a spec would match, for trying one out before saving it
+    pub async fn preview(&self, spec: &SearchSpec) -> Result<Vec<JournalEntry>> {
+        let spec = self.normalize_spec(spec).await?;
+        self.evaluate(&spec).await
+    }
+
+    /// Recount every saved search and return the new counts
+    pub async fn refresh_counts(&self) -> Result<Vec<SavedSearchCount>> {
+        let today = SettingsStore::new(self.database).timezone().await?.today();
+
+        let mut counts = Vec::new();
+        for search in self.fetch_all().await? {
+            let entry_count = self.count(&search.spec, today).await?;
+            self.store_count(&search.id, entry_count).await?;
+            counts.push(SavedSearchCount {
+                id: search.id,
+                entry_count,
+            });
+        }
+
+        Ok(counts)
+    }
+
+    /// How many entries match `spec`, counted without loading them
+    async fn count(&self, spec: &SearchSpec, today: NaiveDate) -> Result<i64> {
+        let (conditions, values) = spec.conditions(today, &self.database.timezone());
+        let sql = format!(
+            "SELECT COUNT(*) FROM journal_entries WHERE 1 = 1{}",
+            conditions
+        );
+
+        let mut query = sqlx::query_scalar(&sql);
+        for value in values {
+            query = query.bind(value);
+        }
+
+        query
+            .fetch_one(self.database.pool())
+            .await
+            .context("Failed to count saved search")
+    }
+
+    /// The entries matching `spec`, newest first
+    async fn evaluate(&self, spec: &SearchSpec) -> Result<Vec<JournalEntry>> {
+        let today = SettingsStore::new(self.database).timezone().await?.today();
+
+        let candidates = if spec.query.is_empty() {
+            self.database
+                .list_entries(Some(-1), None, &spec.filter)
+                .await?
+        } else {
+            self.database
+                .search_entries(&spec.query, &spec.filter)
+                .await?
+        };
+
+        Ok(candidates
+            .into_iter()
+            .filter(|entry| spec.matches(entry, today))
+            .collect())
+    }
+
+    async fn store_count(&self, id: &str, entry_count: i64) -> Result<()> {
+        sqlx::query("UPDATE saved_searches SET entry_count = ?, counted_at = ? WHERE id = ?")
+            .bind(entry_count)
+            .bind(format_timestamp(&Utc::now()))
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to store saved search count")?;
+
+        Ok(())
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<SavedSearch>> {
+        let rows =
+            sqlx::query("SELECT * FROM saved_searches ORDER BY position, name COLLATE NOCASE")
+                .fetch_all(self.database.pool())
+                .await
+                .context("Failed to list saved searches")?;
+
+        rows.iter().map(saved_search_from_row).collect()
+    }
+
+    async fn require(&self, id: &str) -> Result<SavedSearch> {
+        self.get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Saved search not found: {}", id))
+    }
+
+    async fn normalize(&self, input: &SavedSearchInput) -> Result<SavedSearchInput> {
+        let name = input.name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Saved search name cannot be empty"));
+        }
+
+        Ok(SavedSearchInput {
+            name: name.to_string(),
+            spec: self.normalize_spec(&input.spec).await?,
+        })
+    }
+
+    /// Trim and deduplicate the spec and resolve its moods to registry ids
+    async fn normalize_spec(&self, spec: &SearchSpec) -> Result<SearchSpec> {
+        if let Some(range) = &spec.date {
+            range.validate()?;
+        }
+
+        let registry = MoodRegistry::new(self.database);
+        let mut moods = Vec::new();
+        for raw in &spec.moods {
+            if raw.trim().is_empty() {
+                continue;
+            }
+            let id = registry
+                .resolve(raw)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Unknown mood: {}", raw.trim()))?;
+            moods.push(id);
+        }
+
+        Ok(SearchSpec {
+            query: spec.query.trim().to_string(),
+            sources: dedup(spec.sources.iter().map(|source| {
+                let source = source.trim().to_lowercase();
+                if is_imported_source(Some(&source)) {
+                    source
+                } else {
+                    LOCAL_SOURCE.to_string()
+                }
+            })),
+            tags: dedup(
+                spec.tags
+                    .iter()
+                    .map(|tag| normalize_tag(tag))
+                    .filter(|tag| !tag.is_empty()),
+            ),
+            moods: dedup(moods.into_iter()),
+            date: spec.date,
+            filter: spec.filter,
+        })
+    }
+}
+
+/// `count` comma-separated SQL placeholders
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+/// When `day` begins in `timezone`, formatted like stored timestamps
+fn day_start(timezone: &JournalTimezone, day: NaiveDate) -> String {
+    // A DST change can skip midnight, and the day then begins an hour later
+    let start = (0..2)
+        .find_map(|hour| timezone.from_local(day.and_hms_opt(hour, 0, 0)?))
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| day.and_time(NaiveTime::MIN).and_utc());
+    format_timestamp(&start)
+}
+
+/// Tags compare without case or a leading #
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+fn dedup(values: impl Iterator<Item = String>) -> Vec<String> {
+    let mut unique = Vec::new();
+    for value in values {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    unique
+}
+
+fn saved_search_from_row(row: &SqliteRow) -> Result<SavedSearch> {
+    let spec: String = row.get("spec");
+    let counted_at: Option<String> = row.get("counted_at");
+
+    Ok(SavedSearch {
+        id: row.get("id"),
+        name: row.get("name"),
+        spec: serde_json::from_str(&spec).context("Invalid saved search spec")?,
+        position: row.get("position"),
+        entry_count: row.get("entry_count"),
+        counted_at: counted_at.as_deref().map(parse_timestamp).transpose()?,
+        created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
+        updated_at: parse_timestamp(&row.get::<String, _>("updated_at"))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, TempJournal};
+
+    fn entry(id: &str, tags: &[&str], source: Option<&str>, entry_date: &str) -> JournalEntry {
+        JournalEntry {
+            title: format!("Entry {}", id),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            source: source.map(str::to_string),
+            ..test_support::entry_at(id, "A walk by the river", entry_date)
+        }
+    }
+
+    async fn journal() -> TempJournal {
+        let database = TempJournal::new().await;
+
+        let mut pinned = entry("pinned", &["Work"], None, "2026-03-10T09:00:00+00:00");
+        pinned.pinned = true;
+        for entry in [
+            pinned,
+            entry(
+                "imported",
+                &["#work"],
+                Some("Mastodon"),
+                "2026-03-12T18:00:00+00:00",
+            ),
+            entry(
+                "local",
+                &["travel"],
+                Some("local"),
+                "2026-02-20T12:00:00+00:00",
+            ),
+            // Still the 14th where it was written, though the 15th in UTC
+            entry(
+                "late",
+                &["work", "travel"],
+                None,
+                "2026-03-14T23:30:00-05:00",
+            ),
+        ] {
+            database.create_entry(&entry).await.unwrap();
+        }
+
+        database
+    }
+
+    #[tokio::test]
+    async fn sql_counts_agree_with_matching() {
+        let database = journal().await;
+        let store = SavedSearchStore::new(&database);
+        let today = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+
+        let specs = [
+            (SearchSpec::default(), 4),
+            (
+                SearchSpec {
+                    tags: vec!["work".to_string()],
+                    ..Default::default()
+                },
+                3,
+            ),
+            (
+                SearchSpec {
+                    tags: vec!["work".to_string(), "travel".to_string()],
+                    ..Default::default()
+                },
+                1,
+            ),
+            (
+                SearchSpec {
+                    sources: vec![LOCAL_SOURCE.to_string()],
+                    ..Default::default()
+                },
+                3,
+            ),
+            (
+                SearchSpec {
+                    sources: vec!["mastodon".to_string()],
+                    ..Default::default()
+                },
+                1,
+            ),
+            (
+                SearchSpec {
+                    date: Some(DateRange::LastDays { days: 5 }),
+                    ..Default::default()
+                },
+                3,
+            ),
+            (
+                SearchSpec {
+                    date: Some(DateRange::Between {
+                        from: None,
+                        to: NaiveDate::from_ymd_opt(2026, 3, 14),
+                    }),
+                    filter: EntryFilter {
+                        pinned: Some(false),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                3,
+            ),
+            (
+                SearchSpec {
+                    query: "river".to_string(),
+                    date: Some(DateRange::ThisMonth),
+                    ..Default::default()
+                },
+                3,
+            ),
+        ];
+
+        let entries = database.list_all_entries().await.unwrap();
+        for (spec, expected) in specs {
+            let matching = entries
+                .iter()
+                .filter(|entry| spec.matches(entry, today))
+                .count() as i64;
+            assert_eq!(matching, expected, "{:?}", spec);
+            assert_eq!(
+                store.count(&spec, today).await.unwrap(),
+                expected,
+                "{:?}",
+                spec
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn entries_without_an_offset_use_the_journal_timezone() {
+        let database = journal().await;
+        let store = SavedSearchStore::new(&database);
+        let mut patch = serde_json::Map::new();
+        patch.insert("timezone".to_string(), "+09:00".into());
+        SettingsStore::new(&database).update(patch).await.unwrap();
+        sqlx::query("UPDATE journal_entries SET entry_utc_offset = NULL WHERE id = 'late'")
+            .execute(database.pool())
+            .await
+            .unwrap();
+
+        // 04:30 UTC on the 15th is the middle of the day in Tokyo
+        let late = database.get_entry("late").await.unwrap().unwrap();
+        assert_eq!(late.entry_date.to_rfc3339(), "2026-03-15T13:30:00+09:00");
+
+        let today = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let day = |day: u32| SearchSpec {
+            date: Some(DateRange::Between {
+                from: NaiveDate::from_ymd_opt(2026, 3, day),
+                to: NaiveDate::from_ymd_opt(2026, 3, day),
+            }),
+            ..Default::default()
+        };
+        let entries = database.list_all_entries().await.unwrap();
+        for (spec, expected) in [(day(14), 0), (day(15), 1)] {
+            let matching = entries
+                .iter()
+                .filter(|entry| spec.matches(entry, today))
+                .count() as i64;
+            assert_eq!(matching, expected, "{:?}", spec);
+            assert_eq!(store.count(&spec, today).await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn reorder_lists_every_search_once() {
+        let database = journal().await;
+        let store = SavedSearchStore::new(&database);
+        let input = |name: &str| SavedSearchInput {
+            name: name.to_string(),
+            spec: SearchSpec::default(),
+        };
+        let first = store.create(&input("First")).await.unwrap();
+        let second = store.create(&input("Second")).await.unwrap();
+
+        let reordered = store
+            .reorder(&[second.id.clone(), first.id.clone()])
+            .await
+            .unwrap();
+        assert_eq!(reordered[0].id, second.id);
+        assert_eq!(reordered[0].entry_count, Some(4));
+
+        assert!(store
+            .reorder(std::slice::from_ref(&second.id))
+            .await
+            .is_err());
+        assert!(store
+            .reorder(&[second.id.clone(), second.id.clone()])
+            .await
+            .is_err());
+    }
+}
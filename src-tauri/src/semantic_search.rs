@@ -0,0 +1,318 @@
+/**
+ * Semantic search for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Ranking entries by how close their embeddings are to a query's
+ * - Keeping the journal's embeddings in memory between searches
+ *
+ * Vectors are normalized when they are loaded, so cosine similarity is a
+ * plain dot product over one flat array. The array is reloaded only when the
+ * stored embeddings for the model change.
+ */
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+
+use crate::database::{decode_embedding, Database, JournalEntry};
+use crate::storage::EntryFilter;
+
+pub const DEFAULT_RESULTS: usize = 10;
+
+/// Most results one search can return
+pub const MAX_RESULTS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticSearchResult {
+    pub entry: JournalEntry,
+    /// Cosine similarity to the query, from -1 to 1
+    pub score: f32,
+}
+
+/// Normalized embeddings of one model, laid out end to end
+#[derive(Debug)]
+pub struct VectorIndex {
+    model: String,
+    /// Embedding count and newest embedding when loaded, to notice changes
+    version: (i64, Option<String>),
+    dimension: usize,
+    entry_ids: Vec<String>,
+    vectors: Vec<f32>,
+}
+
+pub struct SemanticSearch<'a> {
+    database: &'a Database,
+}
+
+impl<'a> SemanticSearch<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        SemanticSearch { database }
+    }
+
+    /// The `k` entries matching `filter` whose `model` embeddings are closest to `query`, best first
+    pub async fn search(
+        &self,
+        model: &str,
+        query: &[f32],
+        k: usize,
+        filter: &EntryFilter,
+    ) -> Result<Vec<SemanticSearchResult>> {
+        if k == 0 || k > MAX_RESULTS {
+            return Err(anyhow::anyhow!(
+                "Results must be between 1 and {}",
+                MAX_RESULTS
+            ));
+        }
+        let Some(query) = normalized(query) else {
+            return Err(anyhow::anyhow!("The query embedding is empty"));
+        };
+
+        let index = self.vectors(model).await?;
+        if index.entry_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        if query.len() != index.dimension {
+            return Err(anyhow::anyhow!(
+                "The query has {} dimensions but entries were embedded with {}; reindex embeddings",
+                query.len(),
+                index.dimension
+            ));
+        }
+
+        let mut ranked: Vec<(f32, usize)> = index
+            .vectors
+            .chunks_exact(index.dimension)
+            .map(|vector| dot(&query, vector))
+            .zip(0..)
+            .collect();
+        ranked.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut results = Vec::with_capacity(k);
+        for (score, position) in ranked {
+            if results.len() == k {
+                break;
+            }
+
+            let Some(entry) = self.database.get_entry(&index.entry_ids[position]).await? else {
+                continue;
+            };
+            if filter.matches(&entry) {
+                results.push(SemanticSearchResult { entry, score });
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// The embeddings of `model`, loaded again if they changed since the last search
+    async fn vectors(&self, model: &str) -> Result<Arc<VectorIndex>> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS count, MAX(created_at) AS newest FROM embeddings WHERE model_name = ?",
+        )
+        .bind(model)
+        .fetch_one(self.database.pool())
+        .await
+        .context("Failed to read embeddings")?;
+        let version = (row.get("count"), row.get("newest"));
+
+        if let Some(index) = self.database.cached_vectors() {
+            if index.model == model && index.version == version {
+                return Ok(index);
+            }
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT entry_id, dimension, embedding_data FROM embeddings
+            WHERE model_name = ? AND entry_id IN (SELECT id FROM journal_entries)
+            "#,
+        )
+        .bind(model)
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to load embeddings")?;
+
+        // Embeddings of another size were made by a different model behind the same name
+        let dimension = rows
+            .first()
+            .map(|row| row.get::<i64, _>("dimension") as usize)
+            .unwrap_or_default();
+        let mut index = VectorIndex {
+            model: model.to_string(),
+            version,
+            dimension,
+            entry_ids: Vec::with_capacity(rows.len()),
+            vectors: Vec::with_capacity(rows.len() * dimension),
+        };
+        for row in rows {
+            let vector = decode_embedding(&row.get::<Vec<u8>, _>("embedding_data"));
+            if vector.len() != dimension {
+                continue;
+            }
+            if let Some(vector) = normalized(&vector) {
+                index.entry_ids.push(row.get("entry_id"));
+                index.vectors.extend(vector);
+            }
+        }
+
+        let index = Arc::new(index);
+        self.database.cache_vectors(index.clone());
+        Ok(index)
+    }
+}
+
+/// `vector` scaled to unit length; `None` if it is empty or all zeros
+fn normalized(vector: &[f32]) -> Option<Vec<f32>> {
+    let norm = dot(vector, vector).sqrt();
+    if vector.is_empty() || norm == 0.0 || !norm.is_finite() {
+        return None;
+    }
+    Some(vector.iter().map(|value| value / norm).collect())
+}
+
+/// Dot product over eight independent lanes, which the compiler turns into SIMD
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    const LANES: usize = 8;
+
+    let a_chunks = a.chunks_exact(LANES);
+    let b_chunks = b.chunks_exact(LANES);
+    let tail: f32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(x, y)| x * y)
+        .sum();
+
+    let mut sums = [0.0f32; LANES];
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for lane in 0..LANES {
+            sums[lane] += x[lane] * y[lane];
+        }
+    }
+
+    sums.iter().sum::<f32>() + tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Embedding;
+    use crate::test_support::{entry, TempJournal};
+    use chrono::Utc;
+
+    const MODEL: &str = "nomic-embed-text";
+
+    async fn embed(journal: &TempJournal, id: &str, vector: &[f32]) {
+        journal
+            .store_embedding(&Embedding {
+                entry_id: id.to_string(),
+                model_name: MODEL.to_string(),
+                content_hash: id.to_string(),
+                embedding_vector: vector.to_vec(),
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+    }
+
+    async fn search(
+        journal: &TempJournal,
+        query: &[f32],
+        k: usize,
+        filter: &EntryFilter,
+    ) -> Vec<String> {
+        SemanticSearch::new(journal)
+            .search(MODEL, query, k, filter)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|result| result.entry.id)
+            .collect()
+    }
+
+    #[test]
+    fn dot_products_include_the_tail() {
+        // Not a multiple of the lane count
+        let a: Vec<f32> = (1..=11).map(|i| i as f32).collect();
+        let b: Vec<f32> = (1..=11).map(|i| (12 - i) as f32).collect();
+        let expected: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+        assert_eq!(dot(&a, &b), expected);
+        assert_eq!(dot(&a[..3], &b[..3]), 11.0 + 20.0 + 27.0);
+        assert_eq!(dot(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn normalized_vectors_have_unit_length() {
+        let unit = normalized(&[3.0, 4.0]).unwrap();
+        assert_eq!(unit, [0.6, 0.8]);
+        assert!((dot(&unit, &unit) - 1.0).abs() < 1e-6);
+
+        assert!(normalized(&[]).is_none());
+        assert!(normalized(&[0.0, 0.0]).is_none());
+        assert!(normalized(&[f32::NAN, 1.0]).is_none());
+        assert!(normalized(&[f32::INFINITY, 1.0]).is_none());
+    }
+
+    #[tokio::test]
+    async fn results_are_ranked_by_similarity() {
+        let journal = TempJournal::new().await;
+        for id in ["north", "north-east", "east", "south"] {
+            journal.create_entry(&entry(id, id)).await.unwrap();
+        }
+        embed(&journal, "north", &[0.0, 2.0]).await;
+        embed(&journal, "north-east", &[1.0, 1.0]).await;
+        embed(&journal, "east", &[3.0, 0.0]).await;
+        embed(&journal, "south", &[0.0, -1.0]).await;
+
+        let all = EntryFilter::default();
+        assert_eq!(
+            search(&journal, &[0.1, 1.0], 4, &all).await,
+            ["north", "north-east", "east", "south"]
+        );
+        assert_eq!(
+            search(&journal, &[1.0, 0.0], 2, &all).await,
+            ["east", "north-east"]
+        );
+
+        let results = SemanticSearch::new(&journal)
+            .search(MODEL, &[0.0, 5.0], 1, &all)
+            .await
+            .unwrap();
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+
+        let error = SemanticSearch::new(&journal)
+            .search(MODEL, &[1.0, 0.0, 0.0], 1, &all)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("reindex"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn filtered_searches_skip_entries_that_dont_match() {
+        let journal = TempJournal::new().await;
+        for (id, favorite) in [
+            ("close", false),
+            ("closer", false),
+            ("far", true),
+            ("farther", true),
+        ] {
+            let mut written = entry(id, id);
+            written.favorite = favorite;
+            journal.create_entry(&written).await.unwrap();
+        }
+        embed(&journal, "closer", &[1.0, 0.0]).await;
+        embed(&journal, "close", &[1.0, 0.2]).await;
+        embed(&journal, "far", &[0.0, 1.0]).await;
+        embed(&journal, "farther", &[-1.0, 0.0]).await;
+
+        let favorites = EntryFilter {
+            favorite: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            search(&journal, &[1.0, 0.0], 2, &favorites).await,
+            ["far", "farther"]
+        );
+        assert_eq!(search(&journal, &[1.0, 0.0], 1, &favorites).await, ["far"]);
+    }
+}
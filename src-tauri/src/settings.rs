@@ -0,0 +1,428 @@
+/**
+ * Application settings for MyFace SnapJournal
+ *
+ * This module handles:
+ * - The typed Settings struct and its defaults
+ * - Reading and writing settings as rows in app_settings
+ * - Validating partial updates before anything is saved
+ *
+ * Each field is stored under its own key so the rows seeded by the initial
+ * migration (theme, auto_save, privacy_mode) are picked up as they are.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::database::{format_timestamp, Database};
+use crate::reminders;
+use crate::resurfacing;
+
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const DEFAULT_LLAMA_SERVER_URL: &str = "http://localhost:8080";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    System,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyMode {
+    Public,
+    Private,
+    Secret,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AiBackend {
+    #[serde(rename = "ollama")]
+    Ollama,
+    #[serde(rename = "llama.cpp")]
+    LlamaCpp,
+}
+
+impl AiBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AiBackend::Ollama => "ollama",
+            AiBackend::LlamaCpp => "llama.cpp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupSchedule {
+    Off,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub theme: Theme,
+    pub auto_save: bool,
+    pub privacy_mode: PrivacyMode,
+    pub ai_backend: AiBackend,
+    /// Path to the llama.cpp binary when `ai_backend` is llama.cpp
+    pub llama_cpp_path: Option<String>,
+    /// llama-server used for embeddings when `ai_backend` is llama.cpp
+    pub llama_server_url: String,
+    pub chat_model: String,
+    pub embedding_model: String,
+    pub ollama_url: String,
+    pub backup_schedule: BackupSchedule,
+    /// Number of backups to keep before the oldest is removed
+    pub backup_retention: u32,
+    /// IANA name or UTC offset; `None` follows the system timezone
+    pub timezone: Option<String>,
+    /// Folder shared between devices (e.g. with Syncthing) to sync entries through
+    pub sync_folder: Option<String>,
+    /// No reminders between these local times ("HH:MM"); the range may span midnight
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    /// Offer a few older entries to revisit each day
+    pub resurfacing_enabled: bool,
+    /// How many entries to offer
+    pub resurfacing_count: u32,
+    /// Only entries at least this many days old are offered
+    pub resurfacing_min_age_days: u32,
+    /// Days before an offered entry can be offered again
+    pub resurfacing_cooldown_days: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: Theme::Light,
+            auto_save: true,
+            privacy_mode: PrivacyMode::Private,
+            ai_backend: AiBackend::Ollama,
+            llama_cpp_path: None,
+            llama_server_url: DEFAULT_LLAMA_SERVER_URL.to_string(),
+            chat_model: "llama3.2".to_string(),
+            embedding_model: "nomic-embed-text".to_string(),
+            ollama_url: std::env::var("OLLAMA_URL")
+                .unwrap_or_else(|_| DEFAULT_OLLAMA_URL.to_string()),
+            backup_schedule: BackupSchedule::Weekly,
+            backup_retention: 10,
+            timezone: None,
+            sync_folder: None,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            resurfacing_enabled: true,
+            resurfacing_count: 3,
+            resurfacing_min_age_days: 30,
+            resurfacing_cooldown_days: 30,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<()> {
+        if self.chat_model.trim().is_empty() || self.embedding_model.trim().is_empty() {
+            return Err(anyhow::anyhow!("Model names cannot be empty"));
+        }
+
+        validate_http_url(&self.ollama_url, "Ollama")?;
+        validate_http_url(&self.llama_server_url, "llama-server")?;
+
+        if self.ai_backend == AiBackend::LlamaCpp
+            && !matches!(self.llama_cpp_path.as_deref(), Some(p) if !p.trim().is_empty())
+        {
+            return Err(anyhow::anyhow!("llama.cpp needs the path to its binary"));
+        }
+
+        if self.backup_retention == 0 {
+            return Err(anyhow::anyhow!("Keep at least one backup"));
+        }
+
+        if let Some(timezone) = &self.timezone {
+            JournalTimezone::parse(timezone)?;
+        }
+
+        if let Some(folder) = &self.sync_folder {
+            if !std::path::Path::new(folder).is_absolute() {
+                return Err(anyhow::anyhow!("Sync folder must be an absolute path"));
+            }
+        }
+
+        match (&self.quiet_hours_start, &self.quiet_hours_end) {
+            (Some(start), Some(end)) => {
+                if reminders::parse_time(start)? == reminders::parse_time(end)? {
+                    return Err(anyhow::anyhow!(
+                        "Quiet hours must start and end at different times"
+                    ));
+                }
+            }
+            (None, None) => {}
+            _ => return Err(anyhow::anyhow!("Set both the start and end of quiet hours")),
+        }
+
+        if !(1..=resurfacing::MAX_RESURFACED).contains(&self.resurfacing_count) {
+            return Err(anyhow::anyhow!(
+                "Resurface between 1 and {} entries",
+                resurfacing::MAX_RESURFACED
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The timezone days are counted in; a value that no longer parses falls back to the system's
+    pub fn journal_timezone(&self) -> JournalTimezone {
+        match self.timezone.as_deref().map(JournalTimezone::parse) {
+            Some(Ok(timezone)) => timezone,
+            Some(Err(e)) => {
+                eprintln!("Ignoring timezone setting: {}", e);
+                JournalTimezone::System
+            }
+            None => JournalTimezone::System,
+        }
+    }
+}
+
+/// The timezone that decides which day it is for reminders, goals and new entries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JournalTimezone {
+    System,
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl JournalTimezone {
+    /// Accept "UTC", a fixed offset like "+02:00", or an IANA name like "Europe/Paris"
+    pub fn parse(timezone: &str) -> Result<Self> {
+        if let Some(offset) = timezone.strip_prefix(['+', '-']) {
+            let sign = if timezone.starts_with('-') { -1 } else { 1 };
+            if let Some((hours, minutes)) = offset.split_once(':') {
+                let hours: Option<i32> = hours.parse().ok().filter(|_| hours.len() == 2);
+                let minutes: Option<i32> = minutes.parse().ok().filter(|_| minutes.len() == 2);
+                if let (Some(hours), Some(minutes)) = (hours, minutes) {
+                    if hours <= 14 && minutes < 60 {
+                        if let Some(offset) =
+                            FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+                        {
+                            return Ok(JournalTimezone::Fixed(offset));
+                        }
+                    }
+                }
+            }
+            return Err(anyhow::anyhow!("Invalid UTC offset: {}", timezone));
+        }
+
+        timezone
+            .parse::<Tz>()
+            .map(JournalTimezone::Named)
+            .map_err(|_| anyhow::anyhow!("Unknown timezone: {}", timezone))
+    }
+
+    /// `at` as seen in this timezone
+    pub fn at(&self, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            JournalTimezone::System => at.with_timezone(&Local).fixed_offset(),
+            JournalTimezone::Fixed(offset) => at.with_timezone(offset),
+            JournalTimezone::Named(tz) => at.with_timezone(tz).fixed_offset(),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.at(Utc::now())
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    /// A wall-clock time in this timezone; `None` for a time skipped by a DST change
+    pub fn from_local(&self, local: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            JournalTimezone::System => Local
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.fixed_offset()),
+            JournalTimezone::Fixed(offset) => offset.from_local_datetime(&local).earliest(),
+            JournalTimezone::Named(tz) => tz
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.fixed_offset()),
+        }
+    }
+}
+
+pub struct SettingsStore<'a> {
+    database: &'a Database,
+}
+
+impl<'a> SettingsStore<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        SettingsStore { database }
+    }
+
+    /// Current settings, with defaults for anything never saved or no longer valid
+    pub async fn get(&self) -> Result<Settings> {
+        let defaults = to_fields(&Settings::default())?;
+        let mut fields = defaults.clone();
+
+        for (key, default) in &defaults {
+            let Some(stored) = self.database.get_setting(key).await? else {
+                continue;
+            };
+
+            // Strings are stored as-is, everything else as JSON
+            let value = match default {
+                Value::String(_) => Value::String(stored.clone()),
+                _ => serde_json::from_str(&stored).unwrap_or(Value::String(stored.clone())),
+            };
+
+            // Check each field on its own so one bad row doesn't reset the rest
+            let mut candidate = defaults.clone();
+            candidate.insert(key.clone(), value.clone());
+            if serde_json::from_value::<Settings>(Value::Object(candidate)).is_ok() {
+                fields.insert(key.clone(), value);
+            } else {
+                eprintln!("Ignoring invalid value for setting {}: {}", key, stored);
+            }
+        }
+
+        serde_json::from_value(Value::Object(fields)).context("Invalid stored settings")
+    }
+
+    /// The timezone from the current settings
+    pub async fn timezone(&self) -> Result<JournalTimezone> {
+        Ok(self.get().await?.journal_timezone())
+    }
+
+    /// Apply a partial update; nothing is saved unless the result is valid
+    pub async fn update(&self, patch: Map<String, Value>) -> Result<Settings> {
+        let mut fields = to_fields(&self.get().await?)?;
+
+        for (key, value) in patch {
+            if !fields.contains_key(&key) {
+                return Err(anyhow::anyhow!("Unknown setting: {}", key));
+            }
+            fields.insert(key, value);
+        }
+
+        let settings: Settings =
+            serde_json::from_value(Value::Object(fields)).context("Invalid settings")?;
+        settings.validate()?;
+        self.save(&settings).await?;
+
+        Ok(settings)
+    }
+
+    /// Restore every setting to its default
+    pub async fn reset(&self) -> Result<Settings> {
+        let settings = Settings::default();
+        self.save(&settings).await?;
+        Ok(settings)
+    }
+
+    async fn save(&self, settings: &Settings) -> Result<()> {
+        let mut tx = self
+            .database
+            .pool()
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        let updated_at = format_timestamp(&Utc::now());
+        for (key, value) in to_fields(settings)? {
+            let value = match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+
+            sqlx::query(
+                r#"
+                INSERT INTO app_settings (key, value, updated_at) VALUES (?, ?, ?)
+                ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(&key)
+            .bind(&value)
+            .bind(&updated_at)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to save setting {}", key))?;
+        }
+
+        tx.commit().await.context("Failed to commit settings")?;
+
+        // Days of entries saved without an offset move with the timezone
+        let timezone = settings.journal_timezone();
+        if timezone != self.database.timezone() {
+            self.database.set_timezone(timezone);
+            self.database.rebuild_statistics().await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn to_fields(settings: &Settings) -> Result<Map<String, Value>> {
+    match serde_json::to_value(settings)? {
+        Value::Object(fields) => Ok(fields),
+        _ => Err(anyhow::anyhow!("Settings must serialize to an object")),
+    }
+}
+
+fn validate_http_url(value: &str, service: &str) -> Result<()> {
+    let url = reqwest::Url::parse(value)
+        .with_context(|| format!("Invalid {} URL: {}", service, value))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow::anyhow!("{} URL must use http or https", service));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timezone_names_must_exist() {
+        assert!(matches!(
+            JournalTimezone::parse("Europe/Paris"),
+            Ok(JournalTimezone::Named(_))
+        ));
+        assert!(matches!(
+            JournalTimezone::parse("UTC"),
+            Ok(JournalTimezone::Named(_))
+        ));
+        assert!(JournalTimezone::parse("Europe/Atlantis").is_err());
+        assert!(JournalTimezone::parse("a/b").is_err());
+        assert!(JournalTimezone::parse("+15:00").is_err());
+        assert!(JournalTimezone::parse("+5:30").is_err());
+    }
+
+    #[test]
+    fn days_are_counted_in_the_journal_timezone() {
+        let at = "2026-03-01T23:30:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let tokyo = JournalTimezone::parse("Asia/Tokyo").unwrap();
+        assert_eq!(tokyo.at(at).date_naive().to_string(), "2026-03-02");
+
+        let offset = JournalTimezone::parse("-05:00").unwrap();
+        assert_eq!(offset.at(at).date_naive().to_string(), "2026-03-01");
+        assert_eq!(offset.at(at).offset().local_minus_utc(), -5 * 3600);
+    }
+
+    #[test]
+    fn skipped_local_times_have_no_instant() {
+        let paris = JournalTimezone::parse("Europe/Paris").unwrap();
+        let skipped = "2026-03-29T02:30:00".parse::<NaiveDateTime>().unwrap();
+        assert_eq!(paris.from_local(skipped), None);
+
+        let summer = "2026-07-01T09:00:00".parse::<NaiveDateTime>().unwrap();
+        let at = paris.from_local(summer).unwrap();
+        assert_eq!(at.offset().local_minus_utc(), 2 * 3600);
+    }
+}
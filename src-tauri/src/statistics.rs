@@ -0,0 +1,553 @@
+/**
+ * Statistics engine for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Daily per-source aggregates stored in journal_statistics
+ * - Incremental updates as entries are created, updated and deleted
+ * - Range queries rolled up by day, week, month or year
+ */
+use anyhow::{Context, Result};
+use chrono::{Datelike, Duration, NaiveDate, Timelike};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::database::JournalEntry;
+
+/// Source recorded for entries written in the app itself
+pub const LOCAL_SOURCE: &str = "local";
+
+// stat_type is "<metric>:<source>", or "<metric>:<key>:<source>" for moods and
+// hours, with any ':' or '%' in a key or source percent-escaped
+const ENTRIES: &str = "entries";
+const WORDS: &str = "words";
+const CHARACTERS: &str = "characters";
+const MOOD: &str = "mood";
+const HOUR: &str = "hour";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Granularity {
+    /// First day of the period containing `date` (weeks start on Monday)
+    pub fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            Granularity::Month => date.with_day(1).unwrap_or(date),
+            Granularity::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatisticsRange {
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+impl StatisticsRange {
+    /// Inclusive `stat_date` bounds, open ends mapped to sentinels
+    fn bounds(&self) -> (String, String) {
+        let start = self
+            .start
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "0000-01-01".to_string());
+        let end = self
+            .end
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "9999-12-31".to_string());
+        (start, end)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DailyTotals {
+    pub entries: i64,
+    pub words: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceStatistics {
+    pub entries: i64,
+    pub words: i64,
+    pub characters: i64,
+    pub average_words: f64,
+    pub average_characters: f64,
+    pub moods: BTreeMap<String, i64>,
+    /// Entries started in each local hour of the day
+    pub active_hours: [i64; 24],
+}
+
+impl SourceStatistics {
+    fn add(&mut self, metric: &str, key: Option<&str>, value: i64) {
+        match (metric, key) {
+            (ENTRIES, _) => self.entries += value,
+            (WORDS, _) => self.words += value,
+            (CHARACTERS, _) => self.characters += value,
+            (MOOD, Some(mood)) => *self.moods.entry(mood.to_string()).or_insert(0) += value,
+            (HOUR, Some(hour)) => {
+                if let Some(slot) = hour
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|h| self.active_hours.get_mut(h))
+                {
+                    *slot += value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.entries > 0 {
+            self.average_words = self.words as f64 / self.entries as f64;
+            self.average_characters = self.characters as f64 / self.entries as f64;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatisticsBucket {
+    pub period_start: NaiveDate,
+    pub totals: SourceStatistics,
+    pub sources: BTreeMap<String, SourceStatistics>,
+}
+
+pub struct StatisticsEngine<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> StatisticsEngine<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        StatisticsEngine { pool }
+    }
+
+    /// Add a new entry to the daily aggregates
+    pub async fn record_entry(&self, entry: &JournalEntry) -> Result<()> {
+        self.apply(entry, 1.0).await
+    }
+
+    /// Remove a deleted entry from the daily aggregates
+    pub async fn remove_entry(&self, entry: &JournalEntry) -> Result<()> {
+        self.apply(entry, -1.0).await
+    }
+
+    /// Replace the contribution of an entry that was edited
+    pub async fn update_entry(&self, previous: &JournalEntry, entry: &JournalEntry) -> Result<()> {
+        self.apply(previous, -1.0).await?;
+        self.apply(entry, 1.0).await
+    }
+
+    pub async fn is_empty(&self) -> Result<bool> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM journal_statistics")
+            .fetch_one(self.pool)
+            .await
+            .context("Failed to count journal statistics")?;
+
+        Ok(count == 0)
+    }
+
+    /// Recompute every aggregate from scratch
+    pub async fn rebuild(&self, entries: &[JournalEntry]) -> Result<()> {
+        let mut totals: HashMap<(String, String), f64> = HashMap::new();
+        for entry in entries {
+            let (date, stats) = contributions(entry);
+            for (stat_type, value) in stats {
+                *totals.entry((stat_type, date.clone())).or_insert(0.0) += value;
+            }
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        sqlx::query("DELETE FROM journal_statistics")
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear journal statistics")?;
+
+        for ((stat_type, date), value) in totals {
+            sqlx::query(
+                "INSERT INTO journal_statistics (id, stat_type, stat_value, stat_date) VALUES (?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&stat_type)
+            .bind(value)
+            .bind(&date)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store journal statistic")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit journal statistics")?;
+        Ok(())
+    }
+
+    /// Aggregate statistics within `range`, rolled up by `granularity`
+    pub async fn query(
+        &self,
+        range: &StatisticsRange,
+        granularity: Granularity,
+    ) -> Result<Vec<StatisticsBucket>> {
+        let (start, end) = range.bounds();
+
+        let rows = sqlx::query(
+            "SELECT stat_type, stat_value, stat_date FROM journal_statistics
+             WHERE stat_date >= ? AND stat_date <= ?
+             ORDER BY stat_date",
+        )
+        .bind(&start)
+        .bind(&end)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to query journal statistics")?;
+
+        let mut buckets: BTreeMap<NaiveDate, StatisticsBucket> = BTreeMap::new();
+        for row in rows {
+            let stat_type: String = row.get("stat_type");
+            let value = row.get::<f64, _>("stat_value").round() as i64;
+            let date = NaiveDate::parse_from_str(&row.get::<String, _>("stat_date"), "%Y-%m-%d")
+                .context("Invalid statistic date")?;
+
+            let Some((metric, key, source)) = parse_stat_type(&stat_type) else {
+                continue;
+            };
+            let key = key.as_deref();
+
+            let period_start = granularity.period_start(date);
+            let bucket = buckets
+                .entry(period_start)
+                .or_insert_with(|| StatisticsBucket {
+                    period_start,
+                    totals: SourceStatistics::default(),
+                    sources: BTreeMap::new(),
+                });
+
+            bucket.totals.add(metric, key, value);
+            bucket
+                .sources
+                .entry(source)
+                .or_default()
+                .add(metric, key, value);
+        }
+
+        Ok(buckets
+            .into_values()
+            .map(|mut bucket| {
+                bucket.totals.finish();
+                bucket
+                    .sources
+                    .values_mut()
+                    .for_each(SourceStatistics::finish);
+                bucket
+            })
+            .collect())
+    }
+
+    /// Entries and words per local day within `range`, skipping `excluded_sources`
+    /// whatever their case
+    pub async fn daily_totals(
+        &self,
+        range: &StatisticsRange,
+        excluded_sources: &[String],
+    ) -> Result<BTreeMap<NaiveDate, DailyTotals>> {
+        let (start, end) = range.bounds();
+
+        let rows = sqlx::query(
+            "SELECT stat_type, stat_value, stat_date FROM journal_statistics
+             WHERE stat_date >= ? AND stat_date <= ?
+               AND (stat_type LIKE 'entries:%' OR stat_type LIKE 'words:%')",
+        )
+        .bind(&start)
+        .bind(&end)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to query daily totals")?;
+
+        let excluded: Vec<String> = excluded_sources
+            .iter()
+            .map(|source| normalize_source(source))
+            .collect();
+        let mut days: BTreeMap<NaiveDate, DailyTotals> = BTreeMap::new();
+        for row in rows {
+            let stat_type: String = row.get("stat_type");
+            let Some((metric, _, source)) = parse_stat_type(&stat_type) else {
+                continue;
+            };
+            if excluded.contains(&normalize_source(&source)) {
+                continue;
+            }
+
+            let value = row.get::<f64, _>("stat_value").round() as i64;
+            let date = NaiveDate::parse_from_str(&row.get::<String, _>("stat_date"), "%Y-%m-%d")
+                .context("Invalid statistic date")?;
+
+            let day = days.entry(date).or_default();
+            match metric {
+                ENTRIES => day.entries += value,
+                WORDS => day.words += value,
+                _ => {}
+            }
+        }
+
+        days.retain(|_, day| day.entries > 0);
+        Ok(days)
+    }
+
+    async fn apply(&self, entry: &JournalEntry, sign: f64) -> Result<()> {
+        let (date, stats) = contributions(entry);
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        for (stat_type, value) in stats {
+            sqlx::query(
+                r#"
+                INSERT INTO journal_statistics (id, stat_type, stat_value, stat_date)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(stat_type, stat_date) DO UPDATE SET stat_value = stat_value + excluded.stat_value
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&stat_type)
+            .bind(value * sign)
+            .bind(&date)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update journal statistic")?;
+        }
+
+        sqlx::query("DELETE FROM journal_statistics WHERE stat_date = ? AND stat_value <= 0")
+            .bind(&date)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to prune journal statistics")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit journal statistics")?;
+        Ok(())
+    }
+}
+
+/// Normalized source name used for bucketing
+pub fn entry_source(entry: &JournalEntry) -> &str {
+    entry
+        .source
+        .as_deref()
+        .filter(|s| !s.is_empty())
+        .unwrap_or(LOCAL_SOURCE)
+}
+
+/// Sources compare without case or surrounding space, and a missing one is local
+pub fn normalize_source(source: &str) -> String {
+    match source.trim() {
+        "" => LOCAL_SOURCE.to_string(),
+        source => source.to_lowercase(),
+    }
+}
+
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// The day an entry belongs to, in the timezone it was written in, and the statistics it contributes
+fn contributions(entry: &JournalEntry) -> (String, Vec<(String, f64)>) {
+    let local = entry.entry_date;
+    let source = entry_source(entry);
+
+    let mut stats = vec![
+        (stat_type(ENTRIES, None, source), 1.0),
+        (
+            stat_type(WORDS, None, source),
+            word_count(&entry.content) as f64,
+        ),
+        (
+            stat_type(CHARACTERS, None, source),
+            entry.content.chars().count() as f64,
+        ),
+        (
+            stat_type(HOUR, Some(&format!("{:02}", local.hour())), source),
+            1.0,
+        ),
+    ];
+
+    if let Some(mood) = entry.mood.as_deref().map(|m| m.trim().to_lowercase()) {
+        if !mood.is_empty() {
+            stats.push((stat_type(MOOD, Some(&mood), source), 1.0));
+        }
+    }
+
+    (local.date_naive().format("%Y-%m-%d").to_string(), stats)
+}
+
+fn stat_type(metric: &str, key: Option<&str>, source: &str) -> String {
+    match key {
+        Some(key) => format!("{}:{}:{}", metric, escape(key), escape(source)),
+        None => format!("{}:{}", metric, escape(source)),
+    }
+}
+
+/// The metric, key and source a stat_type was made from
+fn parse_stat_type(stat_type: &str) -> Option<(&str, Option<String>, String)> {
+    let parts: Vec<&str> = stat_type.split(':').collect();
+    match parts[..] {
+        [metric, source] => Some((metric, None, unescape(source))),
+        [metric, key, source] => Some((metric, Some(unescape(key)), unescape(source))),
+        _ => None,
+    }
+}
+
+fn escape(part: &str) -> String {
+    part.replace('%', "%25").replace(':', "%3A")
+}
+
+fn unescape(part: &str) -> String {
+    part.replace("%3A", ":").replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry_at, TempJournal};
+
+    const MASTODON: &str = "https://social.example/@me";
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn periods_start_on_mondays_and_firsts() {
+        // Wednesday the 18th and Sunday the 22nd are in the week of Monday the 16th
+        assert_eq!(Granularity::Day.period_start(day(18)), day(18));
+        assert_eq!(Granularity::Week.period_start(day(18)), day(16));
+        assert_eq!(Granularity::Week.period_start(day(22)), day(16));
+        assert_eq!(Granularity::Week.period_start(day(16)), day(16));
+        assert_eq!(Granularity::Month.period_start(day(18)), day(1));
+        assert_eq!(
+            Granularity::Year.period_start(day(18)),
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn entries_count_on_the_day_they_were_written() {
+        let mut entry = entry_at("late", "one two  three", "2026-03-14T23:30:00-05:00");
+        entry.mood = Some(" Happy ".to_string());
+
+        let (date, stats) = contributions(&entry);
+        assert_eq!(date, "2026-03-14");
+        assert_eq!(
+            stats,
+            [
+                ("entries:local".to_string(), 1.0),
+                ("words:local".to_string(), 3.0),
+                ("characters:local".to_string(), 14.0),
+                ("hour:23:local".to_string(), 1.0),
+                ("mood:happy:local".to_string(), 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn stat_types_keep_separators_in_sources_and_keys() {
+        let mut entry = entry_at("post", "Hello", "2026-03-14T09:00:00Z");
+        entry.source = Some(MASTODON.to_string());
+        entry.mood = Some("50%:ok".to_string());
+
+        let (_, stats) = contributions(&entry);
+        let parsed: Vec<_> = stats
+            .iter()
+            .map(|(stat_type, _)| parse_stat_type(stat_type).unwrap())
+            .collect();
+        assert_eq!(parsed[0], ("entries", None, MASTODON.to_string()));
+        assert_eq!(
+            parsed[3],
+            ("hour", Some("09".to_string()), MASTODON.to_string())
+        );
+        assert_eq!(
+            parsed[4],
+            ("mood", Some("50%:ok".to_string()), MASTODON.to_string())
+        );
+
+        assert_eq!(
+            parse_stat_type("entries:local"),
+            Some(("entries", None, "local".to_string()))
+        );
+        assert_eq!(parse_stat_type("entries"), None);
+        assert_eq!(parse_stat_type("mood:a:b:c"), None);
+    }
+
+    #[tokio::test]
+    async fn updates_and_deletes_undo_what_entries_added() {
+        let database = TempJournal::new().await;
+        let statistics = database.statistics();
+        let range = StatisticsRange::default();
+
+        let mut entry = entry_at("post", "Fig tree repotted", "2026-03-14T09:00:00Z");
+        entry.source = Some(MASTODON.to_string());
+        entry.mood = Some("happy".to_string());
+        database.create_entry(&entry).await.unwrap();
+        database
+            .create_entry(&entry_at("local", "Quiet day", "2026-03-14T20:00:00Z"))
+            .await
+            .unwrap();
+
+        let buckets = statistics.query(&range, Granularity::Day).await.unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].totals.entries, 2);
+        assert_eq!(buckets[0].totals.words, 5);
+        let post = &buckets[0].sources[MASTODON];
+        assert_eq!((post.entries, post.words), (1, 3));
+        assert_eq!(post.moods["happy"], 1);
+        assert_eq!(post.active_hours[9], 1);
+
+        // Moving the entry to another day takes everything it added with it
+        entry.content = "Fig tree repotted, basil next".to_string();
+        entry.entry_date = "2026-03-16T18:00:00+01:00".parse().unwrap();
+        entry.mood = None;
+        database.update_entry(&entry).await.unwrap();
+
+        let buckets = statistics.query(&range, Granularity::Day).await.unwrap();
+        assert_eq!(
+            buckets.iter().map(|b| b.period_start).collect::<Vec<_>>(),
+            [day(14), day(16)]
+        );
+        assert_eq!(
+            buckets[0].sources.keys().collect::<Vec<_>>(),
+            [LOCAL_SOURCE]
+        );
+        let post = &buckets[1].sources[MASTODON];
+        assert_eq!((post.entries, post.words), (1, 5));
+        assert!(post.moods.is_empty());
+        assert_eq!(post.active_hours[18], 1);
+
+        let excluded = statistics
+            .daily_totals(&range, &[MASTODON.to_string()])
+            .await
+            .unwrap();
+        assert_eq!(excluded.keys().collect::<Vec<_>>(), [&day(14)]);
+
+        database.delete_entry("post").await.unwrap();
+        database.delete_entry("local").await.unwrap();
+        assert!(statistics.is_empty().await.unwrap());
+    }
+}
@@ -0,0 +1,496 @@
+/**
+ * Entry storage backends for MyFace SnapJournal
+ *
+ * This module handles:
+ * - The EntryStorage trait covering entry CRUD, listing and search
+ * - The default SQLite backend
+ * - Choosing a backend per journal
+ *
+ * Statistics, moods, settings and the rest always live in the journal's
+ * SQLite database. Only where entries are kept depends on the backend.
+ */
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::query::Query;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Row, Sqlite, SqlitePool};
+use std::sync::{Arc, RwLock};
+
+use crate::database::{format_timestamp, parse_entry_date, parse_timestamp, JournalEntry};
+use crate::locations::{Bounds, EntryLocation, LocationSource};
+use crate::markdown_storage::MarkdownStorage;
+use crate::settings::JournalTimezone;
+
+/// Where a journal keeps its entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Entries live in the journal database
+    #[default]
+    Sqlite,
+    /// Entries are Markdown files in a folder; the database is an index
+    Markdown,
+    /// A Markdown folder that is also a git repository with every change committed
+    Git,
+}
+
+impl StorageKind {
+    /// Entries live in a folder of Markdown files rather than the database
+    pub fn uses_folder(self) -> bool {
+        matches!(self, StorageKind::Markdown | StorageKind::Git)
+    }
+}
+
+/// Entry states to restrict listing and search to; `None` matches both
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct EntryFilter {
+    #[serde(default)]
+    pub pinned: Option<bool>,
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    #[serde(default)]
+    pub archived: Option<bool>,
+    #[serde(default)]
+    pub locked: Option<bool>,
+}
+
+impl EntryFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        [
+            (self.pinned, entry.pinned),
+            (self.favorite, entry.favorite),
+            (self.archived, entry.archived),
+            (self.locked, entry.locked),
+        ]
+        .into_iter()
+        .all(|(wanted, state)| wanted.is_none() || wanted == Some(state))
+    }
+
+    /// An ` AND column = value` condition for each state the filter restricts
+    pub fn conditions(&self) -> String {
+        [
+            ("pinned", self.pinned),
+            ("favorite", self.favorite),
+            ("archived", self.archived),
+            ("locked", self.locked),
+        ]
+        .into_iter()
+        .filter_map(|(column, value)| {
+            value.map(|value| format!(" AND {} = {}", column, value as i32))
+        })
+        .collect()
+    }
+}
+
+pub trait EntryStorage {
+    async fn create_entry(&self, entry: &JournalEntry) -> Result<()>;
+    async fn get_entry(&self, id: &str) -> Result<Option<JournalEntry>>;
+    async fn update_entry(&self, entry: &JournalEntry) -> Result<()>;
+    async fn delete_entry(&self, id: &str) -> Result<()>;
+    /// Entries by entry date, newest first; a negative limit means no limit
+    async fn list_entries(
+        &self,
+        limit: i64,
+        offset: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>>;
+    async fn search_entries(&self, query: &str, filter: &EntryFilter) -> Result<Vec<JournalEntry>>;
+    /// Located entries inside `bounds` matching `filter`, newest first
+    async fn list_entries_in_bounds(
+        &self,
+        bounds: &Bounds,
+        limit: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>>;
+}
+
+/// The backend a journal was opened with
+pub enum Storage {
+    Sqlite(SqliteStorage),
+    Markdown(MarkdownStorage),
+}
+
+impl EntryStorage for Storage {
+    async fn create_entry(&self, entry: &JournalEntry) -> Result<()> {
+        match self {
+            Storage::Sqlite(storage) => storage.create_entry(entry).await,
+            Storage::Markdown(storage) => storage.create_entry(entry).await,
+        }
+    }
+
+    async fn get_entry(&self, id: &str) -> Result<Option<JournalEntry>> {
+        match self {
+            Storage::Sqlite(storage) => storage.get_entry(id).await,
+            Storage::Markdown(storage) => storage.get_entry(id).await,
+        }
+    }
+
+    async fn update_entry(&self, entry: &JournalEntry) -> Result<()> {
+        match self {
+            Storage::Sqlite(storage) => storage.update_entry(entry).await,
+            Storage::Markdown(storage) => storage.update_entry(entry).await,
+        }
+    }
+
+    async fn delete_entry(&self, id: &str) -> Result<()> {
+        match self {
+            Storage::Sqlite(storage) => storage.delete_entry(id).await,
+            Storage::Markdown(storage) => storage.delete_entry(id).await,
+        }
+    }
+
+    async fn list_entries(
+        &self,
+        limit: i64,
+        offset: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        match self {
+            Storage::Sqlite(storage) => storage.list_entries(limit, offset, filter).await,
+            Storage::Markdown(storage) => storage.list_entries(limit, offset, filter).await,
+        }
+    }
+
+    async fn search_entries(&self, query: &str, filter: &EntryFilter) -> Result<Vec<JournalEntry>> {
+        match self {
+            Storage::Sqlite(storage) => storage.search_entries(query, filter).await,
+            Storage::Markdown(storage) => storage.search_entries(query, filter).await,
+        }
+    }
+
+    async fn list_entries_in_bounds(
+        &self,
+        bounds: &Bounds,
+        limit: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        match self {
+            Storage::Sqlite(storage) => storage.list_entries_in_bounds(bounds, limit, filter).await,
+            Storage::Markdown(storage) => {
+                storage.list_entries_in_bounds(bounds, limit, filter).await
+            }
+        }
+    }
+}
+
+const INSERT_ENTRY: &str = r#"
+    INSERT INTO journal_entries (id, title, content, tags, mood, mood_intensity, privacy, source, source_id, source_url, metadata, entry_date, entry_utc_offset, created_at, updated_at, location_latitude, location_longitude, location_accuracy, location_name, location_country, location_source, pinned, favorite, archived, locked)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+"#;
+
+/// Entries stored in the journal_entries table
+pub struct SqliteStorage {
+    pool: SqlitePool,
+    /// Reads entry dates saved without an offset
+    timezone: Arc<RwLock<JournalTimezone>>,
+}
+
+impl SqliteStorage {
+    pub fn new(pool: SqlitePool, timezone: Arc<RwLock<JournalTimezone>>) -> Self {
+        SqliteStorage { pool, timezone }
+    }
+
+    fn timezone(&self) -> JournalTimezone {
+        *self.timezone.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Insert an entry or overwrite every field of an existing one
+    pub async fn put_entry(&self, entry: &JournalEntry) -> Result<()> {
+        let sql = format!(
+            "{} ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title, content = excluded.content, tags = excluded.tags,
+                mood = excluded.mood, mood_intensity = excluded.mood_intensity, privacy = excluded.privacy,
+                source = excluded.source, source_id = excluded.source_id, source_url = excluded.source_url,
+                metadata = excluded.metadata, entry_date = excluded.entry_date,
+                entry_utc_offset = excluded.entry_utc_offset, created_at = excluded.created_at,
+                updated_at = excluded.updated_at, location_latitude = excluded.location_latitude,
+                location_longitude = excluded.location_longitude,
+                location_accuracy = excluded.location_accuracy, location_name = excluded.location_name,
+                location_country = excluded.location_country, location_source = excluded.location_source,
+                pinned = excluded.pinned, favorite = excluded.favorite, archived = excluded.archived,
+                locked = excluded.locked",
+            INSERT_ENTRY
+        );
+
+        bind_entry(sqlx::query(&sql), entry)?
+            .execute(&self.pool)
+            .await
+            .context("Failed to store journal entry")?;
+
+        Ok(())
+    }
+}
+
+impl EntryStorage for SqliteStorage {
+    async fn create_entry(&self, entry: &JournalEntry) -> Result<()> {
+        bind_entry(sqlx::query(INSERT_ENTRY), entry)?
+            .execute(&self.pool)
+            .await
+            .context("Failed to create journal entry")?;
+
+        Ok(())
+    }
+
+    async fn get_entry(&self, id: &str) -> Result<Option<JournalEntry>> {
+        let row = sqlx::query("SELECT * FROM journal_entries WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch journal entry")?;
+
+        if let Some(row) = row {
+            Ok(Some(entry_from_row(&row, &self.timezone())?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn update_entry(&self, entry: &JournalEntry) -> Result<()> {
+        let query = sqlx::query(
+            r#"
+            UPDATE journal_entries
+            SET title = ?, content = ?, tags = ?, mood = ?, mood_intensity = ?, privacy = ?, source = ?, source_id = ?, source_url = ?, metadata = ?, entry_date = ?, entry_utc_offset = ?, updated_at = ?,
+                location_latitude = ?, location_longitude = ?, location_accuracy = ?, location_name = ?, location_country = ?, location_source = ?,
+                pinned = ?, favorite = ?, archived = ?, locked = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&entry.title)
+        .bind(&entry.content)
+        .bind(serde_json::to_string(&entry.tags)?)
+        .bind(&entry.mood)
+        .bind(entry.mood_intensity)
+        .bind(&entry.privacy)
+        .bind(&entry.source)
+        .bind(&entry.source_id)
+        .bind(&entry.source_url)
+        .bind(entry.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_default()))
+        .bind(format_timestamp(&entry.entry_date.with_timezone(&Utc)))
+        .bind(entry.entry_date.offset().local_minus_utc() / 60)
+        .bind(format_timestamp(&entry.updated_at));
+
+        bind_states(bind_location(query, entry.location.as_ref()), entry)
+            .bind(&entry.id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update journal entry")?;
+
+        Ok(())
+    }
+
+    async fn delete_entry(&self, id: &str) -> Result<()> {
+        // Delete associated embeddings first; older schemas reference
+        // journal_entries without ON DELETE CASCADE
+        sqlx::query("DELETE FROM embeddings WHERE entry_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete associated embeddings")?;
+
+        sqlx::query("DELETE FROM journal_entries WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete journal entry")?;
+
+        Ok(())
+    }
+
+    async fn list_entries(
+        &self,
+        limit: i64,
+        offset: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        let sql = format!(
+            "SELECT * FROM journal_entries WHERE 1 = 1{}
+             ORDER BY entry_date DESC, created_at DESC LIMIT ? OFFSET ?",
+            filter.conditions()
+        );
+
+        let rows = sqlx::query(&sql)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list journal entries")?;
+
+        let timezone = self.timezone();
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(entry_from_row(&row, &timezone)?);
+        }
+
+        Ok(entries)
+    }
+
+    async fn search_entries(&self, query: &str, filter: &EntryFilter) -> Result<Vec<JournalEntry>> {
+        let search_term = format!("%{}%", query);
+        let sql = format!(
+            "SELECT * FROM journal_entries
+             WHERE (title LIKE ? OR content LIKE ? OR tags LIKE ?){}
+             ORDER BY entry_date DESC, created_at DESC",
+            filter.conditions()
+        );
+
+        let rows = sqlx::query(&sql)
+            .bind(&search_term)
+            .bind(&search_term)
+            .bind(&search_term)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to search journal entries")?;
+
+        let timezone = self.timezone();
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(entry_from_row(&row, &timezone)?);
+        }
+
+        Ok(entries)
+    }
+
+    async fn list_entries_in_bounds(
+        &self,
+        bounds: &Bounds,
+        limit: i64,
+        filter: &EntryFilter,
+    ) -> Result<Vec<JournalEntry>> {
+        // An area crossing the antimeridian is the union of its two sides
+        let longitude = if bounds.crosses_antimeridian() {
+            "(location_longitude >= ? OR location_longitude <= ?)"
+        } else {
+            "location_longitude BETWEEN ? AND ?"
+        };
+        let sql = format!(
+            "SELECT * FROM journal_entries
+             WHERE location_latitude BETWEEN ? AND ? AND {}{}
+             ORDER BY entry_date DESC, created_at DESC LIMIT ?",
+            longitude,
+            filter.conditions()
+        );
+
+        let rows = sqlx::query(&sql)
+            .bind(bounds.south)
+            .bind(bounds.north)
+            .bind(bounds.west)
+            .bind(bounds.east)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list entries in bounds")?;
+
+        let timezone = self.timezone();
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(entry_from_row(&row, &timezone)?);
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Bind every column of INSERT_ENTRY
+fn bind_entry<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    entry: &'q JournalEntry,
+) -> Result<Query<'q, Sqlite, SqliteArguments<'q>>> {
+    let query = query
+        .bind(&entry.id)
+        .bind(&entry.title)
+        .bind(&entry.content)
+        .bind(serde_json::to_string(&entry.tags)?)
+        .bind(&entry.mood)
+        .bind(entry.mood_intensity)
+        .bind(&entry.privacy)
+        .bind(&entry.source)
+        .bind(&entry.source_id)
+        .bind(&entry.source_url)
+        .bind(
+            entry
+                .metadata
+                .as_ref()
+                .map(|m| serde_json::to_string(m).unwrap_or_default()),
+        )
+        .bind(format_timestamp(&entry.entry_date.with_timezone(&Utc)))
+        .bind(entry.entry_date.offset().local_minus_utc() / 60)
+        .bind(format_timestamp(&entry.created_at))
+        .bind(format_timestamp(&entry.updated_at));
+
+    Ok(bind_states(
+        bind_location(query, entry.location.as_ref()),
+        entry,
+    ))
+}
+
+/// Bind the pinned, favorite, archived and locked columns
+fn bind_states<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    entry: &JournalEntry,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    query
+        .bind(entry.pinned)
+        .bind(entry.favorite)
+        .bind(entry.archived)
+        .bind(entry.locked)
+}
+
+/// Bind the six location columns, all NULL for an entry without a location
+fn bind_location<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    location: Option<&'q EntryLocation>,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    query
+        .bind(location.map(|l| l.latitude))
+        .bind(location.map(|l| l.longitude))
+        .bind(location.and_then(|l| l.accuracy))
+        .bind(location.and_then(|l| l.place_name.as_deref()))
+        .bind(location.and_then(|l| l.country.as_deref()))
+        .bind(location.map(|l| l.source.as_str()))
+}
+
+fn location_from_row(row: &SqliteRow) -> Option<EntryLocation> {
+    Some(EntryLocation {
+        latitude: row.get::<Option<f64>, _>("location_latitude")?,
+        longitude: row.get::<Option<f64>, _>("location_longitude")?,
+        accuracy: row.get("location_accuracy"),
+        place_name: row.get("location_name"),
+        country: row.get("location_country"),
+        source: row
+            .get::<Option<String>, _>("location_source")
+            .and_then(|source| LocationSource::parse(&source))
+            .unwrap_or_default(),
+    })
+}
+
+fn entry_from_row(row: &SqliteRow, timezone: &JournalTimezone) -> Result<JournalEntry> {
+    Ok(JournalEntry {
+        id: row.get("id"),
+        title: row.get("title"),
+        content: row.get("content"),
+        tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+        mood: row.get("mood"),
+        mood_intensity: row.get("mood_intensity"),
+        privacy: row.get("privacy"),
+        source: row.get("source"),
+        source_id: row.get("source_id"),
+        source_url: row.get("source_url"),
+        metadata: row
+            .get::<Option<String>, _>("metadata")
+            .and_then(|m| serde_json::from_str(&m).ok()),
+        location: location_from_row(row),
+        pinned: row.get("pinned"),
+        favorite: row.get("favorite"),
+        archived: row.get("archived"),
+        locked: row.get("locked"),
+        entry_date: parse_entry_date(
+            &row.get::<Option<String>, _>("entry_date")
+                .unwrap_or_else(|| row.get("created_at")),
+            row.get("entry_utc_offset"),
+            timezone,
+        )?,
+        created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
+        updated_at: parse_timestamp(&row.get::<String, _>("updated_at"))?,
+    })
+}
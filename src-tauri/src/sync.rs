@@ -0,0 +1,1246 @@
+/**
+ * Folder sync for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Hybrid logical clocks and per-entry version vectors
+ * - Appending this device's entry changes to its log in a shared folder
+ * - Replaying the other devices' logs into the local database
+ * - Keeping the losing side of concurrent edits as conflict copies
+ *
+ * Each device only ever appends to its own `<device id>.jsonl`, so tools
+ * like Syncthing or Nextcloud never have to merge a file and the database
+ * itself is never copied between machines.
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::database::{format_timestamp, parse_timestamp, Database, JournalEntry};
+
+const DEVICE_ID_SETTING: &str = "sync_device_id";
+const CLOCK_SETTING: &str = "sync_clock";
+const LAST_SYNC_SETTING: &str = "last_sync_at";
+const LOG_EXTENSION: &str = "jsonl";
+
+/// A hybrid logical clock reading: wall time, a counter for changes within
+/// the same millisecond, and the device that made the change
+///
+/// Readings are totally ordered, so every device picks the same winner
+/// between two concurrent edits.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Hlc {
+    pub millis: i64,
+    pub counter: u32,
+    pub device_id: String,
+}
+
+impl Hlc {
+    /// The reading for a new change on this device
+    fn tick(last: Option<&Hlc>, device_id: &str) -> Hlc {
+        let now = Utc::now().timestamp_millis();
+        match last {
+            Some(last) if last.millis >= now => Hlc {
+                millis: last.millis,
+                counter: last.counter + 1,
+                device_id: device_id.to_string(),
+            },
+            _ => Hlc {
+                millis: now,
+                counter: 0,
+                device_id: device_id.to_string(),
+            },
+        }
+    }
+
+    /// Move past a reading received from another device, so later local
+    /// changes order after it even if that device's clock runs ahead
+    fn observe(last: Option<&Hlc>, remote: &Hlc, device_id: &str) -> Hlc {
+        let local = Hlc::tick(last, device_id);
+        if remote.millis < local.millis {
+            return local;
+        }
+
+        let counter = if remote.millis == local.millis {
+            remote.counter.max(local.counter) + 1
+        } else {
+            remote.counter + 1
+        };
+        Hlc {
+            millis: remote.millis,
+            counter,
+            device_id: device_id.to_string(),
+        }
+    }
+}
+
+// Zero-padded so readings also sort correctly as text
+impl fmt::Display for Hlc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:015}-{:010}-{}",
+            self.millis, self.counter, self.device_id
+        )
+    }
+}
+
+impl FromStr for Hlc {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let mut parts = value.splitn(3, '-');
+        let (Some(millis), Some(counter), Some(device_id)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(anyhow::anyhow!("Invalid clock reading: {}", value));
+        };
+
+        Ok(Hlc {
+            millis: millis
+                .parse()
+                .with_context(|| format!("Invalid clock reading: {}", value))?,
+            counter: counter
+                .parse()
+                .with_context(|| format!("Invalid clock reading: {}", value))?,
+            device_id: device_id.to_string(),
+        })
+    }
+}
+
+impl From<Hlc> for String {
+    fn from(hlc: Hlc) -> Self {
+        hlc.to_string()
+    }
+}
+
+impl TryFrom<String> for Hlc {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+/// How many changes each device has made to an entry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VersionVector(BTreeMap<String, u64>);
+
+impl VersionVector {
+    fn get(&self, device_id: &str) -> u64 {
+        self.0.get(device_id).copied().unwrap_or(0)
+    }
+
+    fn increment(&mut self, device_id: &str) {
+        *self.0.entry(device_id.to_string()).or_insert(0) += 1;
+    }
+
+    /// `None` when each side has changes the other hasn't seen
+    fn compare(&self, other: &VersionVector) -> Option<Ordering> {
+        let mut ordering = Ordering::Equal;
+        for device_id in self.0.keys().chain(other.0.keys()) {
+            match (ordering, self.get(device_id).cmp(&other.get(device_id))) {
+                (_, Ordering::Equal) => {}
+                (Ordering::Equal, newer) => ordering = newer,
+                (current, newer) if current != newer => return None,
+                _ => {}
+            }
+        }
+        Some(ordering)
+    }
+
+    fn merge(&self, other: &VersionVector) -> VersionVector {
+        let mut merged = self.clone();
+        for (device_id, count) in &other.0 {
+            let entry = merged.0.entry(device_id.clone()).or_insert(0);
+            *entry = (*entry).max(*count);
+        }
+        merged
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOp {
+    Upsert,
+    Delete,
+}
+
+/// One line of a device's log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeRecord {
+    pub entry_id: String,
+    pub op: ChangeOp,
+    pub clock: VersionVector,
+    pub hlc: Hlc,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<JournalEntry>,
+    /// Conflicts on this entry the writer has resolved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolved: Vec<Hlc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub device_id: String,
+    pub exported: usize,
+    pub imported: usize,
+    pub conflicts: usize,
+    /// Log lines that could not be read or applied, with the reason
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub last_hlc: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub device_id: String,
+    pub last_sync_at: Option<DateTime<Utc>>,
+    /// Local changes not yet written to the sync folder
+    pub pending: i64,
+    pub conflicts: i64,
+    pub peers: Vec<SyncPeer>,
+}
+
+/// A version of an entry that lost to a concurrent edit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: String,
+    pub entry_id: String,
+    pub hlc: String,
+    pub entry: JournalEntry,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Discard the conflict copy
+    KeepCurrent,
+    /// Replace the entry with the conflict copy
+    UseCopy,
+    /// Keep the conflict copy as a separate entry
+    KeepBoth,
+}
+
+/// The latest known version of an entry
+struct Version {
+    clock: VersionVector,
+    hlc: Hlc,
+    deleted: bool,
+    pending: bool,
+    resolved: Vec<Hlc>,
+}
+
+enum Outcome {
+    Skipped,
+    Applied,
+    Conflict,
+}
+
+pub struct SyncEngine<'a> {
+    database: &'a Database,
+}
+
+impl<'a> SyncEngine<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        SyncEngine { database }
+    }
+
+    /// This device's id, created the first time it is needed
+    pub async fn device_id(&self) -> Result<String> {
+        if let Some(device_id) = self.database.get_setting(DEVICE_ID_SETTING).await? {
+            return Ok(device_id);
+        }
+
+        let device_id = Uuid::new_v4().to_string();
+        self.database
+            .set_setting(DEVICE_ID_SETTING, &device_id)
+            .await?;
+        Ok(device_id)
+    }
+
+    /// Record a local change to an entry so the next sync writes it out
+    pub async fn record_change(&self, entry_id: &str, deleted: bool) -> Result<()> {
+        let device_id = self.device_id().await?;
+        let hlc = Hlc::tick(self.last_hlc().await?.as_ref(), &device_id);
+        self.set_last_hlc(&hlc).await?;
+
+        let version = self.version(entry_id).await?;
+        let mut clock = version
+            .as_ref()
+            .map(|v| v.clock.clone())
+            .unwrap_or_default();
+        clock.increment(&device_id);
+        let resolved = version.map(|v| v.resolved).unwrap_or_default();
+
+        self.set_version(
+            entry_id,
+            &Version {
+                clock,
+                hlc,
+                deleted,
+                pending: true,
+                resolved,
+            },
+        )
+        .await
+    }
+
+    /// Write local changes to `folder` and merge in every other device's log
+    pub async fn sync(&self, folder: &Path) -> Result<SyncReport> {
+        tokio::fs::create_dir_all(folder)
+            .await
+            .with_context(|| format!("Failed to create sync folder {:?}", folder))?;
+
+        let device_id = self.device_id().await?;
+        let mut report = SyncReport {
+            device_id: device_id.clone(),
+            ..Default::default()
+        };
+
+        self.track_untracked_entries().await?;
+
+        // Write local changes first so they reach the other devices with the
+        // clocks they were made with, not merged with what is about to be read
+        report.exported = self
+            .export_log(&folder.join(format!("{}.{}", device_id, LOG_EXTENSION)))
+            .await?;
+
+        let mut logs = tokio::fs::read_dir(folder)
+            .await
+            .with_context(|| format!("Failed to read sync folder {:?}", folder))?;
+        while let Some(log) = logs.next_entry().await? {
+            let path = log.path();
+            let Some(peer) = peer_id(&path) else {
+                continue;
+            };
+            if peer != device_id {
+                self.import_log(&peer, &path, &mut report).await?;
+            }
+        }
+
+        self.database
+            .set_setting(LAST_SYNC_SETTING, &format_timestamp(&Utc::now()))
+            .await?;
+
+        if report.imported + report.exported + report.conflicts > 0 {
+            println!(
+                "Synced with {:?}: {} written, {} merged, {} conflicts",
+                folder, report.exported, report.imported, report.conflicts
+            );
+        }
+
+        Ok(report)
+    }
+
+    pub async fn status(&self) -> Result<SyncStatus> {
+        let pending: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sync_versions WHERE pending = 1")
+                .fetch_one(self.database.pool())
+                .await
+                .context("Failed to count pending changes")?;
+        let conflicts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sync_conflicts")
+            .fetch_one(self.database.pool())
+            .await
+            .context("Failed to count sync conflicts")?;
+
+        let rows = sqlx::query(
+            "SELECT device_id, last_hlc, updated_at FROM sync_peers ORDER BY updated_at DESC",
+        )
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to list sync peers")?;
+        let mut peers = Vec::with_capacity(rows.len());
+        for row in rows {
+            peers.push(SyncPeer {
+                device_id: row.get("device_id"),
+                last_hlc: row.get("last_hlc"),
+                updated_at: parse_timestamp(&row.get::<String, _>("updated_at"))?,
+            });
+        }
+
+        let last_sync_at = match self.database.get_setting(LAST_SYNC_SETTING).await? {
+            Some(value) => Some(parse_timestamp(&value)?),
+            None => None,
+        };
+
+        Ok(SyncStatus {
+            device_id: self.device_id().await?,
+            last_sync_at,
+            pending,
+            conflicts,
+            peers,
+        })
+    }
+
+    pub async fn list_conflicts(&self) -> Result<Vec<SyncConflict>> {
+        let rows = sqlx::query("SELECT * FROM sync_conflicts ORDER BY created_at DESC")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list sync conflicts")?;
+
+        rows.iter().map(conflict_from_row).collect()
+    }
+
+    /// Settle a conflict; returns the entry that was written, if any
+    pub async fn resolve_conflict(
+        &self,
+        id: &str,
+        resolution: ConflictResolution,
+    ) -> Result<Option<JournalEntry>> {
+        let row = sqlx::query("SELECT * FROM sync_conflicts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await
+            .context("Failed to fetch sync conflict")?
+            .context("Conflict not found")?;
+        let conflict = conflict_from_row(&row)?;
+
+        let written = match resolution {
+            ConflictResolution::KeepCurrent => None,
+            ConflictResolution::UseCopy => {
+                let mut entry = conflict.entry.clone();
+                entry.updated_at = Utc::now();
+                if self.database.get_entry(&entry.id).await?.is_some() {
+                    self.database.update_entry(&entry).await?;
+                } else {
+                    self.database.create_entry(&entry).await?;
+                }
+                Some(entry)
+            }
+            ConflictResolution::KeepBoth => {
+                let mut entry = conflict.entry.clone();
+                entry.id = Uuid::new_v4().to_string();
+                entry.updated_at = Utc::now();
+                self.database.create_entry(&entry).await?;
+                Some(entry)
+            }
+        };
+
+        // Tell the other devices, which hold the same conflict
+        if let Some(mut version) = self.version(&conflict.entry_id).await? {
+            version.resolved.push(conflict.hlc.parse()?);
+            version.pending = true;
+            self.set_version(&conflict.entry_id, &version).await?;
+        }
+
+        sqlx::query("DELETE FROM sync_conflicts WHERE id = ?")
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to delete sync conflict")?;
+
+        Ok(written)
+    }
+
+    /// Give entries written before sync was set up a version to sync from
+    async fn track_untracked_entries(&self) -> Result<()> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM journal_entries WHERE id NOT IN (SELECT entry_id FROM sync_versions)",
+        )
+        .fetch_all(self.database.pool())
+        .await
+        .context("Failed to find untracked entries")?;
+
+        for id in ids {
+            self.record_change(&id, false).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply the lines of `peer`'s log added since the last sync
+    async fn import_log(&self, peer: &str, path: &Path, report: &mut SyncReport) -> Result<()> {
+        let offset: i64 =
+            sqlx::query_scalar("SELECT log_offset FROM sync_peers WHERE device_id = ?")
+                .bind(peer)
+                .fetch_optional(self.database.pool())
+                .await
+                .context("Failed to read sync peer")?
+                .unwrap_or(0);
+
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open {:?}", path))?;
+        // A log that shrank was replaced; replaying it is harmless
+        let offset = if (file.metadata().await?.len() as i64) < offset {
+            0
+        } else {
+            offset
+        };
+        file.seek(std::io::SeekFrom::Start(offset as u64)).await?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).await?;
+
+        // The file may still be arriving; leave a trailing partial line for next time
+        let complete = match bytes.iter().rposition(|b| *b == b'\n') {
+            Some(end) => &bytes[..=end],
+            None => &[][..],
+        };
+
+        let mut consumed = 0;
+        let mut last_hlc = None;
+        for line in complete.split_inclusive(|b| *b == b'\n') {
+            let text = String::from_utf8_lossy(line);
+            if !text.trim().is_empty() {
+                match serde_json::from_str::<ChangeRecord>(text.trim()) {
+                    Ok(record) => match self.apply_record(&record).await {
+                        Ok(applied) => {
+                            match applied {
+                                Outcome::Skipped => {}
+                                Outcome::Applied => report.imported += 1,
+                                Outcome::Conflict => {
+                                    report.imported += 1;
+                                    report.conflicts += 1;
+                                }
+                            }
+                            last_hlc = Some(record.hlc.to_string());
+                        }
+                        // Stop here and retry from this line on the next sync
+                        Err(e) => {
+                            eprintln!("Failed to apply change from {}: {}", peer, e);
+                            report.failed.push(format!("{}: {}", peer, e));
+                            break;
+                        }
+                    },
+                    // A line that can't be parsed never will be
+                    Err(e) => {
+                        eprintln!("Skipping unreadable change from {}: {}", peer, e);
+                        report.failed.push(format!("{}: {}", peer, e));
+                    }
+                }
+            }
+            consumed += line.len();
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO sync_peers (device_id, log_offset, last_hlc, updated_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT(device_id) DO UPDATE SET
+                log_offset = excluded.log_offset,
+                last_hlc = COALESCE(excluded.last_hlc, sync_peers.last_hlc),
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(peer)
+        .bind(offset + consumed as i64)
+        .bind(last_hlc)
+        .bind(format_timestamp(&Utc::now()))
+        .execute(self.database.pool())
+        .await
+        .context("Failed to update sync peer")?;
+
+        Ok(())
+    }
+
+    async fn apply_record(&self, record: &ChangeRecord) -> Result<Outcome> {
+        if let Some(entry) = &record.entry {
+            if entry.id != record.entry_id {
+                return Err(anyhow::anyhow!(
+                    "Change for {} carries another entry",
+                    record.entry_id
+                ));
+            }
+        }
+
+        let device_id = self.device_id().await?;
+        let observed = Hlc::observe(self.last_hlc().await?.as_ref(), &record.hlc, &device_id);
+        self.set_last_hlc(&observed).await?;
+
+        for hlc in &record.resolved {
+            sqlx::query("DELETE FROM sync_conflicts WHERE entry_id = ? AND hlc = ?")
+                .bind(&record.entry_id)
+                .bind(hlc.to_string())
+                .execute(self.database.pool())
+                .await
+                .context("Failed to clear resolved conflict")?;
+        }
+
+        let deleted = record.op == ChangeOp::Delete;
+        let Some(local) = self.version(&record.entry_id).await? else {
+            self.apply_change(record).await?;
+            self.set_version(
+                &record.entry_id,
+                &Version {
+                    clock: record.clock.clone(),
+                    hlc: record.hlc.clone(),
+                    deleted,
+                    pending: false,
+                    resolved: vec![],
+                },
+            )
+            .await?;
+            return Ok(Outcome::Applied);
+        };
+
+        match record.clock.compare(&local.clock) {
+            // Already seen, or older than what we have
+            Some(Ordering::Less | Ordering::Equal) => Ok(Outcome::Skipped),
+            // Builds on our version
+            Some(Ordering::Greater) => {
+                self.apply_change(record).await?;
+                self.set_version(
+                    &record.entry_id,
+                    &Version {
+                        clock: record.clock.clone(),
+                        hlc: record.hlc.clone(),
+                        deleted,
+                        // Still send resolutions made here
+                        pending: !local.resolved.is_empty(),
+                        resolved: local.resolved,
+                    },
+                )
+                .await?;
+                Ok(Outcome::Applied)
+            }
+            // Edited on both devices; the later change wins on every device
+            None => {
+                let current = self.database.get_entry(&record.entry_id).await?;
+                let clock = local.clock.merge(&record.clock);
+                let same = serde_json::to_value(&current)? == serde_json::to_value(&record.entry)?;
+                let remote_wins = record.hlc > local.hlc;
+
+                let mut conflict = false;
+                if !same {
+                    let loser = if remote_wins {
+                        current.map(|entry| (entry, &local.clock, &local.hlc))
+                    } else {
+                        record
+                            .entry
+                            .clone()
+                            .map(|entry| (entry, &record.clock, &record.hlc))
+                    };
+                    // A losing delete leaves nothing behind to keep
+                    if let Some((entry, clock, hlc)) = loser {
+                        self.save_conflict(entry, clock, hlc).await?;
+                        conflict = true;
+                    }
+                    if remote_wins {
+                        self.apply_change(record).await?;
+                    }
+                }
+
+                let (hlc, deleted) = if remote_wins {
+                    (record.hlc.clone(), deleted)
+                } else {
+                    (local.hlc.clone(), local.deleted)
+                };
+                self.set_version(
+                    &record.entry_id,
+                    &Version {
+                        clock,
+                        hlc,
+                        deleted,
+                        pending: local.pending,
+                        resolved: local.resolved,
+                    },
+                )
+                .await?;
+
+                Ok(if conflict {
+                    Outcome::Conflict
+                } else {
+                    Outcome::Applied
+                })
+            }
+        }
+    }
+
+    async fn apply_change(&self, record: &ChangeRecord) -> Result<()> {
+        match (record.op, &record.entry) {
+            (ChangeOp::Upsert, Some(entry)) => self.database.apply_synced_entry(entry).await,
+            (ChangeOp::Upsert, None) => Err(anyhow::anyhow!(
+                "Change for {} is missing the entry",
+                record.entry_id
+            )),
+            (ChangeOp::Delete, _) => self.database.apply_synced_delete(&record.entry_id).await,
+        }
+    }
+
+    /// Append pending local changes to this device's log, returning how many were written
+    async fn export_log(&self, path: &Path) -> Result<usize> {
+        let rows = sqlx::query("SELECT * FROM sync_versions WHERE pending = 1 ORDER BY hlc")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list pending changes")?;
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let mut lines = String::new();
+        let mut written = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let entry_id: String = row.get("entry_id");
+            let version = version_from_row(row)?;
+            let entry = if version.deleted {
+                None
+            } else {
+                self.database.get_entry(&entry_id).await?
+            };
+
+            let record = ChangeRecord {
+                entry_id: entry_id.clone(),
+                op: if entry.is_some() {
+                    ChangeOp::Upsert
+                } else {
+                    ChangeOp::Delete
+                },
+                clock: version.clock,
+                hlc: version.hlc.clone(),
+                entry,
+                resolved: version.resolved,
+            };
+            lines.push_str(&serde_json::to_string(&record)?);
+            lines.push('\n');
+            written.push((entry_id, version.hlc));
+        }
+
+        let mut log = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open {:?}", path))?;
+        log.write_all(lines.as_bytes())
+            .await
+            .with_context(|| format!("Failed to write {:?}", path))?;
+        log.sync_all().await?;
+
+        // Entries changed again since being read stay pending
+        for (entry_id, hlc) in &written {
+            sqlx::query(
+                "UPDATE sync_versions SET pending = 0, resolved = NULL WHERE entry_id = ? AND hlc = ?",
+            )
+            .bind(entry_id)
+            .bind(hlc.to_string())
+            .execute(self.database.pool())
+            .await
+            .context("Failed to mark changes as synced")?;
+        }
+
+        Ok(written.len())
+    }
+
+    async fn save_conflict(
+        &self,
+        entry: JournalEntry,
+        clock: &VersionVector,
+        hlc: &Hlc,
+    ) -> Result<()> {
+        // Every device keeps the same loser, so resolving on one clears it on all
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO sync_conflicts (id, entry_id, clock, hlc, entry, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(format!("{}:{}", entry.id, hlc))
+        .bind(&entry.id)
+        .bind(serde_json::to_string(clock)?)
+        .bind(hlc.to_string())
+        .bind(serde_json::to_string(&entry)?)
+        .bind(format_timestamp(&Utc::now()))
+        .execute(self.database.pool())
+        .await
+        .context("Failed to save sync conflict")?;
+
+        Ok(())
+    }
+
+    async fn version(&self, entry_id: &str) -> Result<Option<Version>> {
+        let row = sqlx::query("SELECT * FROM sync_versions WHERE entry_id = ?")
+            .bind(entry_id)
+            .fetch_optional(self.database.pool())
+            .await
+            .context("Failed to read entry version")?;
+
+        row.as_ref().map(version_from_row).transpose()
+    }
+
+    async fn set_version(&self, entry_id: &str, version: &Version) -> Result<()> {
+        let resolved = if version.resolved.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&version.resolved)?)
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO sync_versions (entry_id, clock, hlc, deleted, pending, resolved)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(entry_id) DO UPDATE SET
+                clock = excluded.clock, hlc = excluded.hlc, deleted = excluded.deleted,
+                pending = excluded.pending, resolved = excluded.resolved
+            "#,
+        )
+        .bind(entry_id)
+        .bind(serde_json::to_string(&version.clock)?)
+        .bind(version.hlc.to_string())
+        .bind(version.deleted)
+        .bind(version.pending)
+        .bind(resolved)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to write entry version")?;
+
+        Ok(())
+    }
+
+    async fn last_hlc(&self) -> Result<Option<Hlc>> {
+        self.database
+            .get_setting(CLOCK_SETTING)
+            .await?
+            .map(|value| value.parse())
+            .transpose()
+    }
+
+    async fn set_last_hlc(&self, hlc: &Hlc) -> Result<()> {
+        self.database
+            .set_setting(CLOCK_SETTING, &hlc.to_string())
+            .await
+    }
+}
+
+/// The device a log file belongs to; anything else in the folder is ignored
+fn peer_id(path: &Path) -> Option<String> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some(LOG_EXTENSION) {
+        return None;
+    }
+
+    // Sync tools leave conflicted copies like "<id>.sync-conflict-....jsonl"
+    let stem = path.file_stem()?.to_str()?;
+    Uuid::parse_str(stem).ok().map(|_| stem.to_string())
+}
+
+fn version_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Version> {
+    Ok(Version {
+        clock: serde_json::from_str(&row.get::<String, _>("clock"))?,
+        hlc: row.get::<String, _>("hlc").parse()?,
+        deleted: row.get("deleted"),
+        pending: row.get("pending"),
+        resolved: row
+            .get::<Option<String>, _>("resolved")
+            .map(|resolved| serde_json::from_str(&resolved))
+            .transpose()?
+            .unwrap_or_default(),
+    })
+}
+
+fn conflict_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<SyncConflict> {
+    Ok(SyncConflict {
+        id: row.get("id"),
+        entry_id: row.get("entry_id"),
+        hlc: row.get("hlc"),
+        entry: serde_json::from_str(&row.get::<String, _>("entry"))?,
+        created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, TempJournal};
+    use std::path::PathBuf;
+
+    const DEVICE: &str = "8d5bcb4e-5b0e-4a8e-9d43-2f6c1f0f7a11";
+
+    fn hlc(millis: i64, counter: u32, device_id: &str) -> Hlc {
+        Hlc {
+            millis,
+            counter,
+            device_id: device_id.to_string(),
+        }
+    }
+
+    fn clock(counts: &[(&str, u64)]) -> VersionVector {
+        VersionVector(
+            counts
+                .iter()
+                .map(|(device_id, count)| (device_id.to_string(), *count))
+                .collect(),
+        )
+    }
+
+    /// Two devices sharing a sync folder
+    struct Devices {
+        a: TempJournal,
+        b: TempJournal,
+        folder: PathBuf,
+    }
+
+    impl Devices {
+        async fn new() -> Self {
+            let a = TempJournal::new().await;
+            let b = TempJournal::new().await;
+            let folder = a.dir().join("sync");
+            Devices { a, b, folder }
+        }
+
+        async fn sync(&self, journal: &TempJournal) -> SyncReport {
+            SyncEngine::new(journal).sync(&self.folder).await.unwrap()
+        }
+
+        /// Sync until both devices have seen everything
+        async fn settle(&self) {
+            self.sync(&self.a).await;
+            self.sync(&self.b).await;
+            self.sync(&self.a).await;
+        }
+    }
+
+    async fn edit(journal: &TempJournal, id: &str, content: &str) {
+        let mut entry = journal.get_entry(id).await.unwrap().unwrap();
+        entry.content = content.to_string();
+        entry.updated_at = Utc::now();
+        journal.update_entry(&entry).await.unwrap();
+    }
+
+    async fn content(journal: &TempJournal, id: &str) -> Option<String> {
+        journal
+            .get_entry(id)
+            .await
+            .unwrap()
+            .map(|entry| entry.content)
+    }
+
+    async fn conflicts(journal: &TempJournal) -> Vec<SyncConflict> {
+        SyncEngine::new(journal).list_conflicts().await.unwrap()
+    }
+
+    // Keeps the two sides' clock readings apart so the winner is known
+    async fn next_millisecond() {
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    #[test]
+    fn ticks_count_up_while_the_clock_stands_still() {
+        let ahead = hlc(Utc::now().timestamp_millis() + 60_000, 4, "other");
+        assert_eq!(
+            Hlc::tick(Some(&ahead), DEVICE),
+            hlc(ahead.millis, 5, DEVICE)
+        );
+
+        let behind = hlc(1_000, 9, DEVICE);
+        let next = Hlc::tick(Some(&behind), DEVICE);
+        assert!(next.millis > behind.millis);
+        assert_eq!(next.counter, 0);
+        assert!(next > behind);
+    }
+
+    #[test]
+    fn observing_a_remote_reading_moves_past_it() {
+        let now = Utc::now().timestamp_millis();
+
+        // A device whose clock runs ahead drags ours along
+        let remote = hlc(now + 60_000, 3, "other");
+        let observed = Hlc::observe(None, &remote, DEVICE);
+        assert_eq!(observed, hlc(remote.millis, 4, DEVICE));
+        assert!(Hlc::tick(Some(&observed), DEVICE) > remote);
+
+        // Same millisecond as our last reading: past both counters
+        let last = hlc(now + 60_000, 7, DEVICE);
+        assert_eq!(
+            Hlc::observe(Some(&last), &remote, DEVICE),
+            hlc(remote.millis, 9, DEVICE)
+        );
+
+        // An old reading doesn't hold our clock back
+        let old = hlc(1_000, 50, "other");
+        let observed = Hlc::observe(None, &old, DEVICE);
+        assert!(observed.millis >= now);
+        assert_eq!(observed.counter, 0);
+    }
+
+    #[test]
+    fn readings_sort_the_same_as_text() {
+        let readings = [
+            hlc(999, 12, DEVICE),
+            hlc(1_000, 2, DEVICE),
+            hlc(1_000, 10, "0-first"),
+            hlc(1_000, 10, DEVICE),
+        ];
+        for pair in readings.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].to_string() < pair[1].to_string());
+        }
+
+        // Device ids keep their dashes
+        let reading = hlc(1_000, 10, DEVICE);
+        assert_eq!(reading.to_string().parse::<Hlc>().unwrap(), reading);
+        assert!("12-x".parse::<Hlc>().is_err());
+    }
+
+    #[test]
+    fn version_vectors_order_changes_that_build_on_each_other() {
+        let base = clock(&[("a", 2), ("b", 1)]);
+        assert_eq!(base.compare(&base.clone()), Some(Ordering::Equal));
+        assert_eq!(
+            clock(&[("a", 3), ("b", 1)]).compare(&base),
+            Some(Ordering::Greater)
+        );
+        // A device missing from one side counts as no changes
+        assert_eq!(clock(&[("a", 2)]).compare(&base), Some(Ordering::Less));
+        assert_eq!(
+            clock(&[("a", 2), ("b", 1), ("c", 1)]).compare(&base),
+            Some(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn version_vectors_detect_and_merge_concurrent_changes() {
+        let ours = clock(&[("a", 3), ("b", 1)]);
+        let theirs = clock(&[("a", 2), ("b", 2), ("c", 1)]);
+        assert_eq!(ours.compare(&theirs), None);
+        assert_eq!(theirs.compare(&ours), None);
+
+        let merged = ours.merge(&theirs);
+        assert_eq!(merged, clock(&[("a", 3), ("b", 2), ("c", 1)]));
+        assert_eq!(merged.compare(&ours), Some(Ordering::Greater));
+        assert_eq!(merged.compare(&theirs), Some(Ordering::Greater));
+    }
+
+    #[tokio::test]
+    async fn changes_reach_the_other_device() {
+        let devices = Devices::new().await;
+        devices
+            .a
+            .create_entry(&entry("walk", "Long walk"))
+            .await
+            .unwrap();
+
+        let report = devices.sync(&devices.a).await;
+        assert_eq!(report.exported, 1);
+        let report = devices.sync(&devices.b).await;
+        assert_eq!((report.imported, report.conflicts), (1, 0));
+        assert_eq!(
+            content(&devices.b, "walk").await.as_deref(),
+            Some("Long walk")
+        );
+
+        // Received changes are not written back out
+        assert_eq!(devices.sync(&devices.b).await.exported, 0);
+        assert_eq!(
+            SyncEngine::new(&devices.b).status().await.unwrap().pending,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_edits_keep_the_earlier_one_as_a_conflict_copy() {
+        let devices = Devices::new().await;
+        devices
+            .a
+            .create_entry(&entry("walk", "Long walk"))
+            .await
+            .unwrap();
+        devices.settle().await;
+
+        edit(&devices.a, "walk", "Long walk, edited on a").await;
+        next_millisecond().await;
+        edit(&devices.b, "walk", "Long walk, edited on b").await;
+
+        devices.sync(&devices.a).await;
+        let report = devices.sync(&devices.b).await;
+        assert_eq!(report.conflicts, 1);
+        let report = devices.sync(&devices.a).await;
+        assert_eq!(report.conflicts, 1);
+
+        for device in [&devices.a, &devices.b] {
+            assert_eq!(
+                content(device, "walk").await.as_deref(),
+                Some("Long walk, edited on b")
+            );
+            let conflicts = conflicts(device).await;
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!(conflicts[0].entry.content, "Long walk, edited on a");
+        }
+        // Both devices name the copy the same, so either can resolve it
+        assert_eq!(
+            conflicts(&devices.a).await[0].id,
+            conflicts(&devices.b).await[0].id
+        );
+    }
+
+    #[tokio::test]
+    async fn a_later_delete_keeps_the_edit_as_a_conflict_copy() {
+        let devices = Devices::new().await;
+        devices
+            .a
+            .create_entry(&entry("walk", "Long walk"))
+            .await
+            .unwrap();
+        devices.settle().await;
+
+        edit(&devices.b, "walk", "Long walk, edited on b").await;
+        next_millisecond().await;
+        devices.a.delete_entry("walk").await.unwrap();
+        devices.settle().await;
+
+        for device in [&devices.a, &devices.b] {
+            assert_eq!(content(device, "walk").await, None);
+            let conflicts = conflicts(device).await;
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!(conflicts[0].entry.content, "Long walk, edited on b");
+        }
+    }
+
+    #[tokio::test]
+    async fn a_later_edit_brings_a_deleted_entry_back() {
+        let devices = Devices::new().await;
+        devices
+            .a
+            .create_entry(&entry("walk", "Long walk"))
+            .await
+            .unwrap();
+        devices.settle().await;
+
+        devices.a.delete_entry("walk").await.unwrap();
+        next_millisecond().await;
+        edit(&devices.b, "walk", "Long walk, edited on b").await;
+        devices.settle().await;
+
+        for device in [&devices.a, &devices.b] {
+            assert_eq!(
+                content(device, "walk").await.as_deref(),
+                Some("Long walk, edited on b")
+            );
+            // The losing delete has nothing to keep
+            assert!(conflicts(device).await.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn replaying_a_log_changes_nothing() {
+        let devices = Devices::new().await;
+        devices
+            .a
+            .create_entry(&entry("walk", "Long walk"))
+            .await
+            .unwrap();
+        devices.settle().await;
+        edit(&devices.a, "walk", "Long walk, edited on a").await;
+        devices.a.delete_entry("walk").await.unwrap();
+        devices
+            .a
+            .create_entry(&entry("swim", "Cold lake"))
+            .await
+            .unwrap();
+        devices.settle().await;
+        let before = devices.b.list_all_entries().await.unwrap();
+
+        // Forget how far a's log was read, as if it had been replaced
+        sqlx::query("DELETE FROM sync_peers")
+            .execute(devices.b.pool())
+            .await
+            .unwrap();
+        let report = devices.sync(&devices.b).await;
+        assert_eq!((report.imported, report.conflicts), (0, 0));
+        assert!(report.failed.is_empty());
+
+        let after = devices.b.list_all_entries().await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&after).unwrap(),
+            serde_json::to_value(&before).unwrap()
+        );
+        assert_eq!(content(&devices.b, "walk").await, None);
+        assert_eq!(
+            content(&devices.b, "swim").await.as_deref(),
+            Some("Cold lake")
+        );
+    }
+
+    #[tokio::test]
+    async fn resolving_a_conflict_on_one_device_clears_it_on_the_other() {
+        let devices = Devices::new().await;
+        devices
+            .a
+            .create_entry(&entry("walk", "Long walk"))
+            .await
+            .unwrap();
+        devices.settle().await;
+        edit(&devices.a, "walk", "Long walk, edited on a").await;
+        next_millisecond().await;
+        edit(&devices.b, "walk", "Long walk, edited on b").await;
+        devices.settle().await;
+
+        let conflict = conflicts(&devices.b).await.remove(0);
+        let kept = SyncEngine::new(&devices.b)
+            .resolve_conflict(&conflict.id, ConflictResolution::KeepBoth)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(kept.id, "walk");
+        assert_eq!(kept.content, "Long walk, edited on a");
+        assert!(conflicts(&devices.b).await.is_empty());
+
+        devices.settle().await;
+        assert!(conflicts(&devices.a).await.is_empty());
+        assert_eq!(
+            content(&devices.a, &kept.id).await.as_deref(),
+            Some("Long walk, edited on a")
+        );
+        assert_eq!(
+            content(&devices.a, "walk").await.as_deref(),
+            Some("Long walk, edited on b")
+        );
+    }
+
+    #[tokio::test]
+    async fn using_the_copy_replaces_the_entry_everywhere() {
+        let devices = Devices::new().await;
+        devices
+            .a
+            .create_entry(&entry("walk", "Long walk"))
+            .await
+            .unwrap();
+        devices.settle().await;
+        edit(&devices.a, "walk", "Long walk, edited on a").await;
+        next_millisecond().await;
+        edit(&devices.b, "walk", "Long walk, edited on b").await;
+        devices.settle().await;
+
+        let conflict = conflicts(&devices.a).await.remove(0);
+        SyncEngine::new(&devices.a)
+            .resolve_conflict(&conflict.id, ConflictResolution::UseCopy)
+            .await
+            .unwrap();
+        devices.settle().await;
+
+        for device in [&devices.a, &devices.b] {
+            assert_eq!(
+                content(device, "walk").await.as_deref(),
+                Some("Long walk, edited on a")
+            );
+            assert!(conflicts(device).await.is_empty());
+        }
+    }
+
+    #[test]
+    fn only_device_logs_are_read() {
+        let folder = Path::new("/sync");
+        assert_eq!(
+            peer_id(&folder.join(format!("{}.jsonl", DEVICE))).as_deref(),
+            Some(DEVICE)
+        );
+        assert_eq!(
+            peer_id(&folder.join(format!("{}.sync-conflict-20260101.jsonl", DEVICE))),
+            None
+        );
+        assert_eq!(peer_id(&folder.join(format!("{}.txt", DEVICE))), None);
+    }
+}
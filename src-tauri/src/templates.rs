@@ -0,0 +1,501 @@
+/**
+ * Entry templates for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Built-in and user-defined templates with default tags, mood and privacy
+ * - Filling {{variables}} such as the date or the last entry's title
+ * - A prompt of the day, optionally written by the local AI model
+ * - Creating entries from a template
+ */
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::ai_service::ChatRequest;
+use crate::database::{format_timestamp, parse_timestamp, Database, JournalEntry};
+use crate::moods::{self, MoodRegistry};
+use crate::settings::SettingsStore;
+use crate::storage::EntryFilter;
+
+/// Variables a template can use, written as `{{name}}`
+pub const VARIABLES: &[&str] = &[
+    "date",
+    "time",
+    "weekday",
+    "month",
+    "year",
+    "week",
+    "last_entry_title",
+    "prompt_of_the_day",
+];
+
+const PRIVACY_LEVELS: &[&str] = &["private", "public", "friends"];
+
+/// Used for {{prompt_of_the_day}} when no AI prompt was requested or available
+const PROMPTS: &[&str] = &[
+    "What made today different from yesterday?",
+    "What are you looking forward to this week?",
+    "Who made a difference to your day, and how?",
+    "What is something you learned recently?",
+    "What drained your energy today, and what restored it?",
+    "What would you like to remember about today a year from now?",
+    "What is a small win you have not celebrated yet?",
+    "What are you avoiding, and why?",
+    "Describe a moment today when you felt at ease.",
+    "What would make tomorrow a good day?",
+    "What is on your mind that you have not said out loud?",
+    "What did you notice today that you usually overlook?",
+    "Which habit served you well this week?",
+    "What is a question you keep coming back to?",
+    "What are you grateful for that you took for granted last year?",
+    "What would you tell yourself from a month ago?",
+    "Where did you spend most of your attention today?",
+    "What is something you are proud of, however small?",
+    "What surprised you recently?",
+    "What does rest look like for you right now?",
+    "What conversation would you like to have soon?",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryTemplate {
+    pub id: String,
+    pub name: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub mood: Option<String>,
+    pub mood_intensity: Option<f64>,
+    pub privacy: String,
+    /// The known variables that appear in the title or content
+    pub variables: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl EntryTemplate {
+    pub fn uses(&self, variable: &str) -> bool {
+        self.variables.iter().any(|v| v == variable)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateInput {
+    pub name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub mood: Option<String>,
+    pub mood_intensity: Option<f64>,
+    pub privacy: Option<String>,
+}
+
+pub struct TemplateStore<'a> {
+    database: &'a Database,
+}
+
+impl<'a> TemplateStore<'a> {
+    pub fn new(database: &'a Database) -> Self {
+        TemplateStore { database }
+    }
+
+    pub async fn list(&self) -> Result<Vec<EntryTemplate>> {
+        let rows = sqlx::query("SELECT * FROM entry_templates ORDER BY name COLLATE NOCASE")
+            .fetch_all(self.database.pool())
+            .await
+            .context("Failed to list entry templates")?;
+
+        rows.iter().map(template_from_row).collect()
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<EntryTemplate>> {
+        let row = sqlx::query("SELECT * FROM entry_templates WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await
+            .context("Failed to fetch entry template")?;
+
+        row.as_ref().map(template_from_row).transpose()
+    }
+
+    pub async fn create(&self, input: &TemplateInput) -> Result<EntryTemplate> {
+        let input = self.normalize(input).await?;
+        let id = Uuid::new_v4().to_string();
+        let now = format_timestamp(&Utc::now());
+
+        sqlx::query(
+            r#"
+            INSERT INTO entry_templates (id, name, title, content, tags, mood, mood_intensity, privacy, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.name)
+        .bind(&input.title)
+        .bind(&input.content)
+        .bind(serde_json::to_string(&input.tags)?)
+        .bind(&input.mood)
+        .bind(input.mood_intensity)
+        .bind(&input.privacy)
+        .bind(&now)
+        .bind(&now)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to create entry template")?;
+
+        self.get(&id)
+            .await?
+            .context("Template not found after creation")
+    }
+
+    pub async fn update(&self, id: &str, input: &TemplateInput) -> Result<EntryTemplate> {
+        let input = self.normalize(input).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE entry_templates
+            SET name = ?, title = ?, content = ?, tags = ?, mood = ?, mood_intensity = ?, privacy = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&input.name)
+        .bind(&input.title)
+        .bind(&input.content)
+        .bind(serde_json::to_string(&input.tags)?)
+        .bind(&input.mood)
+        .bind(input.mood_intensity)
+        .bind(&input.privacy)
+        .bind(format_timestamp(&Utc::now()))
+        .bind(id)
+        .execute(self.database.pool())
+        .await
+        .context("Failed to update entry template")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Template not found: {}", id));
+        }
+
+        self.get(id)
+            .await?
+            .context("Template not found after update")
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM entry_templates WHERE id = ?")
+            .bind(id)
+            .execute(self.database.pool())
+            .await
+            .context("Failed to delete entry template")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Template not found: {}", id));
+        }
+
+        Ok(())
+    }
+
+    /// Values for every variable on `entry_date`; `prompt` replaces the built-in prompt of the day
+    pub async fn values(
+        &self,
+        entry_date: DateTime<FixedOffset>,
+        prompt: Option<String>,
+    ) -> Result<HashMap<&'static str, String>> {
+        let last_entry_title = self
+            .database
+            .list_entries(Some(1), None, &EntryFilter::default())
+            .await?
+            .into_iter()
+            .next()
+            .map(|entry| entry.title)
+            .unwrap_or_default();
+        let prompt =
+            prompt.unwrap_or_else(|| prompt_of_the_day(entry_date.date_naive()).to_string());
+
+        Ok(HashMap::from([
+            ("date", entry_date.format("%Y-%m-%d").to_string()),
+            ("time", entry_date.format("%H:%M").to_string()),
+            ("weekday", entry_date.format("%A").to_string()),
+            ("month", entry_date.format("%B").to_string()),
+            ("year", entry_date.format("%Y").to_string()),
+            ("week", entry_date.iso_week().week().to_string()),
+            ("last_entry_title", last_entry_title),
+            ("prompt_of_the_day", prompt),
+        ]))
+    }
+
+    /// Render a template into a new entry and save it
+    pub async fn create_entry(
+        &self,
+        id: &str,
+        entry_date: Option<DateTime<FixedOffset>>,
+        prompt: Option<String>,
+    ) -> Result<JournalEntry> {
+        let template = self
+            .get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Template not found: {}", id))?;
+        let entry_date = match entry_date {
+            Some(entry_date) => entry_date,
+            None => SettingsStore::new(self.database).timezone().await?.now(),
+        };
+        let values = self.values(entry_date, prompt).await?;
+
+        // The mood may have been deleted since the template was saved
+        let mood = match &template.mood {
+            Some(mood) => MoodRegistry::new(self.database).resolve(mood).await?,
+            None => None,
+        };
+
+        let entry = JournalEntry {
+            id: Uuid::new_v4().to_string(),
+            title: render(&template.title, &values),
+            content: render(&template.content, &values),
+            tags: template.tags,
+            mood_intensity: mood.as_ref().and(template.mood_intensity),
+            mood,
+            privacy: template.privacy,
+            source: None,
+            source_id: None,
+            source_url: None,
+            metadata: Some(serde_json::json!({ "template_id": template.id })),
+            location: None,
+            pinned: false,
+            favorite: false,
+            archived: false,
+            locked: false,
+            entry_date,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        self.database.create_entry(&entry).await?;
+
+        Ok(entry)
+    }
+
+    /// Trim and validate input, registering an unknown mood like entries do
+    async fn normalize(&self, input: &TemplateInput) -> Result<TemplateInput> {
+        let name = input.name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Template name cannot be empty"));
+        }
+
+        let privacy = input.privacy.as_deref().unwrap_or("private");
+        if !PRIVACY_LEVELS.contains(&privacy) {
+            return Err(anyhow::anyhow!("Unknown privacy level: {}", privacy));
+        }
+
+        moods::validate_intensity(input.mood_intensity)?;
+        let mood = MoodRegistry::new(self.database)
+            .resolve_or_create(input.mood.as_deref())
+            .await?;
+
+        Ok(TemplateInput {
+            name: name.to_string(),
+            title: input.title.clone(),
+            content: input.content.clone(),
+            tags: input
+                .tags
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            mood_intensity: mood.as_ref().and(input.mood_intensity),
+            mood,
+            privacy: Some(privacy.to_string()),
+        })
+    }
+}
+
+/// Replace every known `{{variable}}`; unknown ones are left as written
+pub fn render(text: &str, values: &HashMap<&'static str, String>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+
+        output.push_str(&rest[..start]);
+        match values.get(after[..end].trim()) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// The known variables used in `text`, in order of first use
+pub fn variables_in(text: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+
+        let name = after[..end].trim();
+        if VARIABLES.contains(&name) && !found.iter().any(|f| f == name) {
+            found.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+
+    found
+}
+
+/// The same prompt all day, a different one each day
+pub fn prompt_of_the_day(date: NaiveDate) -> &'static str {
+    PROMPTS[date.num_days_from_ce().unsigned_abs() as usize % PROMPTS.len()]
+}
+
+/// Ask the AI model for a journaling prompt suited to the template
+pub fn prompt_request(template: &EntryTemplate, date: NaiveDate) -> ChatRequest {
+    ChatRequest {
+        message: format!(
+            "Write one short, open-ended journaling prompt for a \"{}\" entry on {}. Reply with the prompt only.",
+            template.name,
+            date.format("%A, %B %-d")
+        ),
+        context: None,
+        model: None,
+    }
+}
+
+/// The prompt in an AI response, without quotes or a leading label
+pub fn clean_prompt(response: &str) -> Option<String> {
+    let line = response
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    let line = line
+        .strip_prefix("Prompt:")
+        .map(str::trim)
+        .unwrap_or(line)
+        .trim_matches(|c| c == '"' || c == '\u{201c}' || c == '\u{201d}')
+        .trim();
+
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+fn template_from_row(row: &SqliteRow) -> Result<EntryTemplate> {
+    let title: String = row.get("title");
+    let content: String = row.get("content");
+    let mut variables = variables_in(&title);
+    for variable in variables_in(&content) {
+        if !variables.contains(&variable) {
+            variables.push(variable);
+        }
+    }
+
+    Ok(EntryTemplate {
+        id: row.get("id"),
+        name: row.get("name"),
+        tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+        mood: row.get("mood"),
+        mood_intensity: row.get("mood_intensity"),
+        privacy: row.get("privacy"),
+        variables,
+        created_at: parse_timestamp(&row.get::<String, _>("created_at"))?,
+        updated_at: parse_timestamp(&row.get::<String, _>("updated_at"))?,
+        title,
+        content,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempJournal;
+
+    fn values() -> HashMap<&'static str, String> {
+        HashMap::from([
+            ("date", "2026-03-14".to_string()),
+            ("weekday", "Saturday".to_string()),
+        ])
+    }
+
+    #[test]
+    fn render_fills_known_variables() {
+        assert_eq!(
+            render("Standup {{date}} ({{ weekday }})", &values()),
+            "Standup 2026-03-14 (Saturday)"
+        );
+    }
+
+    #[test]
+    fn render_leaves_unknown_and_unterminated_variables() {
+        assert_eq!(
+            render("{{mood}} on {{date}}", &values()),
+            "{{mood}} on 2026-03-14"
+        );
+        assert_eq!(
+            render("{{date}} then {{weekday", &values()),
+            "2026-03-14 then {{weekday"
+        );
+        assert_eq!(render("{{", &values()), "{{");
+    }
+
+    #[test]
+    fn variables_in_lists_known_variables_once() {
+        assert_eq!(
+            variables_in("{{week}} {{date}} {{ week }} {{unknown}}"),
+            ["week", "date"]
+        );
+        assert_eq!(variables_in("{{date}} and {{year"), ["date"]);
+        assert!(variables_in("no variables").is_empty());
+    }
+
+    #[test]
+    fn clean_prompt_strips_labels_and_quotes() {
+        assert_eq!(
+            clean_prompt("\n  Prompt: \"What surprised you today?\"\nMore text").as_deref(),
+            Some("What surprised you today?")
+        );
+        assert_eq!(
+            clean_prompt("\u{201c}Who helped you this week?\u{201d}").as_deref(),
+            Some("Who helped you this week?")
+        );
+        assert_eq!(clean_prompt("  \n\n"), None);
+        assert_eq!(clean_prompt("Prompt: \"\""), None);
+    }
+
+    #[tokio::test]
+    async fn built_in_templates_have_rfc3339_timestamps() {
+        let database = TempJournal::new().await;
+
+        let created_at: String =
+            sqlx::query_scalar("SELECT created_at FROM entry_templates WHERE id = 'standup'")
+                .fetch_one(database.pool())
+                .await
+                .unwrap();
+        assert!(DateTime::parse_from_rfc3339(&created_at).is_ok());
+
+        let id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO entry_templates (id, name) VALUES (?, 'Defaults')")
+            .bind(&id)
+            .execute(database.pool())
+            .await
+            .unwrap();
+        let updated_at: String =
+            sqlx::query_scalar("SELECT updated_at FROM entry_templates WHERE id = ?")
+                .bind(&id)
+                .fetch_one(database.pool())
+                .await
+                .unwrap();
+        assert!(DateTime::parse_from_rfc3339(&updated_at).is_ok());
+    }
+}
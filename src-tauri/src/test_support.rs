@@ -0,0 +1,235 @@
+/**
+ * Shared fixtures for unit tests
+ *
+ * This module handles:
+ * - Journal entries with defaults for the fields a test doesn't care about
+ * - Throwaway journals whose folder is removed when the test ends
+ * - A local HTTP server that answers with canned responses and records requests
+ */
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::database::{Database, JournalEntry};
+
+/// A private entry with no title, tags or source, written at `entry_date` (RFC 3339)
+pub fn entry_at(id: &str, content: &str, entry_date: &str) -> JournalEntry {
+    let entry_date = DateTime::parse_from_rfc3339(entry_date).unwrap();
+    JournalEntry {
+        id: id.to_string(),
+        title: String::new(),
+        content: content.to_string(),
+        tags: Vec::new(),
+        mood: None,
+        mood_intensity: None,
+        privacy: "private".to_string(),
+        source: None,
+        source_id: None,
+        source_url: None,
+        metadata: None,
+        location: None,
+        pinned: false,
+        favorite: false,
+        archived: false,
+        locked: false,
+        entry_date,
+        created_at: entry_date.with_timezone(&Utc),
+        updated_at: entry_date.with_timezone(&Utc),
+    }
+}
+
+/// A private entry with no title, tags or source, written now
+pub fn entry(id: &str, content: &str) -> JournalEntry {
+    entry_at(id, content, &Utc::now().to_rfc3339())
+}
+
+/// A journal in a temporary folder of its own, deleted when it is dropped
+pub struct TempJournal {
+    database: Database,
+    dir: PathBuf,
+}
+
+impl TempJournal {
+    /// A journal kept in SQLite
+    pub async fn new() -> Self {
+        let dir = temp_dir();
+        let database = Database::new(dir.join("journal.db")).await.unwrap();
+        TempJournal { database, dir }
+    }
+
+    /// A journal kept as Markdown files in an `entries` folder
+    pub async fn markdown() -> Self {
+        let dir = temp_dir();
+        let database = Database::with_markdown_folder(dir.join("journal.db"), dir.join("entries"))
+            .await
+            .unwrap();
+        TempJournal { database, dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Deref for TempJournal {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.database
+    }
+}
+
+impl Drop for TempJournal {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("snapjournal-test-{}", Uuid::new_v4()))
+}
+
+/// A request as the mock server saw it
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Keyed by lowercase header name
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// The body as JSON, or null if it isn't any
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// What the mock server answers with
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn json(status: u16, body: &serde_json::Value) -> Self {
+        Response::new(status)
+            .with_header("Content-Type", "application/json")
+            .with_body(body.to_string())
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// An HTTP server on a local port that answers every request with `reply`
+/// and records what was asked; it stops with the test process
+pub struct MockServer {
+    /// `http://127.0.0.1:<port>`, without a trailing slash
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub fn start(reply: impl Fn(&Request) -> Response + Send + 'static) -> Self {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let seen = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let request = read_request(&stream);
+                seen.lock().unwrap().push(request.clone());
+                write_response(stream, reply(&request));
+            }
+        });
+
+        MockServer { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The requests seen so far, forgetting them
+    pub fn take_requests(&self) -> Vec<Request> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+fn read_request(stream: &std::net::TcpStream) -> Request {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .map(|length| length.parse().unwrap())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+
+    Request {
+        method,
+        path,
+        headers,
+        body,
+    }
+}
+
+fn write_response(mut stream: std::net::TcpStream, response: Response) {
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).unwrap();
+    stream.write_all(&response.body).unwrap();
+}
@@ -0,0 +1,1333 @@
+/**
+ * WebDAV sync for MyFace SnapJournal
+ *
+ * This module handles:
+ * - Keeping the WebDAV password and sync passphrase in the system's
+ *   credential store, with only the URL and username on disk
+ * - A small WebDAV client (PROPFIND, GET, PUT, MKCOL) with ETag conditions
+ * - Encrypting the change log before it leaves the device
+ * - Uploading and downloading the log in numbered chunks so an interrupted
+ *   sync picks up where it stopped
+ *
+ * The server sees the same per-device logs as a sync folder, cut into
+ * immutable chunks and sealed with a key derived from the passphrase. A
+ * local mirror of the logs is kept in the journal's data directory and
+ * merged by the folder sync engine. Any WebDAV server works, including
+ * Nextcloud or `rclone serve webdav` on localhost.
+ */
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::sync::{SyncEngine, SyncReport};
+
+const MANIFEST_FILE: &str = "sync.json";
+const STATE_FILE: &str = "webdav-state.json";
+const CHUNK_EXTENSION: &str = "chunk";
+/// Upper bound for one uploaded chunk; a chunk always ends on a full line
+const MAX_CHUNK_BYTES: usize = 256 * 1024;
+const KDF_ITERATIONS: u32 = 600_000;
+/// Service name the secrets are filed under in the system's credential store
+const KEYRING_SERVICE: &str = "MyFace SnapJournal";
+/// Encrypted into the manifest so a wrong passphrase fails before any data is read
+const PASSPHRASE_CHECK: &[u8] = b"myface-snapjournal-sync";
+
+/// Everything needed to reach the server and read the logs
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebDavCredentials {
+    /// Collection that holds this journal's logs
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Shared by every device syncing the journal; the server never sees it
+    pub passphrase: String,
+}
+
+/// The account as shown to the frontend, without secrets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebDavAccount {
+    pub url: String,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebDavStatus {
+    pub account: Option<WebDavAccount>,
+    /// Bytes of the local log not yet on the server
+    pub pending_upload_bytes: u64,
+    pub last_push_at: Option<DateTime<Utc>>,
+    pub last_pull_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl WebDavCredentials {
+    pub fn account(&self) -> WebDavAccount {
+        WebDavAccount {
+            url: self.url.clone(),
+            username: self.username.clone(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let url = reqwest::Url::parse(&self.url)
+            .with_context(|| format!("Invalid WebDAV URL: {}", self.url))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow::anyhow!("WebDAV URL must use http or https"));
+        }
+        if self.passphrase.chars().count() < 8 {
+            return Err(anyhow::anyhow!(
+                "Sync passphrase must be at least 8 characters"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The secret half of the credentials, kept in the system's credential store
+#[derive(Serialize, Deserialize)]
+struct Secrets {
+    password: String,
+    passphrase: String,
+}
+
+/// The account saved in `path`, without reading the secrets
+pub async fn load_account(path: &Path) -> Result<Option<WebDavAccount>> {
+    match read_credentials_file(path).await? {
+        Some(contents) => Ok(Some(
+            serde_json::from_value(contents).context("Invalid WebDAV credentials file")?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// The account saved in `path` together with its secrets
+pub async fn load_credentials(path: &Path, journal_id: &str) -> Result<Option<WebDavCredentials>> {
+    let Some(contents) = read_credentials_file(path).await? else {
+        return Ok(None);
+    };
+
+    // Files written before the credential store was used hold the secrets too
+    if let Ok(credentials) = serde_json::from_value::<WebDavCredentials>(contents.clone()) {
+        save_credentials(path, journal_id, &credentials).await?;
+        return Ok(Some(credentials));
+    }
+
+    let account: WebDavAccount =
+        serde_json::from_value(contents).context("Invalid WebDAV credentials file")?;
+    let secrets = read_secrets(journal_id).await?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "The WebDAV password is missing from the system keychain; add the account again"
+        )
+    })?;
+
+    Ok(Some(WebDavCredentials {
+        url: account.url,
+        username: account.username,
+        password: secrets.password,
+        passphrase: secrets.passphrase,
+    }))
+}
+
+/// Save the secrets in the system's credential store and the account in a file
+/// only the current user can read
+pub async fn save_credentials(
+    path: &Path,
+    journal_id: &str,
+    credentials: &WebDavCredentials,
+) -> Result<()> {
+    write_secrets(
+        journal_id,
+        &Secrets {
+            password: credentials.password.clone(),
+            passphrase: credentials.passphrase.clone(),
+        },
+    )
+    .await?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .context("Failed to create credentials directory")?;
+    }
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options
+        .open(path)
+        .await
+        .context("Failed to open WebDAV credentials file")?;
+    file.write_all(serde_json::to_string(&credentials.account())?.as_bytes())
+        .await
+        .context("Failed to write WebDAV credentials")?;
+    file.sync_all().await?;
+
+    Ok(())
+}
+
+pub async fn delete_credentials(path: &Path, journal_id: &str) -> Result<()> {
+    delete_secrets(journal_id).await?;
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).context("Failed to delete WebDAV credentials"),
+    }
+}
+
+async fn read_credentials_file(path: &Path) -> Result<Option<serde_json::Value>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => Ok(Some(
+            serde_json::from_str(&contents).context("Invalid WebDAV credentials file")?,
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).context("Failed to read WebDAV credentials"),
+    }
+}
+
+fn keyring_entry(journal_id: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, &format!("webdav:{}", journal_id))
+        .context("Failed to open the system keychain")
+}
+
+// The credential store may block on a prompt or a D-Bus call, so it is used
+// from a blocking thread
+
+async fn read_secrets(journal_id: &str) -> Result<Option<Secrets>> {
+    let entry = keyring_entry(journal_id)?;
+    tokio::task::spawn_blocking(move || match entry.get_password() {
+        Ok(secrets) => Ok(Some(
+            serde_json::from_str(&secrets).context("Invalid WebDAV secrets in the keychain")?,
+        )),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e).context("Failed to read the WebDAV password from the system keychain"),
+    })
+    .await?
+}
+
+async fn write_secrets(journal_id: &str, secrets: &Secrets) -> Result<()> {
+    let entry = keyring_entry(journal_id)?;
+    let secrets = serde_json::to_string(secrets)?;
+    tokio::task::spawn_blocking(move || {
+        entry
+            .set_password(&secrets)
+            .context("Failed to save the WebDAV password in the system keychain")
+    })
+    .await?
+}
+
+async fn delete_secrets(journal_id: &str) -> Result<()> {
+    let entry = keyring_entry(journal_id)?;
+    tokio::task::spawn_blocking(move || match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e).context("Failed to remove the WebDAV password from the system keychain"),
+    })
+    .await?
+}
+
+/// Upload and download progress, kept next to the mirrored logs
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    url: String,
+    device_id: Option<String>,
+    /// Bytes of this device's log already on the server
+    uploaded_offset: u64,
+    next_chunk: u64,
+    peers: HashMap<String, PeerState>,
+    manifest: Option<Manifest>,
+    manifest_etag: Option<String>,
+    last_push_at: Option<DateTime<Utc>>,
+    last_pull_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PeerState {
+    next_chunk: u64,
+    /// ETag of the peer's collection when it was last read in full
+    etag: Option<String>,
+}
+
+/// Key derivation parameters shared by every device, stored on the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    iterations: u32,
+    salt: String,
+    check: String,
+}
+
+pub async fn status(mirror: &Path, account: Option<WebDavAccount>) -> Result<WebDavStatus> {
+    let state = load_state(mirror).await?;
+    let mut status = WebDavStatus {
+        account,
+        last_push_at: state.last_push_at,
+        last_pull_at: state.last_pull_at,
+        last_error: state.last_error,
+        ..Default::default()
+    };
+
+    // Only count against the server the state belongs to
+    let same_server = status.account.as_ref().map(|a| collection_url(&a.url)) == Some(state.url);
+    if let (true, Some(device_id)) = (same_server, &state.device_id) {
+        if let Ok(metadata) = tokio::fs::metadata(log_path(mirror, device_id)).await {
+            status.pending_upload_bytes = metadata.len().saturating_sub(state.uploaded_offset);
+        }
+    }
+
+    Ok(status)
+}
+
+/// The mirrored log of a device
+fn log_path(mirror: &Path, device_id: &str) -> PathBuf {
+    mirror.join(format!("{}.jsonl", device_id))
+}
+
+pub struct WebDavSync<'a> {
+    database: &'a Database,
+    client: WebDavClient,
+    mirror: PathBuf,
+    passphrase: String,
+}
+
+impl<'a> WebDavSync<'a> {
+    pub fn new(
+        database: &'a Database,
+        credentials: &WebDavCredentials,
+        mirror: PathBuf,
+    ) -> Result<Self> {
+        Ok(WebDavSync {
+            database,
+            client: WebDavClient::new(credentials)?,
+            mirror,
+            passphrase: credentials.passphrase.clone(),
+        })
+    }
+
+    /// Check that the server accepts the credentials, creating the collection if needed
+    pub async fn check(&self) -> Result<()> {
+        self.client.mkcol("").await?;
+        self.client.propfind("", 0).await.map(|_| ())
+    }
+
+    /// Download new chunks, merge them, then upload this device's changes
+    pub async fn sync(&self) -> Result<SyncReport> {
+        tokio::fs::create_dir_all(&self.mirror)
+            .await
+            .context("Failed to create WebDAV mirror directory")?;
+
+        let device_id = SyncEngine::new(self.database).device_id().await?;
+        let mut state = load_state(&self.mirror).await?;
+        if state.url != self.client.base.as_str() || state.device_id.as_deref() != Some(&device_id)
+        {
+            self.reset_mirror(&mut state, &device_id).await?;
+        }
+
+        let result = self.sync_with_state(&mut state, &device_id).await;
+        state.last_error = result.as_ref().err().map(|e| format!("{:#}", e));
+        save_state(&self.mirror, &state).await?;
+
+        result
+    }
+
+    async fn sync_with_state(&self, state: &mut SyncState, device_id: &str) -> Result<SyncReport> {
+        let key = self.key(state).await?;
+
+        self.pull(state, &key, device_id).await?;
+        state.last_pull_at = Some(Utc::now());
+        save_state(&self.mirror, state).await?;
+
+        let report = SyncEngine::new(self.database).sync(&self.mirror).await?;
+
+        self.push(state, &key, device_id).await?;
+        state.last_push_at = Some(Utc::now());
+
+        Ok(report)
+    }
+
+    /// Start over against a different server
+    async fn reset_mirror(&self, state: &mut SyncState, device_id: &str) -> Result<()> {
+        let own_log = log_path(&self.mirror, device_id);
+        let mut logs = tokio::fs::read_dir(&self.mirror).await?;
+        while let Some(log) = logs.next_entry().await? {
+            let path = log.path();
+            // Our own log is uploaded again from the start; peer logs are downloaded again
+            if path.extension().and_then(|e| e.to_str()) == Some("jsonl") && path != own_log {
+                tokio::fs::remove_file(&path).await?;
+            }
+        }
+
+        *state = SyncState {
+            url: self.client.base.to_string(),
+            device_id: Some(device_id.to_string()),
+            ..Default::default()
+        };
+        Ok(())
+    }
+
+    /// The log encryption key, creating the server's manifest on first use
+    async fn key(&self, state: &mut SyncState) -> Result<LessSafeKey> {
+        let manifest = match self
+            .client
+            .get(MANIFEST_FILE, state.manifest_etag.as_deref())
+            .await?
+        {
+            Fetched::NotModified => state.manifest.clone(),
+            Fetched::Missing => None,
+            Fetched::Body(bytes, etag) => {
+                let manifest: Manifest =
+                    serde_json::from_slice(&bytes).context("Invalid sync manifest on server")?;
+                state.manifest_etag = etag;
+                Some(manifest)
+            }
+        };
+
+        let manifest = match manifest {
+            Some(manifest) => manifest,
+            None => self.create_manifest(state).await?,
+        };
+        if manifest.version != 1 {
+            return Err(anyhow::anyhow!(
+                "Unsupported sync manifest version {}",
+                manifest.version
+            ));
+        }
+
+        let salt = base64::engine::general_purpose::STANDARD
+            .decode(&manifest.salt)
+            .context("Invalid salt in sync manifest")?;
+        let key = derive_key(self.passphrase.clone(), salt, manifest.iterations).await?;
+
+        let check = base64::engine::general_purpose::STANDARD
+            .decode(&manifest.check)
+            .context("Invalid check in sync manifest")?;
+        if open(&key, MANIFEST_FILE, &check).ok().as_deref() != Some(PASSPHRASE_CHECK) {
+            return Err(anyhow::anyhow!(
+                "The sync passphrase does not match the one used by your other devices"
+            ));
+        }
+
+        state.manifest = Some(manifest);
+        Ok(key)
+    }
+
+    async fn create_manifest(&self, state: &mut SyncState) -> Result<Manifest> {
+        let mut salt = [0u8; 16];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| anyhow::anyhow!("Failed to generate salt"))?;
+        let key = derive_key(self.passphrase.clone(), salt.to_vec(), KDF_ITERATIONS).await?;
+
+        let manifest = Manifest {
+            version: 1,
+            iterations: KDF_ITERATIONS,
+            salt: base64::engine::general_purpose::STANDARD.encode(salt),
+            check: base64::engine::general_purpose::STANDARD.encode(seal(
+                &key,
+                MANIFEST_FILE,
+                PASSPHRASE_CHECK,
+            )?),
+        };
+
+        match self
+            .client
+            .put_new(MANIFEST_FILE, serde_json::to_vec(&manifest)?)
+            .await?
+        {
+            Some(etag) => {
+                state.manifest_etag = etag;
+                Ok(manifest)
+            }
+            // Another device got there first; use theirs
+            None => match self.client.get(MANIFEST_FILE, None).await? {
+                Fetched::Body(bytes, etag) => {
+                    state.manifest_etag = etag;
+                    serde_json::from_slice(&bytes).context("Invalid sync manifest on server")
+                }
+                _ => Err(anyhow::anyhow!("Sync manifest disappeared from the server")),
+            },
+        }
+    }
+
+    /// Append new chunks from every other device to the mirrored logs
+    async fn pull(&self, state: &mut SyncState, key: &LessSafeKey, device_id: &str) -> Result<()> {
+        for (name, etag) in self.client.list_collections("").await? {
+            if name == device_id || Uuid::parse_str(&name).is_err() {
+                continue;
+            }
+
+            let peer = state.peers.entry(name.clone()).or_default();
+            if etag.is_some() && peer.etag == etag {
+                continue;
+            }
+
+            let mut chunks: Vec<u64> = self
+                .client
+                .list_files(&format!("{}/", name))
+                .await?
+                .iter()
+                .filter_map(|file| file.strip_suffix(&format!(".{}", CHUNK_EXTENSION)))
+                .filter_map(|seq| seq.parse().ok())
+                .filter(|seq| *seq >= peer.next_chunk)
+                .collect();
+            chunks.sort_unstable();
+
+            let log = log_path(&self.mirror, &name);
+            let mut complete = true;
+            for seq in chunks {
+                // Chunks are uploaded in order; a gap is still on its way
+                if seq != peer.next_chunk {
+                    complete = false;
+                    break;
+                }
+
+                let path = chunk_path(&name, seq);
+                let bytes = match self.client.get(&path, None).await? {
+                    Fetched::Body(bytes, _) => bytes,
+                    _ => {
+                        complete = false;
+                        break;
+                    }
+                };
+                let lines = open(key, &path, &bytes)
+                    .with_context(|| format!("Failed to decrypt {}", path))?;
+
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&log)
+                    .await
+                    .with_context(|| format!("Failed to open {:?}", log))?;
+                file.write_all(&lines).await?;
+                file.sync_all().await?;
+
+                peer.next_chunk = seq + 1;
+            }
+
+            if complete {
+                peer.etag = etag;
+            }
+            save_state(&self.mirror, state).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Upload the part of this device's log the server doesn't have yet
+    async fn push(&self, state: &mut SyncState, key: &LessSafeKey, device_id: &str) -> Result<()> {
+        let mut file = match tokio::fs::File::open(log_path(&self.mirror, device_id)).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e).context("Failed to open local change log"),
+        };
+        if file.metadata().await?.len() <= state.uploaded_offset {
+            return Ok(());
+        }
+
+        file.seek(std::io::SeekFrom::Start(state.uploaded_offset))
+            .await?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).await?;
+
+        self.client.mkcol(&format!("{}/", device_id)).await?;
+
+        let mut rest = &bytes[..];
+        while !rest.is_empty() {
+            let chunk = next_chunk(rest);
+            let path = chunk_path(device_id, state.next_chunk);
+
+            // A chunk that already exists was uploaded before the response got lost
+            self.client.put_new(&path, seal(key, &path, chunk)?).await?;
+
+            state.uploaded_offset += chunk.len() as u64;
+            state.next_chunk += 1;
+            save_state(&self.mirror, state).await?;
+            rest = &rest[chunk.len()..];
+        }
+
+        Ok(())
+    }
+}
+
+fn chunk_path(device_id: &str, seq: u64) -> String {
+    format!("{}/{:010}.{}", device_id, seq, CHUNK_EXTENSION)
+}
+
+/// The longest run of whole lines that fits in a chunk
+fn next_chunk(bytes: &[u8]) -> &[u8] {
+    if bytes.len() <= MAX_CHUNK_BYTES {
+        return bytes;
+    }
+
+    match bytes[..MAX_CHUNK_BYTES].iter().rposition(|b| *b == b'\n') {
+        Some(end) => &bytes[..=end],
+        // A single line longer than a chunk goes up on its own
+        None => match bytes.iter().position(|b| *b == b'\n') {
+            Some(end) => &bytes[..=end],
+            None => bytes,
+        },
+    }
+}
+
+async fn load_state(mirror: &Path) -> Result<SyncState> {
+    match tokio::fs::read_to_string(mirror.join(STATE_FILE)).await {
+        Ok(contents) => serde_json::from_str(&contents).context("Invalid WebDAV sync state"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(SyncState::default()),
+        Err(e) => Err(e).context("Failed to read WebDAV sync state"),
+    }
+}
+
+async fn save_state(mirror: &Path, state: &SyncState) -> Result<()> {
+    let path = mirror.join(STATE_FILE);
+    let temp_path = mirror.join(format!(".{}.tmp", STATE_FILE));
+    tokio::fs::write(&temp_path, serde_json::to_vec_pretty(state)?)
+        .await
+        .context("Failed to write WebDAV sync state")?;
+    tokio::fs::rename(&temp_path, &path)
+        .await
+        .context("Failed to write WebDAV sync state")?;
+    Ok(())
+}
+
+async fn derive_key(passphrase: String, salt: Vec<u8>, iterations: u32) -> Result<LessSafeKey> {
+    let iterations =
+        NonZeroU32::new(iterations).context("Invalid iteration count in sync manifest")?;
+
+    // Deliberately slow, so keep it off the async workers
+    tokio::task::spawn_blocking(move || {
+        let mut key = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            passphrase.as_bytes(),
+            &mut key,
+        );
+        UnboundKey::new(&CHACHA20_POLY1305, &key)
+            .map(LessSafeKey::new)
+            .map_err(|_| anyhow::anyhow!("Failed to create sync key"))
+    })
+    .await?
+}
+
+/// Encrypt with a random nonce, binding the ciphertext to its path on the server
+fn seal(key: &LessSafeKey, path: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow::anyhow!("Failed to generate nonce"))?;
+
+    let mut sealed = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(path.as_bytes()),
+        &mut sealed,
+    )
+    .map_err(|_| anyhow::anyhow!("Failed to encrypt {}", path))?;
+
+    let mut output = nonce.to_vec();
+    output.extend_from_slice(&sealed);
+    Ok(output)
+}
+
+fn open(key: &LessSafeKey, path: &str, data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        return Err(anyhow::anyhow!("{} is too short", path));
+    }
+
+    let (nonce, sealed) = data.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| anyhow::anyhow!("Invalid nonce in {}", path))?;
+    let mut sealed = sealed.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(path.as_bytes()), &mut sealed)
+        .map_err(|_| anyhow::anyhow!("{} could not be decrypted", path))?;
+
+    Ok(plaintext.to_vec())
+}
+
+enum Fetched {
+    Body(Vec<u8>, Option<String>),
+    NotModified,
+    Missing,
+}
+
+/// Just enough WebDAV for the sync logs
+struct WebDavClient {
+    http: reqwest::Client,
+    base: reqwest::Url,
+    username: String,
+    password: String,
+}
+
+impl WebDavClient {
+    fn new(credentials: &WebDavCredentials) -> Result<Self> {
+        Ok(WebDavClient {
+            http: reqwest::Client::new(),
+            base: reqwest::Url::parse(&collection_url(&credentials.url))
+                .with_context(|| format!("Invalid WebDAV URL: {}", credentials.url))?,
+            username: credentials.username.clone(),
+            password: credentials.password.clone(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<reqwest::RequestBuilder> {
+        let url = self
+            .base
+            .join(path)
+            .with_context(|| format!("Invalid WebDAV path: {}", path))?;
+        Ok(self
+            .http
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password)))
+    }
+
+    /// GET, returning `NotModified` when the resource still has `etag`
+    async fn get(&self, path: &str, etag: Option<&str>) -> Result<Fetched> {
+        let mut request = self.request(Method::GET, path)?;
+        if let Some(etag) = etag {
+            request = request.header("If-None-Match", etag);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to download {}", path))?;
+        match response.status() {
+            StatusCode::NOT_MODIFIED => Ok(Fetched::NotModified),
+            StatusCode::NOT_FOUND => Ok(Fetched::Missing),
+            status if status.is_success() => {
+                let etag = response_etag(&response);
+                Ok(Fetched::Body(response.bytes().await?.to_vec(), etag))
+            }
+            status => Err(anyhow::anyhow!("Downloading {} failed: {}", path, status)),
+        }
+    }
+
+    /// PUT only if nothing exists at `path`; `None` when something already does
+    async fn put_new(&self, path: &str, body: Vec<u8>) -> Result<Option<Option<String>>> {
+        let response = self
+            .request(Method::PUT, path)?
+            .header("If-None-Match", "*")
+            .body(body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to upload {}", path))?;
+
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(None),
+            status if status.is_success() => Ok(Some(response_etag(&response))),
+            status => Err(anyhow::anyhow!("Uploading {} failed: {}", path, status)),
+        }
+    }
+
+    async fn mkcol(&self, path: &str) -> Result<()> {
+        let response = self
+            .request(Method::from_bytes(b"MKCOL")?, path)?
+            .send()
+            .await
+            .with_context(|| format!("Failed to create {}", path))?;
+
+        match response.status() {
+            // 405 means the collection is already there
+            StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            status if status.is_success() => Ok(()),
+            status => Err(anyhow::anyhow!("Creating {} failed: {}", path, status)),
+        }
+    }
+
+    /// Members of a collection as (name, is collection, etag)
+    async fn propfind(&self, path: &str, depth: u8) -> Result<Vec<(String, bool, Option<String>)>> {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getetag/></d:prop></d:propfind>"#;
+
+        let response = self
+            .request(Method::from_bytes(b"PROPFIND")?, path)?
+            .header("Depth", depth.to_string())
+            .header("Content-Type", "application/xml")
+            .body(body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to list {}", path))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(vec![]),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(anyhow::anyhow!(
+                    "The WebDAV server rejected the credentials"
+                ))
+            }
+            status if !status.is_success() => {
+                return Err(anyhow::anyhow!("Listing {} failed: {}", path, status))
+            }
+            _ => {}
+        }
+
+        let xml = response.text().await?;
+        let own_path = self
+            .base
+            .join(path)?
+            .path()
+            .trim_end_matches('/')
+            .to_string();
+
+        let mut members = Vec::new();
+        for response in xml_elements(&xml, "response") {
+            let Some(href) = xml_elements(response, "href").first().map(|h| unescape(h)) else {
+                continue;
+            };
+            // The href may be absolute; compare paths only
+            let href_path = reqwest::Url::parse(&href)
+                .map(|url| url.path().to_string())
+                .unwrap_or(href);
+            let href_path = href_path.trim_end_matches('/');
+            if href_path == own_path {
+                continue;
+            }
+
+            let name = href_path.rsplit('/').next().unwrap_or_default().to_string();
+            let is_collection = xml_elements(response, "resourcetype")
+                .first()
+                .is_some_and(|t| !xml_elements(t, "collection").is_empty());
+            let etag = xml_elements(response, "getetag")
+                .first()
+                .map(|e| unescape(e.trim()))
+                .filter(|e| !e.is_empty());
+            members.push((name, is_collection, etag));
+        }
+
+        Ok(members)
+    }
+
+    async fn list_collections(&self, path: &str) -> Result<Vec<(String, Option<String>)>> {
+        Ok(self
+            .propfind(path, 1)
+            .await?
+            .into_iter()
+            .filter(|(_, is_collection, _)| *is_collection)
+            .map(|(name, _, etag)| (name, etag))
+            .collect())
+    }
+
+    async fn list_files(&self, path: &str) -> Result<Vec<String>> {
+        Ok(self
+            .propfind(path, 1)
+            .await?
+            .into_iter()
+            .filter(|(_, is_collection, _)| !*is_collection)
+            .map(|(name, _, _)| name)
+            .collect())
+    }
+}
+
+/// Relative paths resolve inside a collection only when its URL ends in a slash
+fn collection_url(url: &str) -> String {
+    if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    }
+}
+
+fn response_etag(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get("ETag")
+        .and_then(|etag| etag.to_str().ok())
+        .map(|etag| etag.to_string())
+}
+
+/// Contents of every `<prefix:name>` element, whatever namespace prefix the server uses
+fn xml_elements<'x>(xml: &'x str, name: &str) -> Vec<&'x str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let tag_end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[..tag_end];
+        if tag.starts_with('/') || tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        let full_name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        let local_name = full_name.rsplit(':').next().unwrap_or_default();
+        if local_name != name {
+            continue;
+        }
+
+        // <d:collection/> has no contents
+        if tag.ends_with('/') {
+            found.push("");
+            continue;
+        }
+
+        let contents = &rest[tag_end + 1..];
+        let closing = format!("</{}>", full_name);
+        if let Some(end) = contents.find(&closing) {
+            found.push(&contents[..end]);
+            rest = &contents[end + closing.len()..];
+        }
+    }
+
+    found
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockServer, Request, Response};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// A WebDAV server backed by a temporary directory
+    struct DavServer {
+        server: MockServer,
+        root: PathBuf,
+        /// PUTs accepted before the server starts failing them
+        puts_allowed: Arc<AtomicUsize>,
+    }
+
+    impl DavServer {
+        fn start() -> Self {
+            let root = temp_dir("server");
+            std::fs::create_dir_all(root.join("journal")).unwrap();
+            let puts_allowed = Arc::new(AtomicUsize::new(usize::MAX));
+
+            let (server_root, allowed) = (root.clone(), puts_allowed.clone());
+            let server = MockServer::start(move |request| reply(&server_root, request, &allowed));
+
+            DavServer {
+                server,
+                root,
+                puts_allowed,
+            }
+        }
+
+        fn credentials(&self, passphrase: &str) -> WebDavCredentials {
+            WebDavCredentials {
+                url: format!("{}/journal", self.server.url),
+                username: "user".to_string(),
+                password: "secret".to_string(),
+                passphrase: passphrase.to_string(),
+            }
+        }
+
+        fn take_requests(&self) -> Vec<Request> {
+            self.server.take_requests()
+        }
+
+        /// Put a manifest with a cheap key derivation on the server, so tests
+        /// don't spend seconds in PBKDF2
+        async fn seed_manifest(&self, passphrase: &str) {
+            let salt = vec![7u8; 16];
+            let key = derive_key(passphrase.to_string(), salt.clone(), 1000)
+                .await
+                .unwrap();
+            let manifest = Manifest {
+                version: 1,
+                iterations: 1000,
+                salt: base64::engine::general_purpose::STANDARD.encode(salt),
+                check: base64::engine::general_purpose::STANDARD
+                    .encode(seal(&key, MANIFEST_FILE, PASSPHRASE_CHECK).unwrap()),
+            };
+            std::fs::write(
+                self.root.join("journal").join(MANIFEST_FILE),
+                serde_json::to_vec(&manifest).unwrap(),
+            )
+            .unwrap();
+        }
+    }
+
+    fn reply(root: &Path, request: &Request, puts_allowed: &AtomicUsize) -> Response {
+        let path = &request.path;
+        let if_none_match = request.header("if-none-match").map(str::to_string);
+        let file = root.join(path.trim_matches('/'));
+        let with_etag =
+            |status, file: &Path| Response::new(status).with_header("ETag", &etag_of(file));
+
+        match request.method.as_str() {
+            "MKCOL" if file.exists() => Response::new(405),
+            "MKCOL" => {
+                std::fs::create_dir_all(&file).unwrap();
+                Response::new(201)
+            }
+            "PUT" if if_none_match.as_deref() == Some("*") && file.exists() => Response::new(412),
+            "PUT" => {
+                let allowed = puts_allowed.load(Ordering::SeqCst);
+                if allowed == 0 {
+                    Response::new(500)
+                } else {
+                    puts_allowed.store(allowed.saturating_sub(1), Ordering::SeqCst);
+                    std::fs::write(&file, &request.body).unwrap();
+                    with_etag(201, &file)
+                }
+            }
+            "GET" if !file.is_file() => Response::new(404),
+            "GET" if if_none_match == Some(etag_of(&file)) => Response::new(304),
+            "GET" => with_etag(200, &file).with_body(std::fs::read(&file).unwrap()),
+            "PROPFIND" if !file.exists() => Response::new(404),
+            "PROPFIND" => {
+                let mut members = vec![(path.clone(), file.clone())];
+                if file.is_dir() {
+                    for member in std::fs::read_dir(&file).unwrap() {
+                        let member = member.unwrap();
+                        let name = member.file_name().to_string_lossy().to_string();
+                        members.push((
+                            format!("{}/{}", path.trim_end_matches('/'), name),
+                            member.path(),
+                        ));
+                    }
+                }
+
+                let mut xml =
+                    String::from(r#"<?xml version="1.0"?><D:multistatus xmlns:D="DAV:">"#);
+                for (href, member) in members {
+                    let kind = if member.is_dir() {
+                        "<D:resourcetype><D:collection/></D:resourcetype>"
+                    } else {
+                        "<D:resourcetype/>"
+                    };
+                    xml.push_str(&format!(
+                        "<D:response><D:href>{}</D:href><D:propstat><D:prop>{}<D:getetag>{}</D:getetag></D:prop></D:propstat></D:response>",
+                        href,
+                        kind,
+                        etag_of(&member).replace('"', "&quot;")
+                    ));
+                }
+                xml.push_str("</D:multistatus>");
+                Response::new(207).with_body(xml)
+            }
+            _ => Response::new(405),
+        }
+    }
+
+    /// Changes whenever anything in a file or collection changes
+    fn etag_of(path: &Path) -> String {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        if path.is_dir() {
+            let mut members: Vec<PathBuf> = std::fs::read_dir(path)
+                .unwrap()
+                .map(|member| member.unwrap().path())
+                .collect();
+            members.sort();
+            for member in members {
+                member.hash(&mut hasher);
+                etag_of(&member).hash(&mut hasher);
+            }
+        } else {
+            std::fs::read(path).unwrap().hash(&mut hasher);
+        }
+        format!("\"{:x}\"", hasher.finish())
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("webdav-{}-{}", name, Uuid::new_v4()))
+    }
+
+    async fn journal_with_entries(contents: &[String]) -> (Database, PathBuf) {
+        let dir = temp_dir("journal");
+        let database = Database::new(dir.join("journal.db")).await.unwrap();
+        for (i, content) in contents.iter().enumerate() {
+            let entry = serde_json::from_value(serde_json::json!({
+                "id": format!("entry-{}", i),
+                "title": "Entry",
+                "content": content,
+                "tags": [],
+                "mood": null,
+                "privacy": "private",
+                "source": null,
+                "source_id": null,
+                "source_url": null,
+                "metadata": null,
+                "created_at": Utc::now(),
+                "updated_at": Utc::now(),
+            }))
+            .unwrap();
+            database.create_entry(&entry).await.unwrap();
+        }
+        (database, dir.join("webdav"))
+    }
+
+    #[tokio::test]
+    async fn put_new_never_overwrites() {
+        let server = DavServer::start();
+        let client = WebDavClient::new(&server.credentials("passphrase")).unwrap();
+
+        assert!(client
+            .put_new("file", b"first".to_vec())
+            .await
+            .unwrap()
+            .is_some());
+        assert!(client
+            .put_new("file", b"second".to_vec())
+            .await
+            .unwrap()
+            .is_none());
+
+        let puts: Vec<Request> = server
+            .take_requests()
+            .into_iter()
+            .filter(|request| request.method == "PUT")
+            .collect();
+        assert_eq!(puts.len(), 2);
+        assert!(puts
+            .iter()
+            .all(|request| request.header("if-none-match") == Some("*")));
+        assert_eq!(
+            std::fs::read(server.root.join("journal/file")).unwrap(),
+            b"first"
+        );
+    }
+
+    #[tokio::test]
+    async fn unchanged_peers_are_not_downloaded_again() {
+        let server = DavServer::start();
+        server.seed_manifest("correct horse").await;
+        let credentials = server.credentials("correct horse");
+
+        let (a, a_mirror) = journal_with_entries(&["hello".to_string()]).await;
+        WebDavSync::new(&a, &credentials, a_mirror)
+            .unwrap()
+            .sync()
+            .await
+            .unwrap();
+
+        let (b, b_mirror) = journal_with_entries(&[]).await;
+        WebDavSync::new(&b, &credentials, b_mirror.clone())
+            .unwrap()
+            .sync()
+            .await
+            .unwrap();
+        assert_eq!(b.list_all_entries().await.unwrap().len(), 1);
+        server.take_requests();
+
+        WebDavSync::new(&b, &credentials, b_mirror)
+            .unwrap()
+            .sync()
+            .await
+            .unwrap();
+        let requests = server.take_requests();
+        let chunk_gets = requests
+            .iter()
+            .filter(|request| request.method == "GET" && request.path.ends_with(CHUNK_EXTENSION))
+            .count();
+        assert_eq!(chunk_gets, 0);
+        // The manifest is only checked against its ETag
+        assert!(requests.iter().any(|request| {
+            request.method == "GET"
+                && request.path.ends_with(MANIFEST_FILE)
+                && request.header("if-none-match").is_some()
+        }));
+    }
+
+    #[tokio::test]
+    async fn interrupted_upload_resumes_at_the_next_chunk() {
+        let server = DavServer::start();
+        server.seed_manifest("correct horse").await;
+        let credentials = server.credentials("correct horse");
+
+        // Two entries too big to share a chunk
+        let big = "x".repeat(MAX_CHUNK_BYTES / 2 + 1);
+        let (a, a_mirror) = journal_with_entries(&[big.clone(), big]).await;
+
+        server.puts_allowed.store(1, Ordering::SeqCst);
+        assert!(WebDavSync::new(&a, &credentials, a_mirror.clone())
+            .unwrap()
+            .sync()
+            .await
+            .is_err());
+        let state = load_state(&a_mirror).await.unwrap();
+        assert_eq!(state.next_chunk, 1);
+        assert!(state.uploaded_offset > 0);
+        assert!(state.last_error.is_some());
+        server.take_requests();
+
+        server.puts_allowed.store(usize::MAX, Ordering::SeqCst);
+        WebDavSync::new(&a, &credentials, a_mirror.clone())
+            .unwrap()
+            .sync()
+            .await
+            .unwrap();
+        let puts: Vec<String> = server
+            .take_requests()
+            .into_iter()
+            .filter(|request| request.method == "PUT")
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(puts.len(), 1);
+        assert!(puts[0].ends_with(&format!("{:010}.{}", 1, CHUNK_EXTENSION)));
+
+        let status = status(&a_mirror, Some(credentials.account()))
+            .await
+            .unwrap();
+        assert_eq!(status.pending_upload_bytes, 0);
+        assert!(status.last_error.is_none());
+
+        let (b, b_mirror) = journal_with_entries(&[]).await;
+        WebDavSync::new(&b, &credentials, b_mirror)
+            .unwrap()
+            .sync()
+            .await
+            .unwrap();
+        assert_eq!(b.list_all_entries().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn wrong_passphrase_is_rejected() {
+        let server = DavServer::start();
+        server.seed_manifest("correct horse").await;
+
+        let (a, a_mirror) = journal_with_entries(&["hello".to_string()]).await;
+        WebDavSync::new(&a, &server.credentials("correct horse"), a_mirror)
+            .unwrap()
+            .sync()
+            .await
+            .unwrap();
+
+        let (b, b_mirror) = journal_with_entries(&[]).await;
+        let error = WebDavSync::new(&b, &server.credentials("battery staple"), b_mirror)
+            .unwrap()
+            .sync()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("passphrase does not match"));
+        assert!(b.list_all_entries().await.unwrap().is_empty());
+    }
+
+    /// Keeps secrets in memory so tests don't touch the real keychain
+    struct MemoryStore;
+    struct MemoryCredential(String);
+
+    static SECRETS: Mutex<Option<HashMap<String, Vec<u8>>>> = Mutex::new(None);
+
+    impl keyring::credential::CredentialApi for MemoryCredential {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            SECRETS
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(self.0.clone(), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            SECRETS
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .get(&self.0)
+                .cloned()
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            SECRETS
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .remove(&self.0)
+                .map(|_| ())
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    impl keyring::credential::CredentialBuilderApi for MemoryStore {
+        fn build(
+            &self,
+            _target: Option<&str>,
+            service: &str,
+            user: &str,
+        ) -> keyring::Result<Box<keyring::credential::Credential>> {
+            Ok(Box::new(MemoryCredential(format!("{}/{}", service, user))))
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    #[tokio::test]
+    async fn secrets_stay_out_of_the_credentials_file() {
+        keyring::set_default_credential_builder(Box::new(MemoryStore));
+        let path = temp_dir("credentials").join("journal.json");
+        let journal_id = Uuid::new_v4().to_string();
+        let credentials = WebDavCredentials {
+            url: "https://dav.example.com/journal".to_string(),
+            username: "user".to_string(),
+            password: "hunter22".to_string(),
+            passphrase: "correct horse".to_string(),
+        };
+
+        save_credentials(&path, &journal_id, &credentials)
+            .await
+            .unwrap();
+        let file = std::fs::read_to_string(&path).unwrap();
+        assert!(!file.contains("hunter22") && !file.contains("correct horse"));
+
+        let loaded = load_credentials(&path, &journal_id).await.unwrap().unwrap();
+        assert_eq!(loaded.password, "hunter22");
+        assert_eq!(loaded.passphrase, "correct horse");
+        assert_eq!(load_account(&path).await.unwrap().unwrap().username, "user");
+
+        delete_credentials(&path, &journal_id).await.unwrap();
+        assert!(load_credentials(&path, &journal_id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(read_secrets(&journal_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn old_credentials_files_move_their_secrets_to_the_keychain() {
+        keyring::set_default_credential_builder(Box::new(MemoryStore));
+        let path = temp_dir("credentials").join("journal.json");
+        let journal_id = Uuid::new_v4().to_string();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            r#"{"url":"https://dav.example.com/journal","username":"user","password":"hunter22","passphrase":"correct horse"}"#,
+        )
+        .unwrap();
+
+        let loaded = load_credentials(&path, &journal_id).await.unwrap().unwrap();
+        assert_eq!(loaded.password, "hunter22");
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter22"));
+        assert_eq!(
+            read_secrets(&journal_id).await.unwrap().unwrap().passphrase,
+            "correct horse"
+        );
+    }
+}
@@ -88,6 +88,8 @@ const NotificationPanel: React.FC = () => {
         return <Brain className="w-5 h-5 text-purple-500" />;
       case 'sync_complete':
         return <RefreshCw className="w-5 h-5 text-green-500" />;
+      case 'reminder':
+        return <Clock className="w-5 h-5 text-blue-500" />;
       default:
         return <Bell className="w-5 h-5 text-gray-500" />;
     }
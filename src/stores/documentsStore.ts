@@ -58,8 +58,7 @@ export const useDocumentsStore = create<DocumentsState & DocumentsActions>()(
     createDocument: async (documentData) => {
       if (isTauri) {
         try {
-          // For now, use journal_entry table as documents are similar
-          // In the future, we might want a separate documents table
+          // Documents are journal entries filed in the built-in Documents collection
           const newEntry = await invoke<any>('create_journal_entry', {
             title: documentData.title,
             content: documentData.content,
@@ -70,6 +69,7 @@ export const useDocumentsStore = create<DocumentsState & DocumentsActions>()(
             sourceId: null,
             sourceUrl: null,
             metadata: { type: 'document', ...documentData.metadata },
+            collectionIds: ['documents'],
           });
           
           // Convert entry to Document format
@@ -221,19 +221,13 @@ export const useDocumentsStore = create<DocumentsState & DocumentsActions>()(
       
       if (isTauri) {
         try {
-          // Load all entries and filter for documents
-          const entries = await invoke<any[]>('list_journal_entries', {
-            limit: null,
-            offset: null,
+          // Load the Documents collection in its saved order
+          const entries = await invoke<any[]>('list_collection_entries', {
+            collectionId: 'documents',
+            filter: null,
           });
           
-          // Filter for documents (entries with metadata.type === 'document')
           const documents = entries
-            .filter((entry: any) => {
-              // Check if metadata exists and has type === 'document'
-              const metadata = entry.metadata;
-              return metadata && typeof metadata === 'object' && metadata.type === 'document';
-            })
             .map((entry: any) => ({
               id: entry.id,
               title: entry.title,
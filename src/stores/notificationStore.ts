@@ -7,11 +7,32 @@
  * - AI responses
  * - System status updates
  * - Import/export operations
+ * - Journaling reminders from the backend scheduler
  */
 
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
-import { Notification, NotificationSettings, NotificationType } from '../types/notification';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import {
+  Notification,
+  NotificationSettings,
+  NotificationType,
+  ReminderNotification
+} from '../types/notification';
+
+const REMINDERS_DUE = 'reminders://due';
+
+type ReminderAction = 'opened' | 'wrote' | 'snoozed' | 'dismissed';
+
+// Tell the scheduler what happened to a reminder so it can stop nagging
+const recordReminderAction = (notification: Notification | undefined, action: ReminderAction) => {
+  if (notification?.type !== 'reminder' || notification.data?.historyId === undefined) {
+    return;
+  }
+  invoke('record_reminder_action', { historyId: notification.data.historyId, action })
+    .catch(error => console.error('Failed to record reminder action:', error));
+};
 
 interface NotificationStore {
   // State
@@ -42,6 +63,7 @@ interface NotificationStore {
   notifyJournalImport: (count: number, source: 'mastodon' | 'facebook') => void;
   notifyAIResponse: (type: 'echo' | 'companion', prompt: string) => void;
   notifySync: (type: 'success' | 'error', details?: string) => void;
+  notifyReminder: (reminder: ReminderNotification) => void;
   notifyGeneral: (type: NotificationType, title: string, message: string, persistent?: boolean) => void;
 }
 
@@ -91,6 +113,11 @@ export const useNotificationStore = create<NotificationStore>()(
       },
 
       markAsRead: (notificationId) => {
+        const notification = get().notifications.find(n => n.id === notificationId);
+        if (notification && !notification.read) {
+          recordReminderAction(notification, 'opened');
+        }
+
         set((state) => {
           const notifications = state.notifications.map(n => 
             n.id === notificationId ? { ...n, read: true } : n
@@ -109,6 +136,11 @@ export const useNotificationStore = create<NotificationStore>()(
       },
 
       removeNotification: (notificationId) => {
+        const notification = get().notifications.find(n => n.id === notificationId);
+        if (notification && !notification.read) {
+          recordReminderAction(notification, 'dismissed');
+        }
+
         set((state) => {
           const notifications = state.notifications.filter(n => n.id !== notificationId);
           const unreadCount = notifications.filter(n => !n.read).length;
@@ -213,6 +245,21 @@ export const useNotificationStore = create<NotificationStore>()(
         });
       },
 
+      notifyReminder: (reminder) => {
+        get().addNotification({
+          type: 'reminder',
+          title: reminder.title,
+          message: reminder.message,
+          persistent: false,
+          data: {
+            historyId: reminder.history_id,
+            reminderId: reminder.reminder_id,
+            scheduledFor: reminder.scheduled_for,
+            snoozed: reminder.snoozed
+          }
+        });
+      },
+
       notifyGeneral: (type, title, message, persistent = false) => {
         get().addNotification({
           type,
@@ -242,4 +289,10 @@ export const useNotificationStore = create<NotificationStore>()(
   )
 );
 
+// Reminders are shown in the notification panel; the backend fires them
+// whether or not a window is looking
+listen<ReminderNotification>(REMINDERS_DUE, (event) => {
+  useNotificationStore.getState().notifyReminder(event.payload);
+}).catch(error => console.error('Failed to listen for reminders:', error));
+
 export default useNotificationStore;
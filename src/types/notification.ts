@@ -16,7 +16,8 @@ export type NotificationType =
   | 'journal_entry_imported'
   | 'ai_response_ready'
   | 'sync_complete'
-  | 'sync_failed';
+  | 'sync_failed'
+  | 'reminder';
 
 export interface Notification {
   id: string;
@@ -30,6 +31,16 @@ export interface Notification {
   data?: Record<string, any>; // Additional metadata
 }
 
+/** Payload of the reminders://due event */
+export interface ReminderNotification {
+  history_id: number; // pass back to record_reminder_action
+  reminder_id: string;
+  title: string;
+  message: string;
+  scheduled_for: string;
+  snoozed: boolean;
+}
+
 export interface NotificationAction {
   id: string;
   label: string;